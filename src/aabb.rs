@@ -0,0 +1,111 @@
+use crate::{interval::Interval, ray::Ray, vec3::Point3};
+
+// axis-aligned bounding box
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Aabb {
+    // box with `a` and `b` as opposite corners
+    pub fn from_points(a: &Point3, b: &Point3) -> Self {
+        Self {
+            x: Interval::new(a.x().min(b.x()), a.x().max(b.x())),
+            y: Interval::new(a.y().min(b.y()), a.y().max(b.y())),
+            z: Interval::new(a.z().min(b.z()), a.z().max(b.z())),
+        }
+    }
+
+    // smallest box enclosing both `a` and `b`
+    pub fn from_boxes(a: &Aabb, b: &Aabb) -> Self {
+        let join = |a: &Interval, b: &Interval| Interval::new(a.min.min(b.min), a.max.max(b.max));
+        Self {
+            x: join(&a.x, &b.x),
+            y: join(&a.y, &b.y),
+            z: join(&a.z, &b.z),
+        }
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        0.5 * (self.min() + self.max())
+    }
+
+    pub fn axis_interval(&self, n: usize) -> &Interval {
+        match n {
+            1 => &self.y,
+            2 => &self.z,
+            _ => &self.x,
+        }
+    }
+
+    pub fn min(&self) -> Point3 {
+        Point3::new(self.x.min, self.y.min, self.z.min)
+    }
+
+    pub fn max(&self) -> Point3 {
+        Point3::new(self.x.max, self.y.max, self.z.max)
+    }
+
+    // returns the part of ray_t for which the ray is inside the box
+    pub fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<Interval> {
+        let mut t = *ray_t;
+
+        for axis in 0..3 {
+            let ax = self.axis_interval(axis);
+            let adinv = 1.0 / r.direction()[axis];
+
+            let t0 = (ax.min - r.origin()[axis]) * adinv;
+            let t1 = (ax.max - r.origin()[axis]) * adinv;
+            let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+
+            t.min = t.min.max(t0);
+            t.max = t.max.min(t1);
+            if t.max <= t.min {
+                return None;
+            }
+        }
+
+        Some(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::Vec3;
+
+    fn unit_box() -> Aabb {
+        Aabb::from_points(&Point3::new(1.0, 1.0, 1.0), &Point3::new(0.0, 0.0, 0.0))
+    }
+
+    #[test]
+    fn hit_clips_the_ray_interval() {
+        let b = unit_box();
+        let r = Ray::new(Point3::new(0.5, 0.5, -1.0), Vec3::new(0.0, 0.0, 2.0), 0.0);
+        let span = b.hit(&r, &Interval::new(0.0, f64::INFINITY)).unwrap();
+        assert_eq!((span.min, span.max), (0.5, 1.0));
+
+        let span = b.hit(&r, &Interval::new(0.75, 0.8)).unwrap();
+        assert_eq!((span.min, span.max), (0.75, 0.8));
+        assert!(b.hit(&r, &Interval::new(0.0, 0.5)).is_none());
+
+        // backwards and beside the box
+        let away = Ray::new(Point3::new(0.5, 0.5, -1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(b.hit(&away, &Interval::new(0.0, f64::INFINITY)).is_none());
+        let beside = Ray::new(Point3::new(1.5, 0.5, -1.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        assert!(b.hit(&beside, &Interval::new(0.0, f64::INFINITY)).is_none());
+    }
+
+    #[test]
+    fn union_and_centroid() {
+        let b = Aabb::from_boxes(
+            &unit_box(),
+            &Aabb::from_points(&Point3::new(-1.0, 2.0, 0.5), &Point3::new(-2.0, 3.0, 0.5)),
+        );
+        assert_eq!(b.min(), Point3::new(-2.0, 0.0, 0.0));
+        assert_eq!(b.max(), Point3::new(1.0, 3.0, 1.0));
+        assert_eq!(b.centroid(), Point3::new(-0.5, 1.5, 0.5));
+    }
+}
@@ -0,0 +1,166 @@
+use std::f64::consts::PI;
+
+use crate::{
+    image::Image,
+    util::{self, alias_table::AliasTable, rand::PCG32RNG},
+    vec3::Vec3,
+};
+
+enum Shape {
+    Disk,
+    // regular polygon inscribed in the unit circle
+    Polygon {
+        blades: u32,
+        rotation: f64,
+    },
+    // pixels picked in proportion to their value
+    Image {
+        width: usize,
+        height: usize,
+        pixels: AliasTable,
+    },
+}
+
+// shape of the lens opening, which out-of-focus highlights (bokeh) take.
+// points are sampled in lens coordinates, the unit disk scaled by the
+// defocus radius
+pub struct Aperture {
+    shape: Shape,
+}
+
+impl Aperture {
+    pub fn disk() -> Self {
+        Self { shape: Shape::Disk }
+    }
+
+    // diaphragm with `blades` straight blades, the first corner `rotation`
+    // degrees counterclockwise from the camera's horizontal
+    pub fn polygon(blades: u32, rotation: f64) -> Self {
+        Self {
+            shape: Shape::Polygon {
+                blades: blades.max(3),
+                rotation: util::deg_to_rad(rotation),
+            },
+        }
+    }
+
+    // opening given by a grayscale mask stretched over the square around the
+    // unit disk, with pixel values read as linear transmittance. None if the
+    // image is black
+    pub fn image(image: &Image) -> Option<Self> {
+        let mut weights = Vec::with_capacity(image.width() * image.height());
+        for y in 0..image.height() {
+            for x in 0..image.width() {
+                let c = image.pixel(x, y);
+                weights.push((c.x() + c.y() + c.z()) / 3.0);
+            }
+        }
+        Some(Self {
+            shape: Shape::Image {
+                width: image.width(),
+                height: image.height(),
+                pixels: AliasTable::new(&weights)?,
+            },
+        })
+    }
+
+    // uniformly distributed point on the opening, in the xy plane
+    pub fn sample(&self, rng: &mut PCG32RNG) -> Vec3 {
+        match &self.shape {
+            Shape::Disk => Vec3::random_in_unit_disk(rng),
+            Shape::Polygon { blades, rotation } => {
+                // the polygon is made of equal triangles around the center
+                let k = (rng.random_f64() * *blades as f64) as u32 % blades;
+                let step = 2.0 * PI / *blades as f64;
+                let a0 = rotation + k as f64 * step;
+                let (a, b) = (
+                    Vec3::new(a0.cos(), a0.sin(), 0.0),
+                    Vec3::new((a0 + step).cos(), (a0 + step).sin(), 0.0),
+                );
+
+                // uniform point in the triangle (0, a, b)
+                let (mut s, mut t) = (rng.random_f64(), rng.random_f64());
+                if s + t > 1.0 {
+                    (s, t) = (1.0 - s, 1.0 - t);
+                }
+                s * a + t * b
+            }
+            Shape::Image {
+                width,
+                height,
+                pixels,
+            } => {
+                let (i, _) = pixels.sample(rng);
+                let x = (i % width) as f64 + rng.random_f64();
+                let y = (i / width) as f64 + rng.random_f64();
+                Vec3::new(
+                    2.0 * x / *width as f64 - 1.0,
+                    1.0 - 2.0 * y / *height as f64,
+                    0.0,
+                )
+            }
+        }
+    }
+}
+
+impl Default for Aperture {
+    fn default() -> Self {
+        Self::disk()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn polygon_samples_stay_inside_the_blades() {
+        let aperture = Aperture::polygon(6, 15.0);
+        let mut rng = PCG32RNG::default();
+        // the hexagon's edges are cos(30 degrees) from the center
+        let apothem = (PI / 6.0).cos();
+        let mut quadrants = [0usize; 4];
+        for _ in 0..10000 {
+            let p = aperture.sample(&mut rng);
+            assert_eq!(p.z(), 0.0);
+            let angle = p.y().atan2(p.x()) - util::deg_to_rad(15.0);
+            let to_edge = (angle.rem_euclid(PI / 3.0) - PI / 6.0).cos();
+            assert!(p.len() * to_edge <= apothem + 1e-12);
+            quadrants[usize::from(p.x() < 0.0) + 2 * usize::from(p.y() < 0.0)] += 1;
+        }
+        for q in quadrants {
+            assert!((q as f64 / 10000.0 - 0.25).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn image_samples_follow_the_mask() {
+        // bright top left, dim bottom right, the rest black
+        let black = Color::default();
+        let image = Image::new(
+            2,
+            2,
+            vec![
+                Color::new(0.75, 0.75, 0.75),
+                black,
+                black,
+                Color::new(0.25, 0.25, 0.25),
+            ],
+        );
+        let aperture = Aperture::image(&image).unwrap();
+        let mut rng = PCG32RNG::default();
+        let n = 10000;
+        let mut top_left = 0;
+        for _ in 0..n {
+            let p = aperture.sample(&mut rng);
+            assert!(p.x().abs() <= 1.0 && p.y().abs() <= 1.0);
+            // never the black pixels
+            assert!((p.x() < 0.0) == (p.y() > 0.0));
+            top_left += usize::from(p.x() < 0.0);
+        }
+        assert!((top_left as f64 / n as f64 - 0.75).abs() < 0.02);
+
+        assert!(Aperture::image(&Image::new(1, 1, vec![black])).is_none());
+    }
+}
@@ -1,14 +1,74 @@
-use std::io::{self, Write, stdout};
+use std::{
+    f64::consts::PI,
+    io::{self, Write, stdout},
+    rc::Rc,
+};
 
 use crate::{
+    aperture::Aperture,
     color::{Color, write_color},
-    hittable::Hittable,
+    hittable::{HitRecord, Hittable},
     interval::Interval,
+    lens::LensSystem,
+    light::Light,
+    mat4::Mat4,
+    phase::PhaseFunction,
     ray::Ray,
+    sky::{Gradient, Sky},
+    spectrum::{self, Wavelengths},
     util::{self, rand::PCG32RNG},
     vec3::{Point3, Vec3},
 };
 
+// how positions on the film map to camera rays. every projection uses the
+// lookfrom / lookat / vup frame, with the view direction at the image center
+#[derive(Debug, Clone, Copy)]
+pub enum Projection {
+    // thin lens perspective, using vfov, defocus_angle and focus_dist, or
+    // the camera's lens system if it has one
+    Perspective,
+    // parallel rays along the view direction, over a view `height` world
+    // units tall
+    Orthographic { height: f64 },
+    // equidistant fisheye: the angle off the view direction grows linearly
+    // with the distance from the image center, up to `fov` / 2 degrees at the
+    // edge of the image circle inscribed in the frame
+    Fisheye { fov: f64 },
+    // full 360 by 180 degree latitude-longitude panorama, best rendered at a
+    // 2:1 aspect ratio
+    Equirectangular,
+}
+
+// how the two eyes of a stereo camera are placed
+#[derive(Debug, Clone, Copy)]
+pub enum StereoMode {
+    // omni-directional stereo for the equirectangular projection: every
+    // direction is seen from its own point on a circle of diameter ipd, so
+    // the eyes are correctly separated whichever way the viewer looks. with
+    // other projections it behaves like `Parallel`
+    OmniDirectional,
+    // eyes offset sideways with parallel view directions, converging at
+    // infinity
+    Parallel,
+    // eyes offset sideways and turned inwards to converge at focus_dist
+    ToeIn,
+}
+
+// where the two eye images are put in the output, left eye first
+#[derive(Debug, Clone, Copy)]
+pub enum StereoLayout {
+    TopBottom,
+    SideBySide,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Stereo {
+    mode: StereoMode,
+    // interpupillary distance, in world units
+    ipd: f64,
+    layout: StereoLayout,
+}
+
 #[allow(dead_code)]
 pub struct Camera {
     aspect_ratio: f64,
@@ -23,6 +83,31 @@ pub struct Camera {
 
     defocus_angle: f64,
     focus_dist: f64,
+    aperture: Aperture,
+    // normal of a tilted plane of focus, None when it is parallel to the film
+    focus_normal: Option<Vec3>,
+    // lens shift, in image widths to the right and heights up
+    shift: (f64, f64),
+    // traced instead of the thin lens, with the film at lookfrom
+    lens: Option<LensSystem>,
+
+    // rays are spread uniformly over [shutter_open, shutter_close)
+    shutter_open: f64,
+    shutter_close: f64,
+
+    // trace hero wavelength samples instead of rgb
+    spectral: bool,
+
+    // radiance of rays leaving the scene
+    sky: Rc<dyn Sky>,
+
+    // factor applied to the radiance before it is written out
+    exposure: f64,
+
+    projection: Projection,
+
+    // renders a left and right eye image side by side or stacked
+    stereo: Option<Stereo>,
 
     image_height: i32,
     pixel_samples_scale: f64,
@@ -109,65 +194,581 @@ impl Camera {
             focus_dist,
             defocus_disk_u,
             defocus_disk_v,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            spectral: false,
+            sky: Rc::new(Gradient),
+            exposure: 1.0,
+            projection: Projection::Perspective,
+            stereo: None,
+            aperture: Aperture::default(),
+            focus_normal: None,
+            shift: (0.0, 0.0),
+            lens: None,
+        }
+    }
+
+    pub fn with_shutter(mut self, open: f64, close: f64) -> Self {
+        self.shutter_open = open;
+        self.shutter_close = close;
+        self
+    }
+
+    pub fn with_spectral(mut self, spectral: bool) -> Self {
+        self.spectral = spectral;
+        self
+    }
+
+    pub fn with_sky(mut self, sky: Rc<dyn Sky>) -> Self {
+        self.sky = sky;
+        self
+    }
+
+    // brightens the image by `stops`, for scenes lit at photometric scale
+    // without daylight
+    pub fn with_exposure(mut self, stops: f64) -> Self {
+        self.exposure = stops.exp2();
+        self
+    }
+
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    // shape of the defocus blur, a disk of the defocus angle by default
+    pub fn with_aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    // tilts the plane of focus of the thin lens (scheimpflug), pivoting
+    // around its point on the view axis. positive `tilt` degrees make it
+    // recede towards the top of the image, like a lens tilted down to keep
+    // the ground sharp, and positive `swing` towards the right
+    pub fn with_tilt(mut self, tilt: f64, swing: f64) -> Self {
+        let n = Mat4::rotate(&self.u, -tilt).transform_vector(&self.w);
+        let n = Mat4::rotate(&self.v, swing).transform_vector(&n);
+        self.focus_normal = (tilt != 0.0 || swing != 0.0).then(|| n.unit_vector());
+        self
+    }
+
+    // moves the thin lens' view sideways without turning it, keeping
+    // verticals parallel, by fractions of the image width and height
+    pub fn with_shift(mut self, horizontal: f64, vertical: f64) -> Self {
+        self.shift = (horizontal, vertical);
+        self
+    }
+
+    // sets focus_dist so the first surface seen through the center of pixel
+    // (i, j) is in focus. the focus is kept if the ray leaves the scene or a
+    // lens system cannot focus on the hit. call it after the other options
+    // that change the view or the lens
+    pub fn focus_on_pixel(
+        mut self,
+        world: &dyn Hittable,
+        i: i32,
+        j: i32,
+        rng: &mut PCG32RNG,
+    ) -> Self {
+        if let Some(r) = self.pixel_center_ray(i, j, rng) {
+            self.focus_along(&r, world, rng);
         }
+        self
+    }
+
+    // like `focus_on_pixel`, with the ray cast from lookfrom towards lookat
+    pub fn focus_on_lookat(mut self, world: &dyn Hittable, rng: &mut PCG32RNG) -> Self {
+        let r = Ray::new(self.center, self.lookat - self.center, self.shutter_open);
+        self.focus_along(&r, world, rng);
+        self
+    }
+
+    // replaces the thin lens with a lens system focused at focus_dist. the
+    // field of view then follows from the lens and its film size, and vfov,
+    // defocus_angle and the aperture are unused. fails if the lens cannot
+    // focus at focus_dist
+    pub fn with_lens(mut self, mut lens: LensSystem) -> io::Result<Self> {
+        lens.focus(self.focus_dist)?;
+        self.lens = Some(lens);
+        Ok(self)
     }
 
-    pub fn render(&self, world: &dyn Hittable, rng: &mut PCG32RNG) -> io::Result<()> {
-        println!("P3\n{} {}\n255", self.image_width, self.image_height);
+    // the output holds both eyes, so it is twice as wide or tall as the
+    // image_width and aspect_ratio given to `new`
+    pub fn with_stereo(mut self, mode: StereoMode, ipd: f64, layout: StereoLayout) -> Self {
+        self.stereo = Some(Stereo { mode, ipd, layout });
+        self
+    }
+
+    pub fn render(
+        &self,
+        world: &dyn Hittable,
+        lights: &dyn Light,
+        rng: &mut PCG32RNG,
+    ) -> io::Result<()> {
+        let (eyes_x, eyes_y) = match self.stereo.map(|s| s.layout) {
+            None => (1, 1),
+            Some(StereoLayout::SideBySide) => (2, 1),
+            Some(StereoLayout::TopBottom) => (1, 2),
+        };
+        let output_width = self.image_width * eyes_x;
+        let output_height = self.image_height * eyes_y;
+        println!("P3\n{} {}\n255", output_width, output_height);
 
-        for j in 0..self.image_height {
-            eprint!("\rscanlines remaining: {:>04}", self.image_height - j);
+        for y in 0..output_height {
+            eprint!("\rscanlines remaining: {:>04}", output_height - y);
             let _ = std::io::stderr().flush();
-            for i in 0..self.image_width {
+            for x in 0..output_width {
+                let (i, j) = (x % self.image_width, y % self.image_height);
+                // signed offset of the eye along u, left eye first
+                let eye = match self.stereo {
+                    Some(stereo) if x >= self.image_width || y >= self.image_height => {
+                        0.5 * stereo.ipd
+                    }
+                    Some(stereo) => -0.5 * stereo.ipd,
+                    None => 0.0,
+                };
+
                 let mut pixel_color = Color::default();
-                let mut r: Ray;
                 for _ in 0..self.samples_per_pixel {
-                    r = self.get_ray(i, j, rng);
-                    pixel_color += self.ray_color(&r, self.max_depth, world, rng);
+                    let lambda = self.spectral.then(|| Wavelengths::sample(rng));
+                    // fisheye pixels outside the image circle and rays
+                    // blocked inside a lens system stay black
+                    let Some((r, weight)) = self.get_ray(i, j, rng) else {
+                        continue;
+                    };
+                    let r = self.eye_ray(r, eye);
+                    let r = r.with_wavelengths(lambda);
+                    let radiance = weight
+                        * self.ray_color(
+                            &r,
+                            self.max_depth,
+                            world,
+                            lights,
+                            &Medium::default(),
+                            None,
+                            rng,
+                        );
+                    pixel_color += match &lambda {
+                        Some(lambda) => spectrum::to_rgb(&radiance, lambda),
+                        None => radiance,
+                    };
                 }
 
-                write_color(&mut stdout(), &(pixel_color * self.pixel_samples_scale))?;
+                let pixel_color = pixel_color * self.pixel_samples_scale * self.exposure;
+                write_color(&mut stdout(), &pixel_color)?;
             }
         }
         eprintln!("\rDone.                        ");
         Ok(())
     }
 
-    fn ray_color(&self, r: &Ray, depth: i32, world: &dyn Hittable, rng: &mut PCG32RNG) -> Color {
+    // `medium` is the interior `r` travels through, empty outside of any
+    // absorbing or scattering dielectric. `scatter_pdf` is the density with
+    // which the previous surface picked `r`, None for camera rays and rays
+    // leaving delta lobes, also those picked inside layered materials, which
+    // see the light sources that next-event estimation could not sample
+    #[allow(clippy::too_many_arguments)]
+    fn ray_color(
+        &self,
+        r: &Ray,
+        depth: i32,
+        world: &dyn Hittable,
+        lights: &dyn Light,
+        medium: &Medium,
+        scatter_pdf: Option<f64>,
+        rng: &mut PCG32RNG,
+    ) -> Color {
         if depth <= 0 {
             return Color::default();
         }
 
-        // min 0.001: we want to ignore hits that are very close to the intersection
-        // point because of floating point imprecision
-        // SEE: shadow acne
-        if let Some(rec) = world.hit(r, &Interval::new(0.001, f64::INFINITY)) {
-            if let Some((attenuation, scattered)) = rec.mat.scatter(r, &rec, rng) {
-                return attenuation * self.ray_color(&scattered, depth - 1, world, rng);
+        // in spectral mode every rgb quantity is upsampled to the ray's
+        // wavelengths, and the returned radiance holds one value per wavelength
+        let lambda = r.wavelengths().copied();
+        let to_path = |c: Color| match &lambda {
+            Some(lambda) => spectrum::upsample(&c, lambda),
+            None => c,
+        };
+
+        // a scattering interior is crossed by a random walk first, and the
+        // path continues from the walk's last segment
+        let walk;
+        let (r, hit, transmittance, scatter_pdf) = match &medium.scattering {
+            Some((scattering, phase)) => {
+                let Some((weight, segment, rec)) = random_walk(
+                    r,
+                    world,
+                    &to_path(medium.absorption),
+                    &to_path(*scattering),
+                    phase.as_ref(),
+                    rng,
+                ) else {
+                    return Color::default();
+                };
+                // emitters are not sampled from inside media, so a walk that
+                // scattered counts their emission in full
+                let scatter_pdf = scatter_pdf.filter(|_| segment.origin() == r.origin());
+                walk = segment;
+                (&walk, Some(rec), weight, scatter_pdf)
             }
-            return Color::default();
+            None => {
+                // min 0.001: we want to ignore hits that are very close to the intersection
+                // point because of floating point imprecision
+                // SEE: shadow acne
+                let hit = world.hit(r, &Interval::new(0.001, f64::INFINITY), rng);
+                let transmittance = hit.as_ref().map_or(Color::new(1.0, 1.0, 1.0), |rec| {
+                    to_path(beer_lambert(
+                        &medium.absorption,
+                        rec.t * r.direction().len(),
+                    ))
+                });
+                (r, hit, transmittance, scatter_pdf)
+            }
+        };
+
+        if let Some(rec) = hit {
+            // emission found by a scattered ray is weighed against sampling
+            // the same emitter as a light. emitters missing from the lights
+            // have a light pdf of zero and always count in full
+            let mut direct = self.direct_light(r, &rec, world, lights, rng);
+            let emitted = rec.mat.emitted(r, &rec);
+            if !emitted.near_zero() {
+                let weight = scatter_pdf.map_or(1.0, |scatter_pdf| {
+                    mis_weights(lights.pdf(r.origin(), &rec.p), scatter_pdf).1
+                });
+                direct += weight * to_path(emitted);
+            }
+
+            if let Some(scatter) = rec.mat.scatter(r, &rec, rng) {
+                let mut attenuation = to_path(scatter.attenuation);
+                let mut next_lambda = lambda;
+                if let Some(lambda) = next_lambda.as_mut()
+                    && rec.mat.dispersive()
+                {
+                    attenuation = lambda.terminate_secondary(&attenuation);
+                }
+                let scattered = scatter.ray.with_wavelengths(next_lambda);
+                let next_pdf = (!scatter.specular).then(|| rec.mat.pdf(r, &rec, &scattered));
+
+                // crossing a medium boundary: entering through the front face
+                // switches to the material's interior, leaving returns to the
+                // medium around it
+                let crossed = Vec3::dot(scattered.direction(), &rec.geometric_normal) < 0.0;
+                let next_medium = match rec.mat.absorption() {
+                    Some(absorption) if crossed && rec.front_face => Medium {
+                        absorption,
+                        scattering: rec.mat.scattering(),
+                        outer: Some(Rc::new(medium.clone())),
+                    },
+                    Some(_) if crossed => medium.outer.as_deref().cloned().unwrap_or_default(),
+                    _ => medium.clone(),
+                };
+
+                return transmittance
+                    * (direct
+                        + attenuation
+                            * self.ray_color(
+                                &scattered,
+                                depth - 1,
+                                world,
+                                lights,
+                                &next_medium,
+                                next_pdf,
+                                rng,
+                            ));
+            }
+            return transmittance * direct;
         }
 
         let unit_direction = r.direction().unit_vector();
-        let a = 0.5 * (unit_direction.y() + 1.0);
-        util::lerp(Color::new(1.0, 1.0, 1.0), Color::new(0.5, 0.7, 1.0), a)
+        let mut radiance = self.sky.radiance(&unit_direction);
+        if scatter_pdf.is_none() {
+            radiance += self.sky.emitted(&unit_direction);
+        }
+        to_path(radiance)
+    }
+
+    // next-event estimation: light reaching `rec` straight from a sampled
+    // light and scattered along -r, in the path's color space
+    fn direct_light(
+        &self,
+        r: &Ray,
+        rec: &HitRecord,
+        world: &dyn Hittable,
+        lights: &dyn Light,
+        rng: &mut PCG32RNG,
+    ) -> Color {
+        let Some(sample) = lights.sample(&rec.p, rng) else {
+            return Color::default();
+        };
+        if sample.pdf <= 0.0 {
+            return Color::default();
+        }
+
+        let shadow_ray =
+            Ray::new(rec.p, sample.direction, r.time()).with_wavelengths(r.wavelengths().copied());
+
+        // specular materials evaluate to zero and need no shadow ray
+        let f = rec.mat.eval(r, rec, &shadow_ray);
+        if f.near_zero() {
+            return Color::default();
+        }
+
+        let visibility = world.transmittance(
+            &shadow_ray,
+            &Interval::new(0.001, sample.distance - 0.001),
+            rng,
+        );
+        if visibility <= 0.0 {
+            return Color::default();
+        }
+
+        // weighed against the material finding the same emitter, which only
+        // emitters with geometry can be
+        let light_pdf = if sample.distance.is_finite() {
+            lights.pdf(&rec.p, &(rec.p + sample.distance * sample.direction))
+        } else {
+            0.0
+        };
+        let (weight, _) = mis_weights(light_pdf, rec.mat.pdf(r, rec, &shadow_ray));
+
+        let radiance = match r.wavelengths() {
+            Some(lambda) => {
+                spectrum::upsample(&f, lambda) * spectrum::upsample(&sample.radiance, lambda)
+            }
+            None => f * sample.radiance,
+        };
+        weight * visibility / sample.pdf * radiance
     }
 
-    fn get_ray(&self, i: i32, j: i32, rng: &mut PCG32RNG) -> Ray {
+    // camera ray for a random point in pixel (i, j), and the weight of its
+    // radiance
+    fn get_ray(&self, i: i32, j: i32, rng: &mut PCG32RNG) -> Option<(Ray, f64)> {
+        let offset = self.sample_square(rng);
+        let (x, y) = (i as f64 + offset.x(), j as f64 + offset.y());
+        if !matches!(self.projection, Projection::Perspective) {
+            return self.get_projected_ray(x, y, rng).map(|r| (r, 1.0));
+        }
+        if let Some(lens) = &self.lens {
+            return self.get_lens_ray(lens, x, y, rng);
+        }
+
         // constructs a camera ray form the defocus disk and directed at a randomly sampled point
         // around the pixel location i, j
-        let offset = self.sample_square(rng);
-        let pixel_sample = self.pixel00_loc
-            + ((i as f64 + offset.x()) * self.pixel_delta_u)
-            + ((j as f64 + offset.y()) * self.pixel_delta_v);
+        let pixel_sample = self.focus_point(&self.pixel_sample(x, y));
         let ray_origin = if self.defocus_angle <= 0.0 {
             self.center
         } else {
             self.defocus_disk_sample(rng)
         };
         let ray_direction = pixel_sample - ray_origin;
+        let ray_time = rng.random_bounded_f64(self.shutter_open, self.shutter_close);
+
+        Some((Ray::new(ray_origin, ray_direction, ray_time), 1.0))
+    }
+
+    // point on the untilted plane of focus seen through film position (x, y)
+    // in pixels, from the top left corner
+    fn pixel_sample(&self, x: f64, y: f64) -> Point3 {
+        let shift = self.shift.0 * self.image_width as f64 * self.pixel_delta_u
+            - self.shift.1 * self.image_height as f64 * self.pixel_delta_v;
+        self.pixel00_loc + (x * self.pixel_delta_u) + (y * self.pixel_delta_v) + shift
+    }
+
+    // moves a point on the untilted plane of focus along its pinhole ray
+    // onto the tilted plane, where the ray's image is sharp
+    fn focus_point(&self, p: &Point3) -> Point3 {
+        let Some(n) = self.focus_normal else {
+            return *p;
+        };
+        let d = *p - self.center;
+        let denom = Vec3::dot(&d, &n);
+        let t = -self.focus_dist * Vec3::dot(&self.w, &n) / denom;
+        // rays parallel to the plane or meeting it behind the camera keep
+        // the untilted focus
+        if denom == 0.0 || t <= 0.0 {
+            return *p;
+        }
+        self.center + t * d
+    }
+
+    // ray through the center of pixel (i, j) without defocus, the chief ray
+    // for a lens system
+    fn pixel_center_ray(&self, i: i32, j: i32, rng: &mut PCG32RNG) -> Option<Ray> {
+        let (x, y) = (i as f64, j as f64);
+        if !matches!(self.projection, Projection::Perspective) {
+            return self.get_projected_ray(x, y, rng);
+        }
+        if let Some(lens) = &self.lens {
+            let width = self.image_width as f64;
+            let height = self.image_height as f64;
+            let (origin, direction) =
+                lens.chief_ray((x + 0.5) / width, (y + 0.5) / height, width / height)?;
+            return Some(Ray::new(
+                self.center + self.lens_to_world(&origin),
+                self.lens_to_world(&direction),
+                self.shutter_open,
+            ));
+        }
+        let pixel_sample = self.pixel_sample(x, y);
+        Some(Ray::new(
+            self.center,
+            pixel_sample - self.center,
+            self.shutter_open,
+        ))
+    }
+
+    // puts the plane of focus through the first hit of `r`
+    fn focus_along(&mut self, r: &Ray, world: &dyn Hittable, rng: &mut PCG32RNG) {
+        let Some(rec) = world.hit(r, &Interval::new(0.001, f64::INFINITY), rng) else {
+            return;
+        };
+        // lens systems focus on a plane parallel to the film
+        let n = match (&self.lens, self.focus_normal) {
+            (None, Some(n)) => n,
+            _ => self.w,
+        };
+        let focus_dist = -Vec3::dot(&(rec.p - self.center), &n) / Vec3::dot(&self.w, &n);
+        if focus_dist > 0.0 {
+            self.set_focus_dist(focus_dist);
+        }
+    }
+
+    // the viewport and the defocus disk scale with the focus distance. a
+    // lens system that cannot focus there keeps the current focus
+    fn set_focus_dist(&mut self, focus_dist: f64) {
+        if let Some(lens) = &mut self.lens
+            && lens.focus(focus_dist).is_err()
+        {
+            return;
+        }
+
+        let scale = focus_dist / self.focus_dist;
+        self.pixel00_loc = self.center + scale * (self.pixel00_loc - self.center);
+        self.pixel_delta_u *= scale;
+        self.pixel_delta_v *= scale;
+        self.defocus_disk_u *= scale;
+        self.defocus_disk_v *= scale;
+        self.focus_dist = focus_dist;
+    }
+
+    // lens system frames are right, up and forward
+    fn lens_to_world(&self, v: &Vec3) -> Vec3 {
+        v.x() * self.u + v.y() * self.v - v.z() * self.w
+    }
+
+    // ray leaving the front of the lens system for the film position (x, y)
+    // in pixels, with the lens' vignetting weight
+    fn get_lens_ray(
+        &self,
+        lens: &LensSystem,
+        x: f64,
+        y: f64,
+        rng: &mut PCG32RNG,
+    ) -> Option<(Ray, f64)> {
+        let width = self.image_width as f64;
+        let height = self.image_height as f64;
+        let (origin, direction, weight) =
+            lens.generate_ray((x + 0.5) / width, (y + 0.5) / height, width / height, rng)?;
+
+        let ray_time = rng.random_bounded_f64(self.shutter_open, self.shutter_close);
+        Some((
+            Ray::new(
+                self.center + self.lens_to_world(&origin),
+                self.lens_to_world(&direction),
+                ray_time,
+            ),
+            weight,
+        ))
+    }
+
+    // moves a ray of the central camera to the eye `offset` along u
+    fn eye_ray(&self, r: Ray, offset: f64) -> Ray {
+        let Some(stereo) = self.stereo else {
+            return r;
+        };
+
+        let (origin, direction) = match (stereo.mode, self.projection) {
+            (StereoMode::OmniDirectional, Projection::Equirectangular) => {
+                // the eye sits on the circle, to the side of the ray's
+                // horizontal direction; looking straight up or down there is
+                // no horizontal direction and both eyes meet
+                let d = r.direction();
+                let (du, dw) = (Vec3::dot(d, &self.u), Vec3::dot(d, &self.w));
+                let side = -dw * self.u + du * self.w;
+                let side = if side.near_zero() {
+                    Vec3::default()
+                } else {
+                    side.unit_vector()
+                };
+                (*r.origin() + offset * side, *d)
+            }
+            (StereoMode::OmniDirectional | StereoMode::Parallel, _) => {
+                (*r.origin() + offset * self.u, *r.direction())
+            }
+            (StereoMode::ToeIn, _) => {
+                // yaw around the camera center so the eye looks at the point
+                // focus_dist ahead of the central camera
+                let yaw = Mat4::rotate(&self.v, (offset / self.focus_dist).atan().to_degrees());
+                let origin = self.center
+                    + yaw.transform_vector(&(*r.origin() - self.center))
+                    + offset * self.u;
+                (origin, yaw.transform_vector(r.direction()))
+            }
+        };
+
+        Ray::new(origin, direction, r.time())
+    }
+
+    // ray through the film position (x, y) in pixels, from the top left
+    // corner, for the projections without a lens
+    fn get_projected_ray(&self, x: f64, y: f64, rng: &mut PCG32RNG) -> Option<Ray> {
+        let width = self.image_width as f64;
+        let height = self.image_height as f64;
+        // pixel centers to [-1, 1], y up
+        let fx = 2.0 * (x + 0.5) / width - 1.0;
+        let fy = 1.0 - 2.0 * (y + 0.5) / height;
+
+        // direction from spherical coordinates around the view direction
+        let spherical = |theta: f64, phi: f64| {
+            theta.sin() * (phi.cos() * self.u + phi.sin() * self.v) - theta.cos() * self.w
+        };
+
+        let (origin, direction) = match self.projection {
+            Projection::Perspective => unreachable!("perspective rays go through the lens"),
+            Projection::Orthographic {
+                height: view_height,
+            } => {
+                let view_width = view_height * width / height;
+                let origin = self.center
+                    + (0.5 * fx * view_width) * self.u
+                    + (0.5 * fy * view_height) * self.v;
+                (origin, -self.w)
+            }
+            Projection::Fisheye { fov } => {
+                let px = 0.5 * fx * width;
+                let py = 0.5 * fy * height;
+                let r = (px * px + py * py).sqrt() / (0.5 * width.min(height));
+                if r > 1.0 {
+                    return None;
+                }
+                let theta = r * util::deg_to_rad(fov / 2.0);
+                (self.center, spherical(theta, py.atan2(px)))
+            }
+            Projection::Equirectangular => {
+                let longitude = fx * PI;
+                let latitude = fy * PI / 2.0;
+                let direction = latitude.cos()
+                    * (longitude.sin() * self.u - longitude.cos() * self.w)
+                    + latitude.sin() * self.v;
+                (self.center, direction)
+            }
+        };
 
-        Ray::new(ray_origin, ray_direction)
+        let ray_time = rng.random_bounded_f64(self.shutter_open, self.shutter_close);
+        Some(Ray::new(origin, direction, ray_time))
     }
 
     fn sample_square(&self, rng: &mut PCG32RNG) -> Vec3 {
@@ -175,11 +776,95 @@ impl Camera {
     }
 
     fn defocus_disk_sample(&self, rng: &mut PCG32RNG) -> Point3 {
-        let p = Point3::random_in_unit_disk(rng);
+        let p = self.aperture.sample(rng);
         self.center + (p.x() * self.defocus_disk_u) + (p.y() * self.defocus_disk_v)
     }
 }
 
+// scattering events a random walk may take before the path is terminated
+const MAX_WALK_STEPS: usize = 256;
+
+// homogeneous interior of the closed surfaces a ray is travelling through
+#[derive(Clone, Default)]
+struct Medium {
+    absorption: Color,
+    // scattering coefficient and phase function, for subsurface scattering
+    scattering: Option<(Color, Rc<dyn PhaseFunction>)>,
+    // the medium around this one, None for vacuum. nested surfaces, e.g. ice
+    // in a drink in a glass, form a stack left one level at a time
+    outer: Option<Rc<Medium>>,
+}
+
+// random walk from `r` through a homogeneous scattering medium until it
+// reaches a surface. returns the path weight, the last segment and the hit
+// at its end, or None if the walk is terminated or never reaches a surface
+fn random_walk(
+    r: &Ray,
+    world: &dyn Hittable,
+    sigma_a: &Color,
+    sigma_s: &Color,
+    phase: &dyn PhaseFunction,
+    rng: &mut PCG32RNG,
+) -> Option<(Color, Ray, HitRecord)> {
+    let mean = |c: Color| (c.x() + c.y() + c.z()) / 3.0;
+    let sigma_t = *sigma_a + *sigma_s;
+    let mut weight = Color::new(1.0, 1.0, 1.0);
+    let mut segment = r.clone();
+
+    for _ in 0..MAX_WALK_STEPS {
+        let rec = world.hit(&segment, &Interval::new(0.001, f64::INFINITY), rng)?;
+        let ray_length = segment.direction().len();
+        let distance = rec.t * ray_length;
+
+        // free flight distance sampled for a random channel, weighted by
+        // the density averaged over all channels
+        let channel = ((rng.random_f64() * 3.0) as usize).min(2);
+        let flight = -(1.0 - rng.random_f64()).ln() / sigma_t[channel];
+
+        if flight >= distance {
+            let transmittance = beer_lambert(&sigma_t, distance);
+            weight = weight * transmittance / mean(transmittance);
+            return Some((weight, segment, rec));
+        }
+
+        let transmittance = beer_lambert(&sigma_t, flight);
+        weight = weight * *sigma_s * transmittance / mean(sigma_t * transmittance);
+
+        // p / pdf is 1 for the exactly sampled phase functions here
+        let direction = phase.sample(segment.direction(), rng);
+        let pdf = phase.pdf(segment.direction(), &direction);
+        if pdf <= 0.0 {
+            return None;
+        }
+        weight *= phase.p(segment.direction(), &direction) / pdf;
+        segment = Ray::new(segment.at(flight / ray_length), direction, segment.time())
+            .with_wavelengths(segment.wavelengths().copied());
+    }
+
+    None
+}
+
+// power heuristic weights of next-event estimation and of a scattered ray
+// finding the same emitter. emitters that cannot be sampled as lights are
+// left to scattered rays alone
+fn mis_weights(light_pdf: f64, scatter_pdf: f64) -> (f64, f64) {
+    if light_pdf <= 0.0 {
+        return (1.0, 1.0);
+    }
+    let (l2, s2) = (light_pdf * light_pdf, scatter_pdf * scatter_pdf);
+    (l2 / (l2 + s2), s2 / (l2 + s2))
+}
+
+// fraction of light surviving `distance` through a medium with absorption
+// coefficient `sigma_a`
+fn beer_lambert(sigma_a: &Color, distance: f64) -> Color {
+    Color::new(
+        (-sigma_a.x() * distance).exp(),
+        (-sigma_a.y() * distance).exp(),
+        (-sigma_a.z() * distance).exp(),
+    )
+}
+
 impl Default for Camera {
     fn default() -> Self {
         Self::new(
@@ -196,3 +881,475 @@ impl Default for Camera {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::{list::HittableList, quad::Quad, sphere::Sphere},
+        light::{list::LightList, point::PointLight, sphere::SphereLight},
+        material::{Coated, Dielectric, DiffuseLight, Lambertian, Material, Metal, Mix},
+        phase::IsotropicPhase,
+    };
+
+    // black backdrop whose only light is the part reserved for lights
+    struct EmittedOnly(Color);
+
+    impl Sky for EmittedOnly {
+        fn radiance(&self, _direction: &Vec3) -> Color {
+            Color::default()
+        }
+
+        fn emitted(&self, _direction: &Vec3) -> Color {
+            self.0
+        }
+    }
+
+    fn floor(mat: Rc<dyn Material>) -> HittableList {
+        let mut world = HittableList::default();
+        world.add(Rc::new(Quad::new(
+            Point3::new(-10.0, 0.0, 10.0),
+            Vec3::new(20.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -20.0),
+            mat,
+        )));
+        world
+    }
+
+    // mean radiance of a ray falling straight down onto the floor
+    fn radiance_from_above(cam: &Camera, world: &dyn Hittable, lights: &dyn Light) -> Color {
+        let mut rng = PCG32RNG::default();
+        let r = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let n = 4000;
+        let mut sum = Color::default();
+        for _ in 0..n {
+            sum += cam.ray_color(&r, 3, world, lights, &Medium::default(), None, &mut rng);
+        }
+        sum / n as f64
+    }
+
+    fn black() -> Rc<dyn Material> {
+        Rc::new(Lambertian::new(Color::default()))
+    }
+
+    #[test]
+    fn sky_emission_is_seen_through_delta_lobes_of_layered_materials() {
+        let cam = Camera::default().with_sky(Rc::new(EmittedOnly(Color::new(1.0, 1.0, 1.0))));
+
+        // normal incidence fresnel reflectance of the coat is 0.04
+        let coated = floor(Rc::new(Coated::new(black(), 1.5)));
+        let c = radiance_from_above(&cam, &coated, &LightList::default());
+        assert!((c.x() - 0.04).abs() < 0.01, "{c:?}");
+
+        // half of the paths take the mirror
+        let mirror = Rc::new(Metal::new(Color::new(1.0, 1.0, 1.0), 0.0));
+        let mixed = floor(Rc::new(Mix::new(black(), mirror, 0.5)));
+        let c = radiance_from_above(&cam, &mixed, &LightList::default());
+        assert!((c.x() - 0.5).abs() < 0.05, "{c:?}");
+    }
+
+    #[test]
+    fn emitters_are_seen_through_delta_lobes_of_layered_materials() {
+        let cam = Camera::default().with_sky(Rc::new(EmittedOnly(Color::default())));
+        let lamp: Rc<dyn Hittable> = Rc::new(Sphere::new(
+            Point3::new(0.0, 5.0, 0.0),
+            1.0,
+            Rc::new(DiffuseLight::new(Color::new(1.0, 1.0, 1.0))),
+        ));
+
+        let mut coated = floor(Rc::new(Coated::new(black(), 1.5)));
+        coated.add(lamp.clone());
+        let c = radiance_from_above(&cam, &coated, &LightList::default());
+        assert!((c.x() - 0.04).abs() < 0.01, "{c:?}");
+
+        let mirror = Rc::new(Metal::new(Color::new(1.0, 1.0, 1.0), 0.0));
+        let mut mixed = floor(Rc::new(Mix::new(black(), mirror, 0.5)));
+        mixed.add(lamp);
+        let c = radiance_from_above(&cam, &mixed, &LightList::default());
+        assert!((c.x() - 0.5).abs() < 0.05, "{c:?}");
+    }
+
+    #[test]
+    fn point_light_is_sampled_by_next_event_estimation() {
+        let cam = Camera::default().with_sky(Rc::new(EmittedOnly(Color::default())));
+        let albedo = Color::new(0.5, 0.5, 0.5);
+        let world = floor(Rc::new(Lambertian::new(albedo)));
+        // 45 degrees off the normal at a distance of 2
+        let lights = LightList::new(Rc::new(PointLight::new(
+            Point3::new(2.0f64.sqrt(), 2.0f64.sqrt(), 0.0),
+            Color::new(8.0, 8.0, 8.0),
+        )));
+
+        // albedo / pi * I / d^2 * cos(theta); the bounce only sees the black
+        // sky
+        let expected = 0.5 / PI * 8.0 / 4.0 * 0.5f64.sqrt();
+        let c = radiance_from_above(&cam, &world, &lights);
+        assert!((c.x() - expected).abs() < 1e-9, "{c:?}");
+
+        // a blocker in between casts a shadow
+        let mut shadowed = floor(Rc::new(Lambertian::new(albedo)));
+        shadowed.add(Rc::new(Sphere::new(
+            Point3::new(0.7, 0.7, 0.0),
+            0.3,
+            black(),
+        )));
+        let c = radiance_from_above(&cam, &shadowed, &lights);
+        assert_eq!(c, Color::default());
+    }
+
+    #[test]
+    fn sphere_lights_are_counted_once() {
+        let cam = Camera::default().with_sky(Rc::new(EmittedOnly(Color::default())));
+        let (r, d) = (0.5, 3.0);
+        let lamp = Rc::new(SphereLight::new(
+            Point3::new(0.0, d, 0.0),
+            r,
+            Color::new(4.0, 4.0, 4.0),
+        ));
+        let mut world = floor(Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))));
+        world.add(lamp.clone());
+        let lights = LightList::new(lamp);
+
+        // sampled by next-event estimation, and not again when a bounce off
+        // the floor hits the lamp: albedo / pi * pi * L * (r / d)^2 right
+        // below it
+        let expected = 0.5 * 4.0 * (r / d) * (r / d);
+        let c = radiance_from_above(&cam, &world, &lights);
+        assert!(
+            (c.x() - expected).abs() < 0.01 * expected,
+            "{c:?} != {expected}"
+        );
+    }
+
+    #[test]
+    fn emitters_missing_from_the_lights_light_diffuse_surfaces() {
+        let cam = Camera::default().with_sky(Rc::new(EmittedOnly(Color::default())));
+        // a 4 x 4 panel facing down, 2 above the floor
+        let mut world = floor(Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))));
+        world.add(Rc::new(Quad::new(
+            Point3::new(-2.0, 2.0, -2.0),
+            Vec3::new(4.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 4.0),
+            Rc::new(DiffuseLight::new(Color::new(1.0, 1.0, 1.0))),
+        )));
+
+        // albedo * L * the form factor of the panel, four squares as wide as
+        // they are high above their corners
+        let expected = 0.5 * 4.0 / PI * 0.5f64.sqrt() * 0.5f64.sqrt().atan();
+        let c = radiance_from_above(&cam, &world, &LightList::default());
+        assert!(
+            (c.x() - expected).abs() < 0.05 * expected,
+            "{c:?} != {expected}"
+        );
+    }
+
+    #[test]
+    fn nested_media_absorb_in_turn() {
+        let cam = Camera::default().with_sky(Rc::new(EmittedOnly(Color::new(1.0, 1.0, 1.0))));
+        // index matched, so the ray passes straight through both spheres
+        let (a, b) = (0.5, 2.0);
+        let glass = |radius: f64, sigma: f64| {
+            Rc::new(Sphere::new(
+                Point3::default(),
+                radius,
+                Rc::new(Dielectric::new(1.0).with_absorption(Color::new(sigma, sigma, sigma))),
+            ))
+        };
+        let mut world = HittableList::default();
+        world.add(glass(1.0, a));
+        world.add(glass(0.5, b));
+
+        // the outer medium absorbs again behind the inner sphere
+        let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let mut rng = PCG32RNG::default();
+        let c = cam.ray_color(
+            &r,
+            10,
+            &world,
+            &LightList::default(),
+            &Medium::default(),
+            None,
+            &mut rng,
+        );
+        let expected = (-a * 1.0 - b * 1.0).exp();
+        assert!((c.x() - expected).abs() < 1e-9, "{c:?} != {expected}");
+    }
+
+    // mean random walk weight from the center of a unit sphere
+    fn mean_walk_weight(sigma_a: Color, sigma_s: Color) -> Color {
+        let mut world = HittableList::default();
+        world.add(Rc::new(Sphere::new(Point3::default(), 1.0, black())));
+        let mut rng = PCG32RNG::default();
+        let r = Ray::new(Point3::default(), Vec3::new(0.0, 1.0, 0.0), 0.0);
+        let n = 20000;
+        let mut sum = Color::default();
+        for _ in 0..n {
+            if let Some((weight, segment, rec)) =
+                random_walk(&r, &world, &sigma_a, &sigma_s, &IsotropicPhase, &mut rng)
+            {
+                assert!((segment.at(rec.t).len() - 1.0).abs() < 1e-9);
+                sum += weight;
+            }
+        }
+        sum / n as f64
+    }
+
+    #[test]
+    fn random_walk_without_absorption_keeps_all_light() {
+        // chromatic scattering, with the flight distance sampled per channel.
+        // strongly chromatic media converge slowly, as the weights of the
+        // channels drift apart with every collision
+        let c = mean_walk_weight(Color::default(), Color::new(1.0, 1.5, 2.0));
+        for i in 0..3 {
+            assert!((c[i] - 1.0).abs() < 0.03, "{c:?}");
+        }
+    }
+
+    #[test]
+    fn random_walk_without_scattering_is_beer_lambert() {
+        let sigma_a = Color::new(0.5, 1.0, 2.0);
+        let c = mean_walk_weight(sigma_a, Color::default());
+        let expected = beer_lambert(&sigma_a, 1.0);
+        for i in 0..3 {
+            assert!((c[i] - expected[i]).abs() < 0.02, "{c:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn ray_times_are_spread_over_the_shutter() {
+        let cam = Camera::default().with_shutter(0.25, 0.75);
+        let mut rng = PCG32RNG::default();
+        let n = 10000;
+        let mut sum = 0.0;
+        for _ in 0..n {
+            let (r, _) = cam.get_ray(50, 30, &mut rng).unwrap();
+            assert!((0.25..0.75).contains(&r.time()));
+            sum += r.time();
+        }
+        assert!((sum / n as f64 - 0.5).abs() < 0.01);
+    }
+
+    // camera at the origin looking down -z with y up, its film 200 pixels
+    // wide and one world unit from the center
+    fn level_camera(aspect_ratio: f64) -> Camera {
+        Camera::new(
+            aspect_ratio,
+            200,
+            1,
+            10,
+            90.0,
+            Point3::default(),
+            Point3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            1.0,
+        )
+    }
+
+    fn assert_vec_near(a: &Vec3, b: &Vec3) {
+        assert!((*a - *b).len() < 1e-9, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn orthographic_rays_are_parallel() {
+        let cam = level_camera(2.0).with_projection(Projection::Orthographic { height: 2.0 });
+        let mut rng = PCG32RNG::default();
+
+        // the film edges, with pixel centers at half a pixel in
+        let corner = cam.get_projected_ray(-0.5, -0.5, &mut rng).unwrap();
+        assert_vec_near(corner.origin(), &Point3::new(-2.0, 1.0, 0.0));
+        assert_vec_near(corner.direction(), &Vec3::new(0.0, 0.0, -1.0));
+
+        let center = cam.get_projected_ray(99.5, 49.5, &mut rng).unwrap();
+        assert_vec_near(center.origin(), &Point3::default());
+        assert_vec_near(center.direction(), &Vec3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn fisheye_angle_grows_with_the_film_radius() {
+        let cam = level_camera(1.0).with_projection(Projection::Fisheye { fov: 180.0 });
+        let mut rng = PCG32RNG::default();
+
+        let center = cam.get_projected_ray(99.5, 99.5, &mut rng).unwrap();
+        assert_vec_near(center.origin(), &Point3::default());
+        assert_vec_near(
+            &center.direction().unit_vector(),
+            &Vec3::new(0.0, 0.0, -1.0),
+        );
+
+        // the edge of the image circle is fov / 2 off the view direction
+        let right = cam.get_projected_ray(199.5, 99.5, &mut rng).unwrap();
+        assert_vec_near(&right.direction().unit_vector(), &Vec3::new(1.0, 0.0, 0.0));
+        let top = cam.get_projected_ray(99.5, -0.5, &mut rng).unwrap();
+        assert_vec_near(&top.direction().unit_vector(), &Vec3::new(0.0, 1.0, 0.0));
+
+        // halfway out is halfway round
+        let d = cam
+            .get_projected_ray(149.5, 99.5, &mut rng)
+            .unwrap()
+            .direction()
+            .unit_vector();
+        assert_vec_near(&d, &Vec3::new(1.0, 0.0, -1.0).unit_vector());
+
+        // the corners are outside the image circle
+        assert!(cam.get_projected_ray(-0.5, -0.5, &mut rng).is_none());
+    }
+
+    #[test]
+    fn equirectangular_covers_the_full_sphere() {
+        let cam = level_camera(2.0).with_projection(Projection::Equirectangular);
+        let mut rng = PCG32RNG::default();
+        let mut direction = |x: f64, y: f64| {
+            cam.get_projected_ray(x, y, &mut rng)
+                .unwrap()
+                .direction()
+                .unit_vector()
+        };
+
+        assert_vec_near(&direction(99.5, 49.5), &Vec3::new(0.0, 0.0, -1.0));
+        assert_vec_near(&direction(149.5, 49.5), &Vec3::new(1.0, 0.0, 0.0));
+        assert_vec_near(&direction(49.5, 49.5), &Vec3::new(-1.0, 0.0, 0.0));
+        assert_vec_near(&direction(-0.5, 49.5), &Vec3::new(0.0, 0.0, 1.0));
+        assert_vec_near(&direction(99.5, -0.5), &Vec3::new(0.0, 1.0, 0.0));
+        assert_vec_near(&direction(99.5, 99.5), &Vec3::new(0.0, -1.0, 0.0));
+    }
+
+    fn forward_ray(direction: Vec3) -> Ray {
+        Ray::new(Point3::default(), direction, 0.0)
+    }
+
+    #[test]
+    fn parallel_eyes_are_offset_sideways() {
+        let cam =
+            level_camera(1.0).with_stereo(StereoMode::Parallel, 0.064, StereoLayout::SideBySide);
+        let d = Vec3::new(0.3, 0.2, -1.0);
+        for offset in [-0.032, 0.032] {
+            let r = cam.eye_ray(forward_ray(d), offset);
+            assert_vec_near(r.origin(), &Point3::new(offset, 0.0, 0.0));
+            assert_vec_near(r.direction(), &d);
+        }
+    }
+
+    #[test]
+    fn toe_in_eyes_converge_at_the_focus_distance() {
+        let cam = level_camera(1.0).with_stereo(StereoMode::ToeIn, 0.064, StereoLayout::TopBottom);
+        for offset in [-0.032, 0.032] {
+            let r = cam.eye_ray(forward_ray(Vec3::new(0.0, 0.0, -1.0)), offset);
+            assert_vec_near(r.origin(), &Point3::new(offset, 0.0, 0.0));
+            let to_focus = Point3::new(0.0, 0.0, -1.0) - *r.origin();
+            assert!(Vec3::cross(r.direction(), &to_focus).len() < 1e-9);
+            assert!(Vec3::dot(r.direction(), &to_focus) > 0.0);
+        }
+    }
+
+    #[test]
+    fn omni_directional_eyes_sit_on_the_viewing_circle() {
+        let cam = level_camera(2.0)
+            .with_projection(Projection::Equirectangular)
+            .with_stereo(StereoMode::OmniDirectional, 0.064, StereoLayout::TopBottom);
+
+        // the left eye is to the left of whichever way the ray looks
+        for (d, left) in [
+            (Vec3::new(0.0, 0.0, -1.0), Vec3::new(-1.0, 0.0, 0.0)),
+            (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+            (Vec3::new(0.0, 0.5, 1.0), Vec3::new(1.0, 0.0, 0.0)),
+        ] {
+            let r = cam.eye_ray(forward_ray(d), -0.032);
+            assert_vec_near(r.origin(), &(0.032 * left));
+            assert_vec_near(r.direction(), &d);
+        }
+
+        // looking straight up both eyes meet at the center
+        let r = cam.eye_ray(forward_ray(Vec3::new(0.0, 1.0, 0.0)), 0.032);
+        assert_vec_near(r.origin(), &Point3::default());
+
+        // other projections fall back to parallel eyes
+        let cam = level_camera(1.0).with_stereo(
+            StereoMode::OmniDirectional,
+            0.064,
+            StereoLayout::TopBottom,
+        );
+        let r = cam.eye_ray(forward_ray(Vec3::new(1.0, 0.0, 0.0)), -0.032);
+        assert_vec_near(r.origin(), &Point3::new(-0.032, 0.0, 0.0));
+    }
+
+    // wall facing the camera at z = -depth, wide enough to fill the view
+    fn wall(depth: f64) -> HittableList {
+        let mut world = HittableList::default();
+        world.add(Rc::new(Quad::new(
+            Point3::new(-100.0, -100.0, -depth),
+            Vec3::new(200.0, 0.0, 0.0),
+            Vec3::new(0.0, 200.0, 0.0),
+            black(),
+        )));
+        world
+    }
+
+    #[test]
+    fn autofocus_puts_the_plane_of_focus_on_the_hit() {
+        let mut rng = PCG32RNG::default();
+
+        let mut world = HittableList::default();
+        world.add(Rc::new(Sphere::new(
+            Point3::new(0.0, 0.0, -3.0),
+            1.0,
+            black(),
+        )));
+        let cam = level_camera(1.0).focus_on_lookat(&world, &mut rng);
+        assert!((cam.focus_dist - 2.0).abs() < 1e-9);
+        // the viewport moves with the focus
+        assert_vec_near(&cam.pixel_sample(99.5, 99.5), &Point3::new(0.0, 0.0, -2.0));
+
+        // the focus distance is measured along the view axis
+        let cam = level_camera(1.0).focus_on_pixel(&wall(4.0), 0, 0, &mut rng);
+        assert!((cam.focus_dist - 4.0).abs() < 1e-9);
+
+        // nothing to focus on keeps the focus
+        let cam = level_camera(1.0).focus_on_pixel(&HittableList::default(), 0, 0, &mut rng);
+        assert_eq!(cam.focus_dist, 1.0);
+    }
+
+    #[test]
+    fn tilted_focus_recedes_towards_the_top() {
+        let cam = level_camera(1.0).with_tilt(30.0, 0.0);
+        let n = cam.focus_normal.unwrap();
+        let pivot = Point3::new(0.0, 0.0, -1.0);
+
+        let mut depths = vec![];
+        for (x, y) in [(99.5, -0.5), (99.5, 99.5), (-0.5, 150.0), (199.5, 199.5)] {
+            let p = cam.focus_point(&cam.pixel_sample(x, y));
+            assert!(Vec3::dot(&(p - pivot), &n).abs() < 1e-9);
+            depths.push(-p.z());
+        }
+        assert!(depths[0] > depths[1]);
+        assert!((depths[1] - 1.0).abs() < 1e-9);
+        assert!(depths[3] < depths[1]);
+
+        // swing tilts it left to right instead
+        let cam = level_camera(1.0).with_tilt(0.0, 30.0);
+        let left = cam.focus_point(&cam.pixel_sample(-0.5, 99.5));
+        let right = cam.focus_point(&cam.pixel_sample(199.5, 99.5));
+        assert!(-right.z() > -left.z());
+
+        // focusing a tilted plane keeps its angle and moves its pivot
+        let mut rng = PCG32RNG::default();
+        let cam =
+            level_camera(1.0)
+                .with_tilt(30.0, 0.0)
+                .focus_on_pixel(&wall(3.0), 99, 20, &mut rng);
+        let p = cam.focus_point(&cam.pixel_sample(99.0, 20.0));
+        assert!((p.z() + 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn shift_moves_the_view_without_turning_it() {
+        let cam = level_camera(1.0).with_shift(0.25, -0.5);
+        // a quarter of the 2 unit wide viewport to the right, half its
+        // height down
+        assert_vec_near(&cam.pixel_sample(99.5, 99.5), &Point3::new(0.5, -1.0, -1.0));
+        assert_vec_near(
+            &(cam.pixel_sample(199.5, 99.5) - cam.pixel_sample(99.5, 99.5)),
+            &Vec3::new(1.0, 0.0, 0.0),
+        );
+    }
+}
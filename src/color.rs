@@ -13,6 +13,22 @@ pub fn linear_to_gamma(linear_component: f64) -> f64 {
     }
 }
 
+// relative luminance of linear srgb
+#[inline]
+pub fn luminance(c: &Color) -> f64 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
+
+// inverse of `linear_to_gamma`, for decoding color images
+#[inline]
+pub fn gamma_to_linear(gamma_component: f64) -> f64 {
+    if gamma_component > 0.0 {
+        gamma_component * gamma_component
+    } else {
+        0.0
+    }
+}
+
 pub fn write_color<T: Write>(out: &mut T, pixel_color: &Color) -> io::Result<()> {
     let r = linear_to_gamma(pixel_color.x());
     let g = linear_to_gamma(pixel_color.y());
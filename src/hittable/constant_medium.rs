@@ -0,0 +1,149 @@
+use std::rc::Rc;
+
+use crate::{
+    color::Color,
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    material::{Isotropic, Material},
+    ray::Ray,
+    util::rand::PCG32RNG,
+    vec3::Vec3,
+};
+
+// volume of uniform density filling a closed, convex boundary (fog, smoke)
+pub struct ConstantMedium {
+    boundary: Rc<dyn Hittable>,
+    neg_inv_density: f64,
+    phase_function: Rc<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Rc<dyn Hittable>, density: f64, albedo: Color) -> Self {
+        Self::with_material(boundary, density, Rc::new(Isotropic::new(albedo)))
+    }
+
+    pub fn with_material(
+        boundary: Rc<dyn Hittable>,
+        density: f64,
+        phase_function: Rc<dyn Material>,
+    ) -> Self {
+        Self {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function,
+        }
+    }
+
+    // part of ray_t spent inside the boundary
+    fn inside(&self, r: &Ray, ray_t: &Interval, rng: &mut PCG32RNG) -> Option<Interval> {
+        // find where the ray enters and leaves the boundary, regardless of
+        // ray_t, so rays starting inside the volume still work
+        let rec1 = self.boundary.hit(r, &Interval::UNIVERSE, rng)?;
+        let rec2 = self
+            .boundary
+            .hit(r, &Interval::new(rec1.t + 0.0001, f64::INFINITY), rng)?;
+
+        let t_enter = rec1.t.max(ray_t.min).max(0.0);
+        let t_exit = rec2.t.min(ray_t.max);
+        if t_enter >= t_exit {
+            return None;
+        }
+        Some(Interval::new(t_enter, t_exit))
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rng: &mut PCG32RNG) -> Option<HitRecord> {
+        let Interval {
+            min: t_enter,
+            max: t_exit,
+        } = self.inside(r, ray_t, rng)?;
+
+        let ray_length = r.direction().len();
+        let distance_inside_boundary = (t_exit - t_enter) * ray_length;
+        let hit_distance = self.neg_inv_density * rng.random_f64().ln();
+        if hit_distance > distance_inside_boundary {
+            return None;
+        }
+
+        let t = t_enter + hit_distance / ray_length;
+        Some(HitRecord {
+            t,
+            p: r.at(t),
+            // normal and face are meaningless inside a volume
+            normal: Vec3::new(1.0, 0.0, 0.0),
+            geometric_normal: Vec3::new(1.0, 0.0, 0.0),
+            u: 0.0,
+            v: 0.0,
+            dpdu: Vec3::default(),
+            dpdv: Vec3::default(),
+            front_face: true,
+            mat: self.phase_function.clone(),
+        })
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: &Interval, rng: &mut PCG32RNG) -> f64 {
+        match self.inside(r, ray_t, rng) {
+            // beer-lambert: exp(-density * distance)
+            Some(span) => (span.size() * r.direction().len() / self.neg_inv_density).exp(),
+            None => 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hittable::sphere::Sphere, vec3::Point3};
+
+    fn fog(density: f64) -> ConstantMedium {
+        let boundary = Rc::new(Sphere::new(
+            Point3::default(),
+            1.0,
+            Rc::new(Isotropic::new(Color::default())),
+        ));
+        ConstantMedium::new(boundary, density, Color::new(0.5, 0.5, 0.5))
+    }
+
+    #[test]
+    fn transmittance_follows_beer_lambert() {
+        let medium = fog(0.5);
+        let mut rng = PCG32RNG::default();
+        let through = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 2.0), 0.0);
+        let t = medium.transmittance(&through, &Interval::new(0.0, f64::INFINITY), &mut rng);
+        assert!((t - (-1.0f64).exp()).abs() < 1e-9);
+
+        // rays starting inside only see the rest of the volume
+        let inside = Ray::new(Point3::default(), Vec3::new(0.0, 1.0, 0.0), 0.0);
+        let t = medium.transmittance(&inside, &Interval::new(0.0, f64::INFINITY), &mut rng);
+        assert!((t - (-0.5f64).exp()).abs() < 1e-9);
+
+        // and shadow rays stop at the light
+        let t = medium.transmittance(&through, &Interval::new(0.0, 2.25), &mut rng);
+        assert!((t - (-0.25f64).exp()).abs() < 1e-9);
+
+        let miss = Ray::new(Point3::new(0.0, 2.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        assert_eq!(
+            medium.transmittance(&miss, &Interval::new(0.0, f64::INFINITY), &mut rng),
+            1.0
+        );
+    }
+
+    #[test]
+    fn hits_are_distributed_over_the_volume() {
+        let medium = fog(0.5);
+        let mut rng = PCG32RNG::default();
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+
+        let n = 20000;
+        let mut hits = 0;
+        for _ in 0..n {
+            if let Some(rec) = medium.hit(&r, &Interval::new(0.0, f64::INFINITY), &mut rng) {
+                assert!(rec.t >= 4.0 && rec.t <= 6.0);
+                hits += 1;
+            }
+        }
+        let expected = 1.0 - (-1.0f64).exp();
+        assert!((hits as f64 / n as f64 - expected).abs() < 0.01);
+    }
+}
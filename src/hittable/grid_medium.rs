@@ -0,0 +1,301 @@
+use std::rc::Rc;
+
+use crate::{
+    aabb::Aabb,
+    color::Color,
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    material::{Isotropic, Material},
+    ray::Ray,
+    util::rand::PCG32RNG,
+    vec3::{Point3, Vec3},
+    volume::{DensityGrid, MajorantGrid},
+};
+
+// voxels per majorant cell along each axis
+const MAJORANT_BLOCK_SIZE: usize = 8;
+
+// heterogeneous volume whose density comes from a voxel grid stretched over
+// an axis-aligned box. wrap it in a `Transform` to place it arbitrarily
+pub struct GridMedium {
+    grid: DensityGrid,
+    majorants: MajorantGrid,
+    bbox: Aabb,
+    density_scale: f64,
+    phase_function: Rc<dyn Material>,
+}
+
+impl GridMedium {
+    pub fn new(
+        grid: DensityGrid,
+        corner_a: Point3,
+        corner_b: Point3,
+        density_scale: f64,
+        albedo: Color,
+    ) -> Self {
+        Self::with_material(
+            grid,
+            corner_a,
+            corner_b,
+            density_scale,
+            Rc::new(Isotropic::new(albedo)),
+        )
+    }
+
+    pub fn with_material(
+        grid: DensityGrid,
+        corner_a: Point3,
+        corner_b: Point3,
+        density_scale: f64,
+        phase_function: Rc<dyn Material>,
+    ) -> Self {
+        let majorants = MajorantGrid::new(&grid, MAJORANT_BLOCK_SIZE);
+        Self {
+            grid,
+            majorants,
+            bbox: Aabb::from_points(&corner_a, &corner_b),
+            density_scale,
+            phase_function,
+        }
+    }
+
+    fn to_grid(&self, p: &Point3) -> Point3 {
+        let min = self.bbox.min();
+        let extent = self.bbox.max() - min;
+        let q = *p - min;
+        Point3::new(q.x() / extent.x(), q.y() / extent.y(), q.z() / extent.z())
+    }
+
+    fn density(&self, p: &Point3) -> f64 {
+        self.density_scale * self.grid.density(&self.to_grid(p))
+    }
+
+    // walks the majorant cells pierced by `r` over `ray_t` (3D DDA), calling
+    // `f(t_start, t_end, majorant)` for each segment until it returns false
+    fn traverse<F>(&self, r: &Ray, ray_t: &Interval, mut f: F)
+    where
+        F: FnMut(f64, f64, f64) -> bool,
+    {
+        let Some(span) = self.bbox.hit(r, ray_t) else {
+            return;
+        };
+
+        let res = self.majorants.res();
+        let voxels = self.grid.res();
+        let block_size = self.majorants.block_size() as f64;
+        let min = self.bbox.min();
+        let extent = self.bbox.max() - min;
+
+        // ray in majorant cell coordinates, with the same parameterization.
+        // cells span block_size voxels, so a partial last cell ends at the
+        // box instead of at res
+        let mut o = Vec3::default();
+        let mut d = Vec3::default();
+        for axis in 0..3 {
+            let scale = voxels[axis] as f64 / (extent[axis] * block_size);
+            o[axis] = (r.origin()[axis] - min[axis]) * scale;
+            d[axis] = r.direction()[axis] * scale;
+        }
+
+        let p0 = o + span.min * d;
+        let mut cell = [0i64; 3];
+        let mut step = [0i64; 3];
+        let mut next_t = [f64::INFINITY; 3];
+        let mut delta_t = [f64::INFINITY; 3];
+        for axis in 0..3 {
+            cell[axis] = (p0[axis].floor() as i64).clamp(0, res[axis] as i64 - 1);
+            if d[axis] > 0.0 {
+                step[axis] = 1;
+                next_t[axis] = span.min + (cell[axis] as f64 + 1.0 - p0[axis]) / d[axis];
+                delta_t[axis] = 1.0 / d[axis];
+            } else if d[axis] < 0.0 {
+                step[axis] = -1;
+                next_t[axis] = span.min + (cell[axis] as f64 - p0[axis]) / d[axis];
+                delta_t[axis] = -1.0 / d[axis];
+            }
+        }
+
+        let mut t = span.min;
+        loop {
+            let axis = (0..3)
+                .min_by(|&a, &b| next_t[a].total_cmp(&next_t[b]))
+                .unwrap();
+            let t_end = next_t[axis].min(span.max);
+
+            let majorant = self.density_scale * self.majorants.get(cell.map(|c| c as usize));
+            if t_end > t && !f(t, t_end, majorant) {
+                return;
+            }
+            if t_end >= span.max {
+                return;
+            }
+
+            t = t_end;
+            cell[axis] += step[axis];
+            if cell[axis] < 0 || cell[axis] >= res[axis] as i64 {
+                return;
+            }
+            next_t[axis] += delta_t[axis];
+        }
+    }
+}
+
+impl Hittable for GridMedium {
+    // delta (woodcock) tracking: sample tentative collisions against the
+    // local majorant and accept each with probability density / majorant
+    fn hit(&self, r: &Ray, ray_t: &Interval, rng: &mut PCG32RNG) -> Option<HitRecord> {
+        let ray_length = r.direction().len();
+        let mut hit_t = None;
+
+        self.traverse(r, ray_t, |t_start, t_end, majorant| {
+            if majorant <= 0.0 {
+                return true;
+            }
+
+            let mut t = t_start;
+            loop {
+                t -= (1.0 - rng.random_f64()).ln() / (majorant * ray_length);
+                if t >= t_end {
+                    return true;
+                }
+                if rng.random_f64() * majorant < self.density(&r.at(t)) {
+                    hit_t = Some(t);
+                    return false;
+                }
+            }
+        });
+
+        let t = hit_t?;
+        Some(HitRecord {
+            t,
+            p: r.at(t),
+            // normal and face are meaningless inside a volume
+            normal: Vec3::new(1.0, 0.0, 0.0),
+            geometric_normal: Vec3::new(1.0, 0.0, 0.0),
+            u: 0.0,
+            v: 0.0,
+            dpdu: Vec3::default(),
+            dpdv: Vec3::default(),
+            front_face: true,
+            mat: self.phase_function.clone(),
+        })
+    }
+
+    // ratio tracking: weight the transmittance by the null-collision
+    // probability at every tentative collision instead of stopping
+    fn transmittance(&self, r: &Ray, ray_t: &Interval, rng: &mut PCG32RNG) -> f64 {
+        let ray_length = r.direction().len();
+        let mut tr = 1.0;
+
+        self.traverse(r, ray_t, |t_start, t_end, majorant| {
+            if majorant <= 0.0 {
+                return true;
+            }
+
+            let mut t = t_start;
+            loop {
+                t -= (1.0 - rng.random_f64()).ln() / (majorant * ray_length);
+                if t >= t_end {
+                    return true;
+                }
+                tr *= 1.0 - (self.density(&r.at(t)) / majorant).min(1.0);
+                if tr <= 0.0 {
+                    return false;
+                }
+            }
+        });
+
+        tr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn majorants_bound_density_when_res_is_not_a_block_multiple() {
+        // a dense slab in voxels 5 and 6 of 10, inside the first majorant
+        // block, and empty space in the partial second block
+        let n = 10;
+        let mut data = vec![0.0; n * n * n];
+        for z in 0..n {
+            for y in 0..n {
+                for x in 5..=6 {
+                    data[(z * n + y) * n + x] = 1.0;
+                }
+            }
+        }
+        let medium = GridMedium::new(
+            DensityGrid::new([n; 3], data),
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 1.0),
+            2.0,
+            Color::new(0.8, 0.8, 0.8),
+        );
+
+        let r = Ray::new(Point3::new(-1.0, 0.5, 0.5), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let mut covered = 0.0;
+        medium.traverse(
+            &r,
+            &Interval::new(0.0, f64::INFINITY),
+            |t0, t1, majorant| {
+                for i in 0..=100 {
+                    let t = t0 + (t1 - t0) * i as f64 / 100.0;
+                    let density = medium.density(&r.at(t));
+                    assert!(
+                        density <= majorant + 1e-12,
+                        "{density} > {majorant} at t = {t}"
+                    );
+                }
+                covered += t1 - t0;
+                true
+            },
+        );
+        assert!((covered - 1.0).abs() < 1e-9);
+    }
+
+    // a linear ramp along x, 0 to 2 over the unit cube, in 20^3 voxels
+    fn ramp() -> GridMedium {
+        let n = 20;
+        let data = (0..n * n * n)
+            .map(|i| 2.0 * ((i % n) as f64 + 0.5) / n as f64)
+            .collect();
+        GridMedium::new(
+            DensityGrid::new([n; 3], data),
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 1.0),
+            1.0,
+            Color::new(0.8, 0.8, 0.8),
+        )
+    }
+
+    #[test]
+    fn tracking_matches_the_optical_depth() {
+        let medium = ramp();
+        let mut rng = PCG32RNG::default();
+        // along x the optical depth is the integral of the ramp, which is
+        // one up to the clamped half voxels at either end
+        let r = Ray::new(Point3::new(-1.0, 0.3, 0.6), Vec3::new(2.0, 0.0, 0.0), 0.0);
+        let ray_t = Interval::new(0.0, f64::INFINITY);
+        let expected = (-1.0f64).exp();
+
+        let n = 20000;
+        let mut tr = 0.0;
+        let mut escaped = 0;
+        for _ in 0..n {
+            tr += medium.transmittance(&r, &ray_t, &mut rng) / n as f64;
+            match medium.hit(&r, &ray_t, &mut rng) {
+                Some(rec) => assert!(rec.p.x() >= 0.0 && rec.p.x() <= 1.0),
+                None => escaped += 1,
+            }
+        }
+        assert!((tr - expected).abs() < 0.01, "{tr}");
+        assert!((escaped as f64 / n as f64 - expected).abs() < 0.01);
+
+        // the medium ends at the bounding box
+        let outside = Ray::new(Point3::new(-1.0, 2.0, 0.5), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert_eq!(medium.transmittance(&outside, &ray_t, &mut rng), 1.0);
+        assert!(medium.hit(&outside, &ray_t, &mut rng).is_none());
+    }
+}
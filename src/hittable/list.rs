@@ -4,6 +4,7 @@ use crate::{
     hittable::{HitRecord, Hittable},
     interval::Interval,
     ray::Ray,
+    util::rand::PCG32RNG,
 };
 
 #[derive(Default)]
@@ -29,12 +30,12 @@ impl HittableList {
 }
 
 impl Hittable for HittableList {
-    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rng: &mut PCG32RNG) -> Option<HitRecord> {
         let mut closest_so_far = ray_t.max;
         let mut rec = None;
 
         for obj in &self.objects {
-            if let Some(t_rec) = obj.hit(r, &Interval::new(ray_t.min, closest_so_far)) {
+            if let Some(t_rec) = obj.hit(r, &Interval::new(ray_t.min, closest_so_far), rng) {
                 closest_so_far = t_rec.t;
                 rec = Some(t_rec)
             }
@@ -42,4 +43,15 @@ impl Hittable for HittableList {
 
         rec
     }
+
+    fn transmittance(&self, r: &Ray, ray_t: &Interval, rng: &mut PCG32RNG) -> f64 {
+        let mut tr = 1.0;
+        for obj in &self.objects {
+            tr *= obj.transmittance(r, ray_t, rng);
+            if tr <= 0.0 {
+                break;
+            }
+        }
+        tr
+    }
 }
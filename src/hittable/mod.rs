@@ -1,5 +1,10 @@
+pub mod constant_medium;
+pub mod grid_medium;
 pub mod list;
+pub mod quad;
 pub mod sphere;
+pub mod transform;
+pub mod triangle;
 
 use std::rc::Rc;
 
@@ -7,14 +12,27 @@ use crate::{
     interval::Interval,
     material::Material,
     ray::Ray,
+    util::rand::PCG32RNG,
     vec3::{Point3, Vec3},
 };
 
+#[derive(Clone)]
 pub struct HitRecord {
+    // shading normal, possibly perturbed by a normal or bump map
     pub normal: Vec3,
+    // normal of the actual surface, on the same side as `normal`. decides
+    // which side of the surface a direction lies on
+    pub geometric_normal: Vec3,
     pub p: Point3,
     pub mat: Rc<dyn Material>,
     pub t: f64,
+    // surface coordinates for texture lookups
+    pub u: f64,
+    pub v: f64,
+    // partial derivatives of p with respect to u and v, zero when the
+    // surface has no parameterization
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
     pub front_face: bool,
 }
 
@@ -27,9 +45,38 @@ impl HitRecord {
         } else {
             -*outward_normal
         };
+        self.geometric_normal = self.normal;
     }
+
+    // replaces the shading normal only, keeping the geometric normal and the
+    // face orientation of the hit
+    pub fn set_shading_normal(&mut self, outward_normal: &Vec3) {
+        self.normal = if self.front_face {
+            *outward_normal
+        } else {
+            -*outward_normal
+        };
+    }
+}
+
+// alpha test for a candidate hit on `mat`: whether the hit is kept
+fn alpha_test(mat: &dyn Material, u: f64, v: f64, p: &Point3, rng: &mut PCG32RNG) -> bool {
+    let alpha = mat.alpha(u, v, p);
+    alpha >= 1.0 || (alpha > 0.0 && rng.random_f64() < alpha)
 }
 
 pub trait Hittable {
-    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord>;
+    fn hit(&self, r: &Ray, ray_t: &Interval, rng: &mut PCG32RNG) -> Option<HitRecord>;
+
+    // fraction of light that makes it along `r` over `ray_t`, for shadow rays.
+    // surfaces are opaque, up to their alpha test, which makes this a
+    // stochastic estimate for partially transparent ones; participating media
+    // override this
+    fn transmittance(&self, r: &Ray, ray_t: &Interval, rng: &mut PCG32RNG) -> f64 {
+        if self.hit(r, ray_t, rng).is_some() {
+            0.0
+        } else {
+            1.0
+        }
+    }
 }
@@ -0,0 +1,106 @@
+use std::rc::Rc;
+
+use crate::{
+    hittable::{HitRecord, Hittable, alpha_test},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    util::rand::PCG32RNG,
+    vec3::{Point3, Vec3},
+};
+
+// parallelogram with corner `q` and edges `u` and `v`
+pub struct Quad {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    // cached plane data: unit normal, plane offset and the vector used to
+    // express hit points in the (u, v) basis
+    normal: Vec3,
+    d: f64,
+    w: Vec3,
+    mat: Rc<dyn Material>,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, mat: Rc<dyn Material>) -> Self {
+        let n = Vec3::cross(&u, &v);
+        let normal = n.unit_vector();
+        Self {
+            q,
+            u,
+            v,
+            normal,
+            d: Vec3::dot(&normal, &q),
+            w: n / n.len_squared(),
+            mat,
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rng: &mut PCG32RNG) -> Option<HitRecord> {
+        let denom = Vec3::dot(&self.normal, r.direction());
+
+        // parallel to the plane
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - Vec3::dot(&self.normal, r.origin())) / denom;
+        if !ray_t.surrounds(t) {
+            return None;
+        }
+
+        // planar coordinates of the hit point along the edges
+        let p = r.at(t);
+        let planar_hitpt = p - self.q;
+        let alpha = Vec3::dot(&self.w, &Vec3::cross(&planar_hitpt, &self.v));
+        let beta = Vec3::dot(&self.w, &Vec3::cross(&self.u, &planar_hitpt));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+        if !alpha_test(self.mat.as_ref(), alpha, beta, &p, rng) {
+            return None;
+        }
+
+        let mut rec = HitRecord {
+            t,
+            normal: Vec3::default(),
+            geometric_normal: Vec3::default(),
+            p,
+            mat: self.mat.clone(),
+            u: alpha,
+            v: beta,
+            dpdu: self.u,
+            dpdv: self.v,
+            front_face: false,
+        };
+        rec.set_face_normal(r, &self.normal);
+
+        Some(rec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, material::Lambertian};
+
+    #[test]
+    fn interval_bounds_are_exclusive() {
+        let quad = Quad::new(
+            Point3::new(-1.0, -1.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        let mut rng = PCG32RNG::default();
+        let r = Ray::new(Point3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+
+        let rec = quad.hit(&r, &Interval::new(0.0, 2.0), &mut rng).unwrap();
+        assert_eq!(rec.t, 1.0);
+        assert!(quad.hit(&r, &Interval::new(1.0, 2.0), &mut rng).is_none());
+        assert!(quad.hit(&r, &Interval::new(0.0, 1.0), &mut rng).is_none());
+    }
+}
@@ -1,15 +1,18 @@
-use std::rc::Rc;
+use std::{f64::consts::PI, rc::Rc};
 
 use crate::{
-    hittable::{HitRecord, Hittable},
+    hittable::{HitRecord, Hittable, alpha_test},
     interval::Interval,
     material::Material,
+    onb::Onb,
     ray::Ray,
+    util::rand::PCG32RNG,
     vec3::{Point3, Vec3},
 };
 
 pub struct Sphere {
-    center: Point3,
+    // linear path of the center over time; static spheres have a zero direction
+    center: Ray,
     radius: f64,
     mat: Rc<dyn Material>,
 }
@@ -17,16 +20,61 @@ pub struct Sphere {
 impl Sphere {
     pub fn new(center: Point3, radius: f64, mat: Rc<dyn Material>) -> Self {
         Self {
-            center,
+            center: Ray::new(center, Vec3::default(), 0.0),
             mat,
             radius: f64::max(0.0, radius),
         }
     }
+
+    // sphere moving from center1 at time 0 to center2 at time 1
+    pub fn new_moving(
+        center1: Point3,
+        center2: Point3,
+        radius: f64,
+        mat: Rc<dyn Material>,
+    ) -> Self {
+        Self {
+            center: Ray::new(center1, center2 - center1, 0.0),
+            mat,
+            radius: f64::max(0.0, radius),
+        }
+    }
+
+    // p: a given point on the unit sphere centered at the origin
+    // u: angle around the y axis from x = -1, in [0, 1]
+    // v: angle from y = -1 to y = +1, in [0, 1]
+    fn get_sphere_uv(p: &Point3) -> (f64, f64) {
+        let theta = (-p.y()).acos();
+        let phi = (-p.z()).atan2(p.x()) + PI;
+
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    // derivatives of the surface point with respect to the uv coordinates of
+    // `get_sphere_uv`, at unit normal `n`. dpdu x dpdv points outwards
+    fn get_sphere_tangents(&self, n: &Vec3) -> (Vec3, Vec3) {
+        let sin_theta = (n.x() * n.x() + n.z() * n.z()).sqrt();
+        let dpdu = 2.0 * PI * self.radius * Vec3::new(n.z(), 0.0, -n.x());
+        if sin_theta < 1e-8 {
+            // u is degenerate at the poles, any tangent frame will do
+            let onb = Onb::new(n);
+            return (*onb.u(), *onb.v());
+        }
+        let dpdv = PI
+            * self.radius
+            * Vec3::new(
+                -n.x() * n.y() / sin_theta,
+                sin_theta,
+                -n.y() * n.z() / sin_theta,
+            );
+        (dpdu, dpdv)
+    }
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
-        let oc = self.center - *r.origin();
+    fn hit(&self, r: &Ray, ray_t: &Interval, rng: &mut PCG32RNG) -> Option<HitRecord> {
+        let current_center = self.center.at(r.time());
+        let oc = current_center - *r.origin();
         let a = r.direction().len_squared();
         // h = -2b
         let h = Vec3::dot(r.direction(), &oc);
@@ -38,24 +86,67 @@ impl Hittable for Sphere {
         }
         let sqrtd = f64::sqrt(discriminant);
 
-        let mut root = (h - sqrtd) / a;
-        if !ray_t.surrounds(root) {
-            root = (h + sqrtd) / a;
+        // the far root is still a candidate when the near one is cut out
+        for root in [(h - sqrtd) / a, (h + sqrtd) / a] {
             if !ray_t.surrounds(root) {
-                return None;
+                continue;
             }
+
+            let p = r.at(root);
+            let outward_normal = (p - current_center) / self.radius;
+            let (u, v) = Self::get_sphere_uv(&outward_normal);
+            if !alpha_test(self.mat.as_ref(), u, v, &p, rng) {
+                continue;
+            }
+
+            let mut rec = HitRecord {
+                t: root,
+                normal: Vec3::default(),
+                geometric_normal: Vec3::default(),
+                p,
+                mat: self.mat.clone(),
+                u,
+                v,
+                dpdu: Vec3::default(),
+                dpdv: Vec3::default(),
+                front_face: false,
+            };
+            rec.set_face_normal(r, &outward_normal);
+            (rec.dpdu, rec.dpdv) = self.get_sphere_tangents(&outward_normal);
+
+            return Some(rec);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, material::Lambertian};
+
+    #[test]
+    fn moving_sphere_is_hit_where_it_is_at_the_ray_time() {
+        let mat = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let sphere = Sphere::new_moving(
+            Point3::new(0.0, 0.0, -5.0),
+            Point3::new(2.0, 0.0, -5.0),
+            0.5,
+            mat,
+        );
+        let mut rng = PCG32RNG::default();
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+
+        for (time, x) in [(0.0, 0.0), (0.5, 1.0), (1.0, 2.0)] {
+            let r = Ray::new(Point3::new(x, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0), time);
+            let rec = sphere.hit(&r, &ray_t, &mut rng).unwrap();
+            assert!((rec.t - 4.5).abs() < 1e-12);
+            assert!((rec.normal - Vec3::new(0.0, 0.0, 1.0)).len() < 1e-12);
         }
 
-        let mut rec = HitRecord {
-            t: root,
-            normal: Vec3::default(),
-            p: r.at(root),
-            mat: self.mat.clone(),
-            front_face: false,
-        };
-        let outward_normal = (rec.p - self.center) / self.radius;
-        rec.set_face_normal(r, &outward_normal);
-
-        Some(rec)
+        // the sphere has left the start of its path by the end of the shutter
+        let r = Ray::new(Point3::default(), Vec3::new(0.0, 0.0, -1.0), 1.0);
+        assert!(sphere.hit(&r, &ray_t, &mut rng).is_none());
     }
 }
@@ -0,0 +1,209 @@
+use std::{borrow::Cow, rc::Rc};
+
+use crate::{
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    mat4::Mat4,
+    quat::Quat,
+    ray::Ray,
+    util::{self, rand::PCG32RNG},
+    vec3::Vec3,
+};
+
+#[derive(Clone)]
+struct Frame {
+    object_to_world: Mat4,
+    world_to_object: Mat4,
+    // inverse transpose of object_to_world, used for normals
+    normal_to_world: Mat4,
+}
+
+impl Frame {
+    fn new(object_to_world: Mat4, world_to_object: Mat4) -> Self {
+        Self {
+            object_to_world,
+            world_to_object,
+            normal_to_world: world_to_object.transpose(),
+        }
+    }
+}
+
+// object_to_world split as translation * rotation * stretch, so each part
+// can be interpolated on its own
+struct Decomposition {
+    translation: Vec3,
+    rotation: Quat,
+    stretch: Mat4,
+}
+
+impl Decomposition {
+    fn new(object_to_world: &Mat4) -> Self {
+        let (translation, rotation, stretch) = object_to_world
+            .decompose()
+            .expect("instance transform must be invertible");
+        Self {
+            translation,
+            rotation,
+            stretch,
+        }
+    }
+}
+
+// places a shared object in the world through an affine transform, so one
+// mesh or sphere cluster can be instanced many times
+pub struct Transform {
+    object: Rc<dyn Hittable>,
+    start: Frame,
+    // transforms at times 0 and 1 for animated instances
+    motion: Option<(Decomposition, Decomposition)>,
+}
+
+impl Transform {
+    pub fn new(object: Rc<dyn Hittable>, object_to_world: Mat4) -> Self {
+        let world_to_object = object_to_world
+            .inverse()
+            .expect("instance transform must be invertible");
+        Self {
+            object,
+            start: Frame::new(object_to_world, world_to_object),
+            motion: None,
+        }
+    }
+
+    // instance moving from `start` at time 0 to `end` at time 1. the
+    // translations and stretches are interpolated linearly and the
+    // rotations along the shorter arc at constant angular velocity
+    pub fn animated(object: Rc<dyn Hittable>, start: Mat4, end: Mat4) -> Self {
+        let motion = (Decomposition::new(&start), Decomposition::new(&end));
+        Self {
+            start: Self::new(object.clone(), start).start,
+            object,
+            motion: Some(motion),
+        }
+    }
+
+    fn to_object(frame: &Frame, r: &Ray) -> Ray {
+        // the direction is left unnormalized so that `t` means the same
+        // thing in both spaces
+        Ray::new(
+            frame.world_to_object.transform_point(r.origin()),
+            frame.world_to_object.transform_vector(r.direction()),
+            r.time(),
+        )
+        .with_wavelengths(r.wavelengths().copied())
+    }
+
+    // the instance's frame at `time`. None if the interpolated stretch is
+    // singular, e.g. halfway between a shape and its mirror image
+    fn frame_at(&self, time: f64) -> Option<Cow<'_, Frame>> {
+        let Some((a, b)) = &self.motion else {
+            return Some(Cow::Borrowed(&self.start));
+        };
+
+        let translation = util::lerp(a.translation, b.translation, time);
+        let rotation = Quat::slerp(&a.rotation, &b.rotation, time).to_mat4();
+        let stretch = util::lerp(a.stretch, b.stretch, time);
+
+        // the inverse only needs the stretch inverted, the rotation is
+        // orthogonal
+        let world_to_object =
+            stretch.inverse()? * rotation.transpose() * Mat4::translate(&-translation);
+        let object_to_world = Mat4::translate(&translation) * rotation * stretch;
+        Some(Cow::Owned(Frame::new(object_to_world, world_to_object)))
+    }
+}
+
+impl Hittable for Transform {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rng: &mut PCG32RNG) -> Option<HitRecord> {
+        let frame = self.frame_at(r.time())?;
+        let object_r = Self::to_object(&frame, r);
+
+        let mut rec = self.object.hit(&object_r, ray_t, rng)?;
+
+        // the inverse transpose preserves the sign of dot(direction, normal),
+        // so front_face carries over unchanged
+        rec.p = frame.object_to_world.transform_point(&rec.p);
+        rec.normal = frame
+            .normal_to_world
+            .transform_vector(&rec.normal)
+            .unit_vector();
+        rec.geometric_normal = frame
+            .normal_to_world
+            .transform_vector(&rec.geometric_normal)
+            .unit_vector();
+        rec.dpdu = frame.object_to_world.transform_vector(&rec.dpdu);
+        rec.dpdv = frame.object_to_world.transform_vector(&rec.dpdv);
+
+        Some(rec)
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: &Interval, rng: &mut PCG32RNG) -> f64 {
+        // a degenerate instance blocks nothing
+        let Some(frame) = self.frame_at(r.time()) else {
+            return 1.0;
+        };
+        self.object
+            .transmittance(&Self::to_object(&frame, r), ray_t, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, hittable::sphere::Sphere, material::Lambertian, vec3::Point3};
+
+    fn sphere() -> Rc<dyn Hittable> {
+        Rc::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        ))
+    }
+
+    #[test]
+    fn half_turn_interpolates_through_a_rotation() {
+        // the element-wise blend of these matrices is singular at t = 0.5
+        let axis = Vec3::new(0.0, 1.0, 0.0);
+        let end = Mat4::translate(&Vec3::new(2.0, 0.0, 0.0)) * Mat4::rotate(&axis, 180.0);
+        let instance = Transform::animated(sphere(), Mat4::IDENTITY, end);
+        let mut rng = PCG32RNG::default();
+
+        let r = Ray::new(Point3::new(1.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.5);
+        let rec = instance
+            .hit(&r, &Interval::new(0.001, f64::INFINITY), &mut rng)
+            .expect("the moving sphere is centered on the ray at t = 0.5");
+        assert!((rec.t - 4.0).abs() < 1e-9);
+        assert!((rec.p - Point3::new(1.0, 0.0, -1.0)).len() < 1e-9);
+    }
+
+    #[test]
+    fn scale_is_interpolated_linearly() {
+        let end = Mat4::scale(&Vec3::new(3.0, 3.0, 3.0));
+        let instance = Transform::animated(sphere(), Mat4::IDENTITY, end);
+        let mut rng = PCG32RNG::default();
+
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.5);
+        let rec = instance
+            .hit(&r, &Interval::new(0.001, f64::INFINITY), &mut rng)
+            .unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn singular_blend_misses_instead_of_panicking() {
+        let end = Mat4::scale(&Vec3::new(-1.0, 1.0, 1.0));
+        let instance = Transform::animated(sphere(), Mat4::IDENTITY, end);
+        let mut rng = PCG32RNG::default();
+
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.5);
+        assert!(
+            instance
+                .hit(&r, &Interval::new(0.001, f64::INFINITY), &mut rng)
+                .is_none()
+        );
+        assert_eq!(
+            instance.transmittance(&r, &Interval::UNIVERSE, &mut rng),
+            1.0
+        );
+    }
+}
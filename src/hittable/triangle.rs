@@ -0,0 +1,167 @@
+use std::rc::Rc;
+
+use crate::{
+    hittable::{HitRecord, Hittable, alpha_test},
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    util::rand::PCG32RNG,
+    vec3::{Point3, Vec3},
+};
+
+// single triangle with optional per-vertex texture coordinates
+pub struct Triangle {
+    a: Point3,
+    ab: Vec3,
+    ac: Vec3,
+    normal: Vec3,
+    uv: [[f64; 2]; 3],
+    // surface derivatives implied by the vertex uvs
+    dpdu: Vec3,
+    dpdv: Vec3,
+    mat: Rc<dyn Material>,
+}
+
+impl Triangle {
+    // the outward normal follows the counter-clockwise winding of a, b, c
+    pub fn new(a: Point3, b: Point3, c: Point3, mat: Rc<dyn Material>) -> Self {
+        let ab = b - a;
+        let ac = c - a;
+        Self {
+            a,
+            ab,
+            ac,
+            normal: Vec3::cross(&ab, &ac).unit_vector(),
+            uv: [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+            dpdu: ab,
+            dpdv: ac,
+            mat,
+        }
+    }
+
+    pub fn with_uvs(mut self, uv_a: [f64; 2], uv_b: [f64; 2], uv_c: [f64; 2]) -> Self {
+        self.uv = [uv_a, uv_b, uv_c];
+
+        // solve [ab ac] = [dpdu dpdv] * [duv_ab duv_ac]
+        let (du1, dv1) = (uv_b[0] - uv_a[0], uv_b[1] - uv_a[1]);
+        let (du2, dv2) = (uv_c[0] - uv_a[0], uv_c[1] - uv_a[1]);
+        let det = du1 * dv2 - dv1 * du2;
+        if det.abs() < 1e-12 {
+            // degenerate uvs: fall back to the edges
+            self.dpdu = self.ab;
+            self.dpdv = self.ac;
+        } else {
+            self.dpdu = (dv2 * self.ab - dv1 * self.ac) / det;
+            self.dpdv = (du1 * self.ac - du2 * self.ab) / det;
+        }
+        self
+    }
+}
+
+impl Hittable for Triangle {
+    // moller-trumbore
+    fn hit(&self, r: &Ray, ray_t: &Interval, rng: &mut PCG32RNG) -> Option<HitRecord> {
+        let pvec = Vec3::cross(r.direction(), &self.ac);
+        let det = Vec3::dot(&self.ab, &pvec);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+
+        let tvec = *r.origin() - self.a;
+        let b1 = Vec3::dot(&tvec, &pvec) * inv_det;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+
+        let qvec = Vec3::cross(&tvec, &self.ab);
+        let b2 = Vec3::dot(r.direction(), &qvec) * inv_det;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+
+        let t = Vec3::dot(&self.ac, &qvec) * inv_det;
+        if !ray_t.surrounds(t) {
+            return None;
+        }
+
+        let b0 = 1.0 - b1 - b2;
+        let [uv_a, uv_b, uv_c] = self.uv;
+        let p = r.at(t);
+        let u = b0 * uv_a[0] + b1 * uv_b[0] + b2 * uv_c[0];
+        let v = b0 * uv_a[1] + b1 * uv_b[1] + b2 * uv_c[1];
+        if !alpha_test(self.mat.as_ref(), u, v, &p, rng) {
+            return None;
+        }
+
+        let mut rec = HitRecord {
+            t,
+            normal: Vec3::default(),
+            geometric_normal: Vec3::default(),
+            p,
+            mat: self.mat.clone(),
+            u,
+            v,
+            dpdu: self.dpdu,
+            dpdv: self.dpdv,
+            front_face: false,
+        };
+        rec.set_face_normal(r, &self.normal);
+
+        Some(rec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, material::Lambertian};
+
+    fn triangle() -> Triangle {
+        Triangle::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(2.0, 0.0, 0.0),
+            Point3::new(0.0, 2.0, 0.0),
+            Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn hit_interpolates_the_vertex_uvs() {
+        let tri = triangle().with_uvs([0.0, 0.0], [0.5, 0.0], [0.5, 1.0]);
+        let mut rng = PCG32RNG::default();
+        let r = Ray::new(Point3::new(0.5, 1.0, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let rec = tri
+            .hit(&r, &Interval::new(0.0, f64::INFINITY), &mut rng)
+            .unwrap();
+
+        assert_eq!(rec.t, 1.0);
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+        // barycentrics (0.25, 0.25, 0.5)
+        assert!((rec.u - 0.375).abs() < 1e-12 && (rec.v - 0.5).abs() < 1e-12);
+
+        // u grows along ab and v along bc, where u is constant
+        assert!((rec.dpdu - Vec3::new(4.0, 0.0, 0.0)).len() < 1e-12);
+        assert!((rec.dpdv - Vec3::new(-2.0, 2.0, 0.0)).len() < 1e-12);
+    }
+
+    #[test]
+    fn rays_outside_the_edges_miss() {
+        let tri = triangle();
+        let mut rng = PCG32RNG::default();
+        let ray_t = Interval::new(0.0, f64::INFINITY);
+        let down = Vec3::new(0.0, 0.0, -1.0);
+        for (x, y) in [(1.5, 1.5), (-0.1, 0.5), (0.5, -0.1)] {
+            let r = Ray::new(Point3::new(x, y, 1.0), down, 0.0);
+            assert!(tri.hit(&r, &ray_t, &mut rng).is_none());
+        }
+
+        // from below the normal is flipped
+        let up = Ray::new(Point3::new(0.5, 0.5, -1.0), -down, 0.0);
+        let rec = tri.hit(&up, &ray_t, &mut rng).unwrap();
+        assert!(!rec.front_face);
+        assert_eq!(rec.normal, down);
+        assert_eq!(rec.geometric_normal, down);
+    }
+}
@@ -0,0 +1,394 @@
+use std::{fs, io, path::Path};
+
+use crate::util;
+
+// luminous intensity distribution of a luminaire, read from an IES LM-63
+// photometric data file (1995 and 2002 revisions). only type C photometry,
+// used by nearly all architectural luminaires, is supported
+//
+// vertical angles are measured from the nadir (0) to the zenith (180) and
+// horizontal angles counterclockwise around the vertical axis
+pub struct IesProfile {
+    vertical: Vec<f64>,
+    horizontal: Vec<f64>,
+    // candela[h][v]
+    candela: Vec<Vec<f64>>,
+    max_candela: f64,
+    mean_candela: f64,
+}
+
+impl IesProfile {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        // keyword lines may use any 8-bit encoding
+        let text: String = fs::read(path)?.into_iter().map(char::from).collect();
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        // the version and keyword lines end at the mandatory TILT line
+        let mut lines = text.lines();
+        let tilt = lines
+            .by_ref()
+            .map(str::trim)
+            .find_map(|l| l.strip_prefix("TILT="))
+            .ok_or_else(|| invalid("missing ies TILT line"))?
+            .trim()
+            .to_string();
+
+        let rest: Vec<&str> = lines.collect();
+        let mut numbers = Numbers {
+            tokens: rest
+                .iter()
+                .flat_map(|l| l.split(|c: char| c.is_whitespace() || c == ','))
+                .filter(|t| !t.is_empty())
+                .collect(),
+            pos: 0,
+        };
+
+        // lamp tilt multipliers only apply to lamps burning off-vertical and
+        // are skipped
+        if tilt == "INCLUDE" {
+            let _geometry = numbers.next()?;
+            let count = numbers.count(2)?;
+            for _ in 0..2 * count {
+                numbers.next()?;
+            }
+        }
+
+        let _lamps = numbers.next()?;
+        let _lumens_per_lamp = numbers.next()?;
+        let multiplier = numbers.next()?;
+        let n_vertical = numbers.count(1)?;
+        let n_horizontal = numbers.count(1)?;
+        let photometric_type = numbers.next()?;
+        let _units = numbers.next()?;
+        let _dimensions = [numbers.next()?, numbers.next()?, numbers.next()?];
+        let ballast_factor = numbers.next()?;
+        let _ballast_lamp_factor = numbers.next()?;
+        let _input_watts = numbers.next()?;
+
+        if photometric_type != 1.0 {
+            return Err(invalid("only type C ies photometry is supported"));
+        }
+        if n_vertical == 0 || n_horizontal == 0 {
+            return Err(invalid("bad ies angle counts"));
+        }
+        // the angles and the candela values must all be present
+        let values = n_vertical
+            .checked_mul(n_horizontal)
+            .and_then(|n| n.checked_add(n_vertical + n_horizontal));
+        if values.is_none_or(|n| n > numbers.remaining()) {
+            return Err(invalid("truncated ies data"));
+        }
+
+        let vertical = (0..n_vertical)
+            .map(|_| numbers.next())
+            .collect::<io::Result<Vec<_>>>()?;
+        let horizontal = (0..n_horizontal)
+            .map(|_| numbers.next())
+            .collect::<io::Result<Vec<_>>>()?;
+        let sorted = |a: &[f64]| a.windows(2).all(|w| w[0] < w[1]);
+        if !sorted(&vertical) || !sorted(&horizontal) {
+            return Err(invalid("ies angles must be increasing"));
+        }
+
+        let scale = multiplier * ballast_factor;
+        let mut candela = Vec::with_capacity(n_horizontal);
+        for _ in 0..n_horizontal {
+            let row = (0..n_vertical)
+                .map(|_| numbers.next().map(|c| (c * scale).max(0.0)))
+                .collect::<io::Result<Vec<_>>>()?;
+            candela.push(row);
+        }
+
+        let max_candela = candela.iter().flatten().fold(0.0, |m: f64, &c| m.max(c));
+        let mut profile = Self {
+            vertical,
+            horizontal,
+            candela,
+            max_candela,
+            mean_candela: 0.0,
+        };
+        profile.mean_candela = profile.integrate_mean();
+        Ok(profile)
+    }
+
+    pub fn max_candela(&self) -> f64 {
+        self.max_candela
+    }
+
+    // intensity at vertical angle `theta` and horizontal angle `phi`, in
+    // degrees, bilinearly interpolated. zero outside the measured vertical
+    // range
+    pub fn candela(&self, theta: f64, phi: f64) -> f64 {
+        let Some((v, tv)) = locate(&self.vertical, theta) else {
+            return 0.0;
+        };
+        let Some((h0, h1, th)) = self.locate_horizontal(phi) else {
+            return 0.0;
+        };
+
+        let at = |h: usize, v: usize| self.candela[h][v.min(self.vertical.len() - 1)];
+        let c0 = (1.0 - tv) * at(h0, v) + tv * at(h0, v + 1);
+        let c1 = (1.0 - tv) * at(h1, v) + tv * at(h1, v + 1);
+        (1.0 - th) * c0 + th * c1
+    }
+
+    // average intensity over the sphere, 1 / 4pi of the luminous flux
+    pub fn mean_candela(&self) -> f64 {
+        self.mean_candela
+    }
+
+    fn integrate_mean(&self) -> f64 {
+        const STEPS: usize = 180;
+        let mut sum = 0.0;
+        let mut weight = 0.0;
+        for i in 0..STEPS {
+            let theta = (i as f64 + 0.5) * 180.0 / STEPS as f64;
+            let sin_theta = util::deg_to_rad(theta).sin();
+            for j in 0..2 * STEPS {
+                let phi = (j as f64 + 0.5) * 180.0 / STEPS as f64;
+                sum += self.candela(theta, phi) * sin_theta;
+            }
+            weight += sin_theta * (2 * STEPS) as f64;
+        }
+        sum / weight
+    }
+
+    // measured horizontal planes on either side of `phi`, in degrees, and
+    // the fractional position between them. the lm-63 symmetry is implied by
+    // the first and last angles: a single plane is rotationally symmetric, a
+    // last angle of 90 symmetric in each quadrant, 180 symmetric about the
+    // 0-180 degree plane and 90 to 270 about the 90-270 degree plane. a full
+    // circle that stops short of 360 wraps around to its first plane
+    fn locate_horizontal(&self, phi: f64) -> Option<(usize, usize, f64)> {
+        let n = self.horizontal.len();
+        if n == 1 {
+            return Some((0, 0, 0.0));
+        }
+        let first = self.horizontal[0];
+        let last = self.horizontal[n - 1];
+        // angles in files are often rounded
+        let near = |a: f64, b: f64| (a - b).abs() < 1e-3;
+
+        let mut phi = phi.rem_euclid(360.0);
+        if near(first, 90.0) && near(last, 270.0) {
+            if phi < 90.0 {
+                phi = 180.0 - phi;
+            } else if phi > 270.0 {
+                phi = 540.0 - phi;
+            }
+            phi = phi.clamp(first, last);
+        } else if near(last, 90.0) || near(last, 180.0) {
+            if phi > 180.0 {
+                phi = 360.0 - phi;
+            }
+            if near(last, 90.0) && phi > 90.0 {
+                phi = 180.0 - phi;
+            }
+            phi = phi.min(last);
+        } else {
+            if phi < first {
+                phi += 360.0;
+            }
+            if phi > last {
+                let t = (phi - last) / (first + 360.0 - last);
+                return Some((n - 1, 0, t.clamp(0.0, 1.0)));
+            }
+        }
+
+        locate(&self.horizontal, phi).map(|(h, t)| (h, h + 1, t))
+    }
+}
+
+// numeric tokens following the TILT line
+struct Numbers<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl Numbers<'_> {
+    fn next(&mut self) -> io::Result<f64> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let token = self
+            .tokens
+            .get(self.pos)
+            .ok_or_else(|| invalid("truncated ies data"))?;
+        self.pos += 1;
+        token
+            .parse::<f64>()
+            .ok()
+            .filter(|x| x.is_finite())
+            .ok_or_else(|| invalid("bad number in ies data"))
+    }
+
+    fn remaining(&self) -> usize {
+        self.tokens.len() - self.pos
+    }
+
+    // a count of items of `size` values each that must still follow
+    fn count(&mut self, size: usize) -> io::Result<usize> {
+        let n = self.next()?;
+        if n < 0.0 || n.fract() != 0.0 || n * size as f64 > self.remaining() as f64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "bad count in ies data",
+            ));
+        }
+        Ok(n as usize)
+    }
+}
+
+// segment of the increasing `angles` containing `x` and the fractional
+// position in it. None outside the range
+fn locate(angles: &[f64], x: f64) -> Option<(usize, f64)> {
+    let first = angles[0];
+    let last = *angles.last().unwrap();
+    if angles.len() == 1 {
+        return (x == first).then_some((0, 0.0));
+    }
+    // a little slack for angles that were rounded in the file
+    if x < first - 1e-6 || x > last + 1e-6 {
+        return None;
+    }
+
+    let i = angles
+        .partition_point(|&a| a <= x)
+        .clamp(1, angles.len() - 1)
+        - 1;
+    let t = ((x - angles[i]) / (angles[i + 1] - angles[i])).clamp(0.0, 1.0);
+    Some((i, t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_IES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/ies/test.ies");
+
+    fn header(counts: &str) -> String {
+        format!("IESNA:LM-63-2002\nTILT=NONE\n1 1000 1 {counts} 1 2 0 0 0\n1 1 10\n")
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn load_test_profile() {
+        let ies = IesProfile::load(TEST_IES).unwrap();
+        assert_eq!(ies.max_candela(), 1200.0);
+
+        // measured planes at 0, 45 and 90 degrees
+        assert_close(ies.candela(0.0, 0.0), 1200.0);
+        assert_close(ies.candela(30.0, 0.0), 900.0);
+        assert_close(ies.candela(30.0, 45.0), 675.0);
+        assert_close(ies.candela(30.0, 90.0), 379.7);
+        assert_close(ies.candela(90.0, 0.0), 0.0);
+
+        // bilinear interpolation
+        assert_close(ies.candela(32.5, 0.0), 0.5 * (900.0 + 805.2));
+        assert_close(ies.candela(30.0, 67.5), 0.5 * (675.0 + 379.7));
+
+        // quadrant symmetry
+        for phi in [135.0, 225.0, 315.0, -45.0] {
+            assert_close(ies.candela(30.0, phi), 675.0);
+        }
+        assert_close(ies.candela(30.0, 180.0), 900.0);
+        assert_close(ies.candela(30.0, 270.0), 379.7);
+
+        // nothing above the horizon
+        assert_close(ies.candela(120.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn mean_candela_of_test_profile() {
+        // the planes follow 1200 cos^n(theta) with n = 2, 4 and 8, so the
+        // flux is 2pi * 1200 * (1/4 / 3 + 1/2 / 5 + 1/4 / 9)
+        let ies = IesProfile::load(TEST_IES).unwrap();
+        let expected = 600.0 * (0.25 / 3.0 + 0.5 / 5.0 + 0.25 / 9.0);
+        let mean = ies.mean_candela();
+        assert!((mean - expected).abs() / expected < 0.01, "{mean}");
+    }
+
+    #[test]
+    fn rotationally_symmetric_profile() {
+        let text = header("3 1") + "0 45 90\n0\n100 50 0\n";
+        let ies = IesProfile::parse(&text).unwrap();
+        for phi in [0.0, 77.0, 200.0] {
+            assert_close(ies.candela(22.5, phi), 75.0);
+        }
+    }
+
+    #[test]
+    fn symmetry_follows_the_last_horizontal_angle() {
+        // quadrant symmetric, with the last angle rounded in the file
+        let text = header("2 2") + "0 90\n0 89.9999\n100 0\n200 0\n";
+        let ies = IesProfile::parse(&text).unwrap();
+        assert_close(ies.candela(0.0, 90.0), 200.0);
+        assert_close(ies.candela(0.0, 270.0), 200.0);
+        for phi in [135.0, 225.0, 315.0] {
+            assert_close(ies.candela(0.0, phi), ies.candela(0.0, 45.0));
+        }
+
+        // symmetric about the 0-180 degree plane
+        let text = header("2 3") + "0 90\n0 90 180\n100 0\n200 0\n300 0\n";
+        let ies = IesProfile::parse(&text).unwrap();
+        assert_close(ies.candela(0.0, 225.0), 250.0);
+        assert_close(ies.candela(0.0, 270.0), 200.0);
+
+        // symmetric about the 90-270 degree plane
+        let text = header("2 3") + "0 90\n90 180 270\n100 0\n200 0\n300 0\n";
+        let ies = IesProfile::parse(&text).unwrap();
+        assert_close(ies.candela(0.0, 45.0), 150.0);
+        assert_close(ies.candela(0.0, 0.0), 200.0);
+        assert_close(ies.candela(0.0, 315.0), 250.0);
+    }
+
+    #[test]
+    fn full_circle_wraps_past_the_last_horizontal_angle() {
+        let text = header("2 4") + "0 90\n0 90 180 270\n100 0\n200 0\n300 0\n400 0\n";
+        let ies = IesProfile::parse(&text).unwrap();
+        assert_close(ies.candela(0.0, 270.0), 400.0);
+        assert_close(ies.candela(0.0, 300.0), 300.0);
+        assert_close(ies.candela(0.0, -45.0), 250.0);
+        assert_close(ies.candela(0.0, 360.0), 100.0);
+    }
+
+    #[test]
+    fn tilt_include_is_skipped() {
+        let text = "IESNA:LM-63-2002\nTILT=INCLUDE\n1\n2\n0 90\n1 0.5\n\
+                    1 1000 2 2 1 1 2 0 0 0\n1 1 10\n0 90\n0\n10 0\n";
+        let ies = IesProfile::parse(text).unwrap();
+        assert_close(ies.candela(0.0, 0.0), 20.0);
+    }
+
+    #[test]
+    fn parse_rejects_malformed_files() {
+        for text in [
+            // no TILT line
+            "IESNA:LM-63-2002\n1 1000 1 1 1 1 2 0 0 0\n1 1 10\n0\n0\n100\n".to_string(),
+            // negative, fractional and huge counts
+            header("-1 1"),
+            header("1.5 1"),
+            header("1 1e300") + "0\n0\n100\n",
+            header("18446744073709551615 18446744073709551615") + "0\n0\n100\n",
+            header("100000 100000") + "0\n0\n100\n",
+            "TILT=INCLUDE\n1\n-5\n".to_string(),
+            "TILT=INCLUDE\n1\n1e18\n1 1\n".to_string(),
+            // type B photometry
+            "TILT=NONE\n1 1000 1 1 1 2 2 0 0 0\n1 1 10\n0\n0\n100\n".to_string(),
+            // truncated candela values and garbage
+            header("2 2") + "0 90\n0 90\n100 50\n100\n",
+            header("1 1") + "0\n0\nlots\n",
+            header("1 1") + "0\n0\nNaN\n",
+            // decreasing angles
+            header("2 1") + "90 0\n0\n100 50\n",
+        ] {
+            let err = IesProfile::parse(&text).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{text:?}");
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::{fs, io, path::Path};
+
+use crate::color::Color;
+
+// rgb image with components in [0, 1], stored row by row from the top
+//
+// loaded from binary (P6) or ascii (P3) portable pixmaps, the format the
+// renderer itself writes
+pub struct Image {
+    width: usize,
+    height: usize,
+    data: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, data: Vec<Color>) -> Self {
+        assert!(width > 0 && height > 0, "image resolution must be non-zero");
+        assert_eq!(
+            data.len(),
+            width * height,
+            "image data does not match its resolution"
+        );
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        Self::parse(&bytes)
+    }
+
+    pub fn parse(bytes: &[u8]) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let mut pos = 0;
+        let magic = next_token(bytes, &mut pos).ok_or_else(|| invalid("missing ppm header"))?;
+        let binary = match magic {
+            b"P6" => true,
+            b"P3" => false,
+            _ => return Err(invalid("not a ppm file")),
+        };
+
+        let mut header = [0usize; 3];
+        for field in &mut header {
+            *field = next_token(bytes, &mut pos)
+                .and_then(|t| std::str::from_utf8(t).ok())
+                .and_then(|t| t.parse().ok())
+                .filter(|&n| n > 0)
+                .ok_or_else(|| invalid("bad ppm header"))?;
+        }
+        let [width, height, max_value] = header;
+        if max_value > 65535 {
+            return Err(invalid("bad ppm maximum value"));
+        }
+
+        let count = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(3))
+            .ok_or_else(|| invalid("ppm resolution is too large"))?;
+        let scale = 1.0 / max_value as f64;
+        let samples: Vec<f64> = if binary {
+            // a single whitespace byte separates the header from the raster
+            let body = bytes.get(pos + 1..).unwrap_or_default();
+            let sample_size = if max_value < 256 { 1 } else { 2 };
+            if count
+                .checked_mul(sample_size)
+                .is_none_or(|size| body.len() < size)
+            {
+                return Err(invalid("ppm data does not match its resolution"));
+            }
+            body.chunks_exact(sample_size)
+                .take(count)
+                .map(|c| match c {
+                    [b] => *b as f64 * scale,
+                    [hi, lo] => u16::from_be_bytes([*hi, *lo]) as f64 * scale,
+                    _ => unreachable!(),
+                })
+                .collect()
+        } else {
+            // every ascii sample takes at least one byte
+            if count > bytes.len() - pos {
+                return Err(invalid("ppm data does not match its resolution"));
+            }
+            let mut samples = Vec::with_capacity(count);
+            for _ in 0..count {
+                let value: usize = next_token(bytes, &mut pos)
+                    .and_then(|t| std::str::from_utf8(t).ok())
+                    .and_then(|t| t.parse().ok())
+                    .ok_or_else(|| invalid("ppm data does not match its resolution"))?;
+                samples.push(value.min(max_value) as f64 * scale);
+            }
+            samples
+        };
+
+        let data = samples
+            .chunks_exact(3)
+            .map(|c| Color::new(c[0], c[1], c[2]))
+            .collect();
+
+        Ok(Self::new(width, height, data))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // applies `f` to every pixel, e.g. to decode gamma
+    pub fn map<F: Fn(&Color) -> Color>(mut self, f: F) -> Self {
+        for c in &mut self.data {
+            *c = f(c);
+        }
+        self
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        self.data[y * self.width + x]
+    }
+
+    // bilinearly interpolated value at texture coordinates (u, v), with v
+    // pointing up. the image repeats outside of [0, 1]^2
+    pub fn sample(&self, u: f64, v: f64) -> Color {
+        let x = u.rem_euclid(1.0) * self.width as f64 - 0.5;
+        let y = (1.0 - v.rem_euclid(1.0)) * self.height as f64 - 0.5;
+
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let (fx, fy) = (x - x0, y - y0);
+
+        let wrap = |i: f64, n: usize| (i as i64).rem_euclid(n as i64) as usize;
+        let (x0, x1) = (wrap(x0, self.width), wrap(x0 + 1.0, self.width));
+        let (y0, y1) = (wrap(y0, self.height), wrap(y0 + 1.0, self.height));
+
+        let top = (1.0 - fx) * self.pixel(x0, y0) + fx * self.pixel(x1, y0);
+        let bottom = (1.0 - fx) * self.pixel(x0, y1) + fx * self.pixel(x1, y1);
+        (1.0 - fy) * top + fy * bottom
+    }
+}
+
+// next whitespace separated token, skipping `#` comments
+fn next_token<'a>(bytes: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    loop {
+        while *pos < bytes.len() && bytes[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        if *pos < bytes.len() && bytes[*pos] == b'#' {
+            while *pos < bytes.len() && bytes[*pos] != b'\n' {
+                *pos += 1;
+            }
+        } else {
+            break;
+        }
+    }
+
+    let start = *pos;
+    while *pos < bytes.len() && !bytes[*pos].is_ascii_whitespace() {
+        *pos += 1;
+    }
+    (*pos > start).then(|| &bytes[start..*pos])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_binary() {
+        let mut bytes = b"P6\n# comment\n2 1\n255\n".to_vec();
+        bytes.extend_from_slice(&[255, 0, 51, 0, 255, 0]);
+        let image = Image::parse(&bytes).unwrap();
+        assert_eq!((image.width(), image.height()), (2, 1));
+        assert_eq!(image.pixel(0, 0), Color::new(1.0, 0.0, 0.2));
+        assert_eq!(image.pixel(1, 0), Color::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn parse_binary_16_bit() {
+        let mut bytes = b"P6 1 1 65535\n".to_vec();
+        bytes.extend_from_slice(&[0xff, 0xff, 0x00, 0x00, 0x80, 0x00]);
+        let image = Image::parse(&bytes).unwrap();
+        assert_eq!(image.pixel(0, 0), Color::new(1.0, 0.0, 32768.0 / 65535.0));
+    }
+
+    #[test]
+    fn parse_ascii() {
+        let image = Image::parse(b"P3\n1 2\n4\n4 0 2\n0 1 4\n").unwrap();
+        assert_eq!((image.width(), image.height()), (1, 2));
+        assert_eq!(image.pixel(0, 0), Color::new(1.0, 0.0, 0.5));
+        assert_eq!(image.pixel(0, 1), Color::new(0.0, 0.25, 1.0));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_files() {
+        let huge = format!("P6 {} {} 255\n", usize::MAX / 2, 3);
+        let huge_ascii = format!("P3 {} {} 255\n1 2 3\n", 1u64 << 40, 1u64 << 20);
+        for bytes in [
+            &b"P5 1 1 255\n\0"[..],
+            b"P6 1 1\n",
+            b"P6 0 1 255\n",
+            b"P6 1 1 70000\n\0\0\0\0\0\0",
+            b"P6 2 2 255\n\0\0\0",
+            b"P3 1 1 255\n1 2\n",
+            huge.as_bytes(),
+            huge_ascii.as_bytes(),
+        ] {
+            let err = Image::parse(bytes).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
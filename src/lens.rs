@@ -0,0 +1,589 @@
+use std::{fs, io, path::Path};
+
+use crate::{
+    util::{self, rand::PCG32RNG},
+    vec3::{Point3, Vec3},
+};
+
+// film radius intervals the exit pupil is bounded over, and the rays traced
+// for each
+const PUPIL_INTERVALS: usize = 64;
+const PUPIL_SAMPLES: usize = 1 << 14;
+
+// one refracting surface of a lens prescription, in millimeters, listed from
+// the object side towards the film as in standard lens data tables
+#[derive(Debug, Clone, Copy)]
+pub struct LensElement {
+    // radius of curvature, positive when the center lies towards the film.
+    // 0 marks the aperture stop
+    pub radius: f64,
+    // axial distance to the next surface, or to the film for the last one
+    pub thickness: f64,
+    // index of refraction behind the surface, 0 or 1 for air
+    pub ior: f64,
+    // clear aperture diameter
+    pub aperture: f64,
+}
+
+// axis-aligned rectangle on a plane perpendicular to the optical axis
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    min: [f64; 2],
+    max: [f64; 2],
+}
+
+impl Rect {
+    const EMPTY: Rect = Rect {
+        min: [f64::INFINITY; 2],
+        max: [f64::NEG_INFINITY; 2],
+    };
+
+    fn is_empty(&self) -> bool {
+        self.min[0] > self.max[0] || self.min[1] > self.max[1]
+    }
+
+    fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.min[0] && x <= self.max[0] && y >= self.min[1] && y <= self.max[1]
+    }
+
+    fn include(&mut self, x: f64, y: f64) {
+        self.min = [self.min[0].min(x), self.min[1].min(y)];
+        self.max = [self.max[0].max(x), self.max[1].max(y)];
+    }
+
+    fn area(&self) -> f64 {
+        if self.is_empty() {
+            0.0
+        } else {
+            (self.max[0] - self.min[0]) * (self.max[1] - self.min[1])
+        }
+    }
+}
+
+// van der corput sequence in `base`, for well spread pupil samples
+fn radical_inverse(base: u64, mut i: u64) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut inv = inv_base;
+    let mut value = 0.0;
+    while i > 0 {
+        value += (i % base) as f64 * inv;
+        i /= base;
+        inv *= inv_base;
+    }
+    value
+}
+
+// ray refracted into a medium with relative index `eta` = eta_i / eta_t,
+// with `n` on the side of `wi`, a unit vector pointing away from the surface
+fn refract(wi: &Vec3, n: &Vec3, eta: f64) -> Option<Vec3> {
+    let cos_theta_i = Vec3::dot(n, wi);
+    let sin2_theta_t = eta * eta * (1.0 - cos_theta_i * cos_theta_i).max(0.0);
+    if sin2_theta_t >= 1.0 {
+        return None;
+    }
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+    Some(eta * -*wi + (eta * cos_theta_i - cos_theta_t) * *n)
+}
+
+// system of spherical lens elements in front of the film, traced exactly to
+// give physically based vignetting, distortion and depth of field (after
+// kolb et al. 1995 and pbrt's RealisticCamera)
+//
+// rays are traced in millimeters in a space with the film at z = 0 and the
+// lens along +z, the direction the camera looks
+pub struct LensSystem {
+    elements: Vec<LensElement>,
+    film_diagonal: f64,
+    // scene units are converted to millimeters with this factor
+    mm_per_unit: f64,
+    // bounds of the exit pupil on the plane of the rear element, as seen from
+    // film points at increasing distances from the axis along +x
+    exit_pupils: Vec<Rect>,
+}
+
+impl LensSystem {
+    // `film_diagonal` is the diagonal of the film or sensor, in millimeters
+    pub fn new(elements: Vec<LensElement>, film_diagonal: f64) -> Self {
+        let mut lens = Self {
+            elements,
+            film_diagonal,
+            mm_per_unit: 1000.0,
+            exit_pupils: Vec::new(),
+        };
+        lens.bound_exit_pupils();
+        lens
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P, film_diagonal: f64) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text, film_diagonal)
+    }
+
+    // tables of radius, thickness, ior and aperture diameter per line, with
+    // `#` comments
+    pub fn parse(text: &str, film_diagonal: f64) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let mut elements = Vec::new();
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            if line.trim().is_empty() {
+                continue;
+            }
+            let values = line
+                .split_whitespace()
+                .map(|t| t.parse::<f64>().ok().filter(|x| x.is_finite()))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| invalid("bad number in lens data"))?;
+            let [radius, thickness, ior, aperture] = values[..] else {
+                return Err(invalid("lens data needs 4 values per element"));
+            };
+            elements.push(LensElement {
+                radius,
+                thickness,
+                ior,
+                aperture,
+            });
+        }
+
+        if elements.is_empty() {
+            return Err(invalid("lens data has no elements"));
+        }
+        Ok(Self::new(elements, film_diagonal))
+    }
+
+    // stops the aperture down to `diameter` millimeters. it cannot open
+    // wider than the stop in the prescription
+    pub fn with_aperture_diameter(mut self, diameter: f64) -> Self {
+        for element in &mut self.elements {
+            if element.radius == 0.0 {
+                element.aperture = diameter.min(element.aperture);
+            }
+        }
+        self.bound_exit_pupils();
+        self
+    }
+
+    // scale of the scene, 1000 for scenes modelled in meters
+    pub fn with_units(mut self, mm_per_unit: f64) -> Self {
+        self.mm_per_unit = mm_per_unit;
+        self
+    }
+
+    // moves the film so objects `distance` scene units in front of it are
+    // in focus, using a thick lens approximation of the system. the lens is
+    // left unchanged if it cannot focus there
+    pub fn focus(&mut self, distance: f64) -> io::Result<()> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidInput, msg.to_string());
+
+        let ([pz0, pz1], [fz0, _]) = self
+            .thick_lens()
+            .ok_or_else(|| invalid("paraxial rays do not pass through the lens"))?;
+        let f = fz0 - pz0;
+        let z = -distance * self.mm_per_unit;
+        let c = (pz1 - z - pz0) * (pz1 - z - 4.0 * f - pz0);
+        if c.is_nan() || c < 0.0 {
+            return Err(invalid("focus distance is closer than the lens can focus"));
+        }
+        let delta = 0.5 * (pz1 - z + pz0 - c.sqrt());
+
+        let back = self.elements.last_mut().unwrap();
+        let thickness = back.thickness;
+        back.thickness += delta;
+        self.bound_exit_pupils();
+        if self.exit_pupils[0].is_empty() {
+            self.elements.last_mut().unwrap().thickness = thickness;
+            self.bound_exit_pupils();
+            return Err(invalid("no light reaches the film center at this focus"));
+        }
+        Ok(())
+    }
+
+    // ray for the film position (s, t) in [0, 1]^2, from the top left
+    // corner, as origin and direction in the camera's right, up and forward
+    // frame, in scene units, and a weight for vignetting. None if the ray is
+    // blocked inside the lens
+    pub fn generate_ray(
+        &self,
+        s: f64,
+        t: f64,
+        aspect_ratio: f64,
+        rng: &mut PCG32RNG,
+    ) -> Option<(Point3, Vec3, f64)> {
+        // weights are relative to the pupil at the center, so nothing is
+        // imaged without one (e.g. before focusing, with the film on the
+        // rear element)
+        let center_area = self.exit_pupils[0].area();
+        if center_area <= 0.0 {
+            return None;
+        }
+
+        let p_film = self.film_point(s, t, aspect_ratio);
+        let (p_rear, area) = self.sample_exit_pupil(p_film.x(), p_film.y(), rng)?;
+        let direction = p_rear - p_film;
+        let (origin, out) = self.trace_from_film(&p_film, &direction)?;
+
+        // irradiance falls off with cos^4 towards the image edges
+        let cos_theta = direction.unit_vector().z();
+        let weight = cos_theta.powi(4) * area / center_area;
+
+        Some((origin / self.mm_per_unit, out.unit_vector(), weight))
+    }
+
+    // ray from the film position (s, t) through the center of the exit pupil
+    // bounds, in the frame of `generate_ray`. it approximates the chief ray,
+    // which the image of the position is centered on
+    pub fn chief_ray(&self, s: f64, t: f64, aspect_ratio: f64) -> Option<(Point3, Vec3)> {
+        let p_film = self.film_point(s, t, aspect_ratio);
+        let (x, y) = (p_film.x(), p_film.y());
+        let pupil = self.exit_pupil(x, y)?;
+        let center = [0, 1].map(|i| 0.5 * (pupil.min[i] + pupil.max[i]));
+        let p_rear = Self::rotate_pupil_point(x, y, center[0], center[1], self.lens_rear_z());
+
+        let (origin, out) = self.trace_from_film(&p_film, &(p_rear - p_film))?;
+        Some((origin / self.mm_per_unit, out.unit_vector()))
+    }
+
+    // point on the film for raster position (s, t). the lens inverts the
+    // image, so the film is flipped horizontally and the top of the raster
+    // is the bottom of the film
+    fn film_point(&self, s: f64, t: f64, aspect_ratio: f64) -> Point3 {
+        let film_width = self.film_diagonal / (1.0 + 1.0 / (aspect_ratio * aspect_ratio)).sqrt();
+        let film_height = film_width / aspect_ratio;
+        Point3::new(-(s - 0.5) * film_width, (t - 0.5) * film_height, 0.0)
+    }
+
+    fn lens_front_z(&self) -> f64 {
+        self.elements.iter().map(|e| e.thickness).sum()
+    }
+
+    fn lens_rear_z(&self) -> f64 {
+        self.elements.last().unwrap().thickness
+    }
+
+    fn rear_element_radius(&self) -> f64 {
+        0.5 * self.elements.last().unwrap().aperture
+    }
+
+    // intersection of a ray with an element whose vertex is at `z`,
+    // returning the distance and the surface normal facing the ray
+    fn intersect(element: &LensElement, z: f64, o: &Point3, d: &Vec3) -> Option<(f64, Vec3)> {
+        if element.radius == 0.0 {
+            let t = (z - o.z()) / d.z();
+            return (t > 0.0).then_some((t, Vec3::new(0.0, 0.0, -d.z().signum())));
+        }
+
+        let radius = element.radius;
+        let oc = *o - Point3::new(0.0, 0.0, z + radius);
+        let a = d.len_squared();
+        let b = 2.0 * Vec3::dot(d, &oc);
+        let c = oc.len_squared() - radius * radius;
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let sqrtd = discriminant.sqrt();
+        let (t0, t1) = ((-b - sqrtd) / (2.0 * a), (-b + sqrtd) / (2.0 * a));
+
+        // the part of the sphere forming the element is the one nearest to
+        // its vertex
+        let closer = (d.z() > 0.0) ^ (radius < 0.0);
+        let t = if closer { t0.min(t1) } else { t0.max(t1) };
+        if t < 0.0 {
+            return None;
+        }
+
+        let n = (oc + t * *d).unit_vector();
+        let n = if Vec3::dot(&n, d) > 0.0 { -n } else { n };
+        Some((t, n))
+    }
+
+    fn ior(&self, i: usize) -> f64 {
+        match self.elements[i].ior {
+            0.0 => 1.0,
+            ior => ior,
+        }
+    }
+
+    // traces a ray leaving the film towards the rear element out of the
+    // front of the lens. None if an aperture blocks it or it is totally
+    // internally reflected
+    fn trace_from_film(&self, o: &Point3, d: &Vec3) -> Option<(Point3, Vec3)> {
+        // the lens in lens space lies along -z, in front of the film
+        let mut o = Point3::new(o.x(), o.y(), -o.z());
+        let mut d = Vec3::new(d.x(), d.y(), -d.z());
+        let mut z = 0.0;
+
+        for i in (0..self.elements.len()).rev() {
+            let element = &self.elements[i];
+            z -= element.thickness;
+            let (t, n) = Self::intersect(element, z, &o, &d)?;
+            let p = o + t * d;
+            if p.x() * p.x() + p.y() * p.y() > 0.25 * element.aperture * element.aperture {
+                return None;
+            }
+            o = p;
+
+            if element.radius != 0.0 {
+                let eta_t = if i > 0 { self.ior(i - 1) } else { 1.0 };
+                d = refract(&-d.unit_vector(), &n, self.ior(i) / eta_t)?;
+            }
+        }
+
+        Some((
+            Point3::new(o.x(), o.y(), -o.z()),
+            Vec3::new(d.x(), d.y(), -d.z()),
+        ))
+    }
+
+    // traces a ray from the scene in front of the lens to the film side
+    fn trace_from_scene(&self, o: &Point3, d: &Vec3) -> Option<(Point3, Vec3)> {
+        let mut o = Point3::new(o.x(), o.y(), -o.z());
+        let mut d = Vec3::new(d.x(), d.y(), -d.z());
+        let mut z = -self.lens_front_z();
+
+        for i in 0..self.elements.len() {
+            let element = &self.elements[i];
+            let (t, n) = Self::intersect(element, z, &o, &d)?;
+            let p = o + t * d;
+            if p.x() * p.x() + p.y() * p.y() > 0.25 * element.aperture * element.aperture {
+                return None;
+            }
+            o = p;
+
+            if element.radius != 0.0 {
+                let eta_i = if i > 0 { self.ior(i - 1) } else { 1.0 };
+                d = refract(&-d.unit_vector(), &n, eta_i / self.ior(i))?;
+            }
+            z += element.thickness;
+        }
+
+        Some((
+            Point3::new(o.x(), o.y(), -o.z()),
+            Vec3::new(d.x(), d.y(), -d.z()),
+        ))
+    }
+
+    // principal plane and focal point, in lens space, of a ray parallel to
+    // the axis at `o_in` that leaves the lens as `o_out`, `d_out`
+    fn cardinal_points(o_in: &Point3, o_out: &Point3, d_out: &Vec3) -> (f64, f64) {
+        let tf = -o_out.x() / d_out.x();
+        let fz = -(o_out.z() + tf * d_out.z());
+        let tp = (o_in.x() - o_out.x()) / d_out.x();
+        let pz = -(o_out.z() + tp * d_out.z());
+        (pz, fz)
+    }
+
+    // principal planes and focal points on the scene and film sides, found
+    // with rays parallel to the axis, close to it
+    fn thick_lens(&self) -> Option<([f64; 2], [f64; 2])> {
+        let x = 0.001 * self.film_diagonal;
+
+        let o_scene = Point3::new(x, 0.0, self.lens_front_z() + 1.0);
+        let (o, d) = self.trace_from_scene(&o_scene, &Vec3::new(0.0, 0.0, -1.0))?;
+        let (pz0, fz0) = Self::cardinal_points(&o_scene, &o, &d);
+
+        let o_film = Point3::new(x, 0.0, self.lens_rear_z() - 1.0);
+        let (o, d) = self.trace_from_film(&o_film, &Vec3::new(0.0, 0.0, 1.0))?;
+        let (pz1, fz1) = Self::cardinal_points(&o_film, &o, &d);
+
+        Some(([pz0, pz1], [fz0, fz1]))
+    }
+
+    // precomputes which part of the rear element passes light to the scene,
+    // from film points over each radius interval, so rays are only sampled
+    // where they can leave the lens
+    fn bound_exit_pupils(&mut self) {
+        let half_diagonal = 0.5 * self.film_diagonal;
+        self.exit_pupils = (0..PUPIL_INTERVALS)
+            .map(|i| {
+                let r0 = i as f64 / PUPIL_INTERVALS as f64 * half_diagonal;
+                let r1 = (i + 1) as f64 / PUPIL_INTERVALS as f64 * half_diagonal;
+                self.bound_exit_pupil(r0, r1)
+            })
+            .collect();
+    }
+
+    fn bound_exit_pupil(&self, r0: f64, r1: f64) -> Rect {
+        let extent = 1.5 * self.rear_element_radius();
+        let rear_z = self.lens_rear_z();
+
+        let mut pupil = Rect::EMPTY;
+        for i in 0..PUPIL_SAMPLES {
+            let p_film = Point3::new(
+                util::lerp(r0, r1, (i as f64 + 0.5) / PUPIL_SAMPLES as f64),
+                0.0,
+                0.0,
+            );
+            let x = util::lerp(-extent, extent, radical_inverse(2, i as u64));
+            let y = util::lerp(-extent, extent, radical_inverse(3, i as u64));
+            if pupil.contains(x, y)
+                || self
+                    .trace_from_film(&p_film, &(Point3::new(x, y, rear_z) - p_film))
+                    .is_some()
+            {
+                pupil.include(x, y);
+            }
+        }
+
+        if pupil.is_empty() {
+            return pupil;
+        }
+        // grow by the sample spacing, to cover the parts the samples missed
+        let margin = 2.0 * (2.0 * extent * 2.0_f64.sqrt()) / (PUPIL_SAMPLES as f64).sqrt();
+        pupil.min = pupil.min.map(|m| m - margin);
+        pupil.max = pupil.max.map(|m| m + margin);
+        pupil
+    }
+
+    // exit pupil bounds for the film point (x, y), in the frame of the +x
+    // axis they were computed on
+    fn exit_pupil(&self, x: f64, y: f64) -> Option<&Rect> {
+        let r = (x * x + y * y).sqrt();
+        let index = ((r / (0.5 * self.film_diagonal) * PUPIL_INTERVALS as f64) as usize)
+            .min(PUPIL_INTERVALS - 1);
+        let pupil = &self.exit_pupils[index];
+        (!pupil.is_empty()).then_some(pupil)
+    }
+
+    // point (lx, ly) of the pupil bounds rotated from the +x axis to the
+    // film point (x, y)
+    fn rotate_pupil_point(x: f64, y: f64, lx: f64, ly: f64, z: f64) -> Point3 {
+        let r = (x * x + y * y).sqrt();
+        let (sin_theta, cos_theta) = if r > 0.0 { (y / r, x / r) } else { (0.0, 1.0) };
+        Point3::new(
+            cos_theta * lx - sin_theta * ly,
+            sin_theta * lx + cos_theta * ly,
+            z,
+        )
+    }
+
+    // uniform point on the exit pupil bounds for a film point, with the area
+    // of the bounds
+    fn sample_exit_pupil(&self, x: f64, y: f64, rng: &mut PCG32RNG) -> Option<(Point3, f64)> {
+        let pupil = self.exit_pupil(x, y)?;
+        let lx = util::lerp(pupil.min[0], pupil.max[0], rng.random_f64());
+        let ly = util::lerp(pupil.min[1], pupil.max[1], rng.random_f64());
+        Some((
+            Self::rotate_pupil_point(x, y, lx, ly, self.lens_rear_z()),
+            pupil.area(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DGAUSS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/lenses/dgauss.50mm.dat");
+
+    fn dgauss() -> LensSystem {
+        LensSystem::load(DGAUSS, 35.0).unwrap()
+    }
+
+    #[test]
+    fn focus_rejects_distances_closer_than_the_lens_can_focus() {
+        let mut lens = dgauss();
+        lens.focus(1.0).unwrap();
+        let thickness = lens.lens_rear_z();
+
+        // 10 mm in front of the film is inside the lens
+        let err = lens.focus(0.01).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(lens.lens_rear_z(), thickness);
+        assert!(!lens.exit_pupils[0].is_empty());
+    }
+
+    #[test]
+    fn unfocused_lens_generates_no_nan_weights() {
+        // the prescription leaves the film on the rear element
+        let lens = dgauss();
+        assert_eq!(lens.lens_rear_z(), 0.0);
+        let mut rng = PCG32RNG::default();
+        for i in 0..100 {
+            let s = i as f64 / 100.0;
+            if let Some((_, _, weight)) = lens.generate_ray(s, 0.5, 1.5, &mut rng) {
+                assert!(weight.is_finite());
+            }
+        }
+    }
+
+    #[test]
+    fn parse_dgauss_prescription() {
+        let lens = dgauss();
+        assert_eq!(lens.elements.len(), 11);
+        let stop = lens.elements[5];
+        assert_eq!((stop.radius, stop.aperture), (0.0, 17.1));
+        assert_eq!(lens.ior(1), 1.0);
+        assert_eq!(lens.ior(5), 1.0);
+        assert_eq!(lens.ior(6), 1.603);
+    }
+
+    #[test]
+    fn thick_lens_focal_length() {
+        let ([pz0, pz1], [fz0, fz1]) = dgauss().thick_lens().unwrap();
+        // the same focal length on both sides of the lens
+        assert!((fz0 - pz0 - 50.0).abs() < 1.0, "{}", fz0 - pz0);
+        assert!((pz1 - fz1 - 50.0).abs() < 1.0, "{}", pz1 - fz1);
+    }
+
+    #[test]
+    fn focus_images_the_focus_distance_on_the_film() {
+        let mut lens = dgauss();
+        lens.focus(1000.0).unwrap();
+        let far = lens.lens_rear_z();
+        lens.focus(1.0).unwrap();
+        let near = lens.lens_rear_z();
+
+        // newton's equation: the film moves out by f^2 / (distance - f)
+        // from the focal plane, about 2.7 mm at a meter
+        let extension = near - far;
+        assert!(extension > 2.4 && extension < 3.0, "{extension}");
+
+        // a paraxial ray from the focused point on the axis converges on
+        // the film center
+        let o = Point3::new(0.0, 0.0, 1000.0);
+        let target = Point3::new(0.5, 0.0, lens.lens_front_z());
+        let (p, d) = lens.trace_from_scene(&o, &(target - o)).unwrap();
+        let x = p.x() - p.z() / d.z() * d.x();
+        assert!(x.abs() < 1e-3, "{x}");
+    }
+
+    #[test]
+    fn focused_lens_has_a_center_pupil() {
+        let mut lens = dgauss();
+        lens.focus(2.0).unwrap();
+        let pupil = lens.exit_pupil(0.0, 0.0).unwrap();
+        // within the sampled extent around the rear element, centered on the
+        // axis
+        let extent = 1.5 * lens.rear_element_radius();
+        assert!(pupil.area() > 1.0 && pupil.area() < 4.0 * extent * extent);
+        for i in 0..2 {
+            assert!((pupil.min[i] + pupil.max[i]).abs() < 0.5);
+        }
+
+        let mut rng = PCG32RNG::default();
+        let (origin, direction, weight) = (0..100)
+            .find_map(|_| lens.generate_ray(0.5, 0.5, 1.5, &mut rng))
+            .unwrap();
+        assert!(weight > 0.0 && weight <= 1.0);
+        assert!(direction.z() > 0.0);
+        assert!(origin.z() > 0.0);
+    }
+
+    #[test]
+    fn parse_rejects_malformed_lines() {
+        for text in [
+            "",
+            "# only a comment\n",
+            "29.475 3.76 1.67\n",
+            "29.475 3.76 1.67 25.2 1\n",
+            "29.475 3.76 glass 25.2\n",
+            "29.475 3.76 1.67 inf\n",
+            "29.475, 3.76, 1.67, 25.2\n",
+        ] {
+            let err = LensSystem::parse(text, 35.0).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{text:?}");
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use std::f64::consts::PI;
+
+use crate::{
+    aabb::Aabb,
+    mat4::Mat4,
+    vec3::{Point3, Vec3},
+};
+
+// spatial and directional extent of the emission of one or more lights, used
+// to estimate how much they contribute to a shading point (after pbrt-v4's
+// LightBounds, without the surface normal term)
+#[derive(Debug, Clone)]
+pub struct LightBounds {
+    pub bbox: Aabb,
+    // central emission direction
+    pub w: Vec3,
+    // cosine of the spread of normals around `w`, -1 for all directions
+    pub cos_theta_o: f64,
+    // cosine of the additional angle light is emitted at beyond the normals
+    pub cos_theta_e: f64,
+    // emitted power, in luminance
+    pub phi: f64,
+}
+
+// cos(max(0, a - b)) and sin(max(0, a - b)) from the sines and cosines
+#[inline]
+fn cos_sub_clamped(sin_a: f64, cos_a: f64, sin_b: f64, cos_b: f64) -> f64 {
+    if cos_a > cos_b {
+        1.0
+    } else {
+        cos_a * cos_b + sin_a * sin_b
+    }
+}
+
+#[inline]
+fn sin_sub_clamped(sin_a: f64, cos_a: f64, sin_b: f64, cos_b: f64) -> f64 {
+    if cos_a > cos_b {
+        0.0
+    } else {
+        sin_a * cos_b - cos_a * sin_b
+    }
+}
+
+#[inline]
+fn sin_from_cos(cos: f64) -> f64 {
+    (1.0 - cos * cos).max(0.0).sqrt()
+}
+
+impl LightBounds {
+    // bounds of a light emitting in every direction from `bbox`
+    pub fn omni(bbox: Aabb, phi: f64) -> Self {
+        Self {
+            bbox,
+            w: Vec3::new(0.0, 0.0, 1.0),
+            cos_theta_o: -1.0,
+            cos_theta_e: 0.0,
+            phi,
+        }
+    }
+
+    pub fn union(a: &Self, b: &Self) -> Self {
+        if a.phi <= 0.0 {
+            return b.clone();
+        }
+        if b.phi <= 0.0 {
+            return a.clone();
+        }
+
+        let (w, cos_theta_o) = Self::cone_union(&a.w, a.cos_theta_o, &b.w, b.cos_theta_o);
+        Self {
+            bbox: Aabb::from_boxes(&a.bbox, &b.bbox),
+            w,
+            cos_theta_o,
+            cos_theta_e: a.cos_theta_e.min(b.cos_theta_e),
+            phi: a.phi + b.phi,
+        }
+    }
+
+    // smallest cone around both direction cones
+    fn cone_union(wa: &Vec3, cos_a: f64, wb: &Vec3, cos_b: f64) -> (Vec3, f64) {
+        let theta_a = cos_a.clamp(-1.0, 1.0).acos();
+        let theta_b = cos_b.clamp(-1.0, 1.0).acos();
+        let theta_d = Vec3::dot(wa, wb).clamp(-1.0, 1.0).acos();
+        if (theta_d + theta_b).min(PI) <= theta_a {
+            return (*wa, cos_a);
+        }
+        if (theta_d + theta_a).min(PI) <= theta_b {
+            return (*wb, cos_b);
+        }
+
+        let theta_o = 0.5 * (theta_a + theta_d + theta_b);
+        let axis = Vec3::cross(wa, wb);
+        if theta_o >= PI || axis.near_zero() {
+            return (*wa, -1.0);
+        }
+        // rotate wa towards wb until the cone just covers both
+        let w = Mat4::rotate(&axis, (theta_o - theta_a).to_degrees()).transform_vector(wa);
+        (w.unit_vector(), theta_o.cos())
+    }
+
+    // conservative estimate of the contribution of the bounded lights at `p`
+    pub fn importance(&self, p: &Point3) -> f64 {
+        let center = self.bbox.centroid();
+        let radius = 0.5 * (self.bbox.max() - self.bbox.min()).len();
+        let to_p = *p - center;
+        // keep points inside or close to the box from blowing up
+        let d2 = to_p.len_squared().max(radius * radius);
+        if d2 == 0.0 {
+            return self.phi;
+        }
+
+        let cos_theta_w = Vec3::dot(&self.w, &to_p.unit_vector());
+        let sin_theta_w = sin_from_cos(cos_theta_w);
+
+        // angle subtended by the bounding sphere of the box
+        let sin2_theta_b = (radius * radius / to_p.len_squared()).min(1.0);
+        let cos_theta_b = if to_p.len_squared() <= radius * radius {
+            -1.0
+        } else {
+            (1.0 - sin2_theta_b).sqrt()
+        };
+        let sin_theta_b = sin_from_cos(cos_theta_b);
+
+        // smallest angle between the emission cone and the direction to p
+        let sin_theta_o = sin_from_cos(self.cos_theta_o);
+        let cos_theta_x = cos_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, self.cos_theta_o);
+        let sin_theta_x = sin_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, self.cos_theta_o);
+        let cos_theta_p = cos_sub_clamped(sin_theta_x, cos_theta_x, sin_theta_b, cos_theta_b);
+        if cos_theta_p <= self.cos_theta_e {
+            return 0.0;
+        }
+
+        self.phi * cos_theta_p / d2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point_bounds(p: Point3, w: Vec3, cos_theta_o: f64, phi: f64) -> LightBounds {
+        LightBounds {
+            bbox: Aabb::from_points(&p, &p),
+            w,
+            cos_theta_o,
+            cos_theta_e: 0.0,
+            phi,
+        }
+    }
+
+    #[test]
+    fn omni_importance_falls_off_with_distance() {
+        let b = LightBounds::omni(
+            Aabb::from_points(&Point3::default(), &Point3::default()),
+            4.0,
+        );
+        let near = b.importance(&Point3::new(1.0, 0.0, 0.0));
+        let far = b.importance(&Point3::new(0.0, -2.0, 0.0));
+        assert!((near - 4.0).abs() < 1e-12);
+        assert!((far - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn points_behind_a_cone_get_no_importance() {
+        let up = Vec3::new(0.0, 0.0, 1.0);
+        let b = point_bounds(Point3::default(), up, 1.0, 1.0);
+        assert!(b.importance(&Point3::new(0.0, 0.0, 1.0)) > 0.0);
+        assert_eq!(b.importance(&Point3::new(0.0, 0.0, -1.0)), 0.0);
+        assert_eq!(b.importance(&Point3::new(1.0, 0.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn union_covers_both_cones() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let y = Vec3::new(0.0, 1.0, 0.0);
+        let a = point_bounds(Point3::default(), x, 1.0, 1.0);
+        let b = point_bounds(Point3::new(1.0, 1.0, 1.0), y, 1.0, 2.0);
+        let u = LightBounds::union(&a, &b);
+
+        assert_eq!(u.phi, 3.0);
+        assert_eq!(u.bbox.max(), Point3::new(1.0, 1.0, 1.0));
+        // the cone bisects the two directions, 45 degrees from each
+        let half = (x + y).unit_vector();
+        assert!((u.w - half).len() < 1e-9, "{:?}", u.w);
+        assert!((u.cos_theta_o - half.x()).abs() < 1e-9);
+        for w in [x, y] {
+            assert!(Vec3::dot(&u.w, &w) >= u.cos_theta_o - 1e-9);
+        }
+
+        // lights without power are left out
+        let dark = point_bounds(Point3::new(5.0, 5.0, 5.0), y, 1.0, 0.0);
+        assert_eq!(LightBounds::union(&a, &dark).bbox.max(), Point3::default());
+    }
+
+    #[test]
+    fn opposite_cones_spread_in_all_directions() {
+        let z = Vec3::new(0.0, 0.0, 1.0);
+        let a = point_bounds(Point3::default(), z, 1.0, 1.0);
+        let b = point_bounds(Point3::default(), -z, 1.0, 1.0);
+        assert_eq!(LightBounds::union(&a, &b).cos_theta_o, -1.0);
+    }
+}
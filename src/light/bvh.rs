@@ -0,0 +1,327 @@
+use std::rc::Rc;
+
+use crate::{
+    interval::Interval,
+    light::{Light, LightSample, bounds::LightBounds},
+    util::rand::PCG32RNG,
+    vec3::Point3,
+};
+
+enum Node {
+    Leaf(usize),
+    Interior([usize; 2]),
+}
+
+// bounding volume hierarchy over the lights, traversed from the root by
+// picking each child in proportion to its estimated importance at the
+// shading point, so nearby and bright lights are chosen more often. lights
+// at infinity share the choice uniformly with the tree as a group
+pub struct LightBvh {
+    lights: Vec<Rc<dyn Light>>,
+    infinite: Vec<Rc<dyn Light>>,
+    // nodes[0] is the root, each with the bounds of the lights below it
+    nodes: Vec<(Node, LightBounds)>,
+}
+
+impl LightBvh {
+    pub fn new(lights: Vec<Rc<dyn Light>>) -> Self {
+        let mut finite = Vec::new();
+        let mut infinite = Vec::new();
+        let mut items = Vec::new();
+        for light in lights {
+            match light.bounds() {
+                // lights that emit nothing are never worth sampling
+                Some(bounds) if bounds.phi > 0.0 => {
+                    items.push((finite.len(), bounds));
+                    finite.push(light);
+                }
+                Some(_) => {}
+                None => infinite.push(light),
+            }
+        }
+
+        let mut nodes = Vec::new();
+        if !items.is_empty() {
+            Self::build(&mut nodes, &mut items);
+        }
+        Self {
+            lights: finite,
+            infinite,
+            nodes,
+        }
+    }
+
+    // appends the subtree over `items` and returns its index. splits at the
+    // median along the axis where the light centroids spread the most
+    fn build(nodes: &mut Vec<(Node, LightBounds)>, items: &mut [(usize, LightBounds)]) -> usize {
+        if let [(light, bounds)] = items {
+            nodes.push((Node::Leaf(*light), bounds.clone()));
+            return nodes.len() - 1;
+        }
+
+        let bounds = items
+            .iter()
+            .skip(1)
+            .fold(items[0].1.clone(), |acc, (_, b)| {
+                LightBounds::union(&acc, b)
+            });
+        let centroids: Vec<Point3> = items.iter().map(|(_, b)| b.bbox.centroid()).collect();
+        let axis = (0..3)
+            .max_by(|&a, &b| {
+                let spread = |axis: usize| {
+                    let (lo, hi) = centroids
+                        .iter()
+                        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), c| {
+                            (lo.min(c[axis]), hi.max(c[axis]))
+                        });
+                    hi - lo
+                };
+                spread(a).total_cmp(&spread(b))
+            })
+            .unwrap();
+
+        let mid = items.len() / 2;
+        items.select_nth_unstable_by(mid, |(_, a), (_, b)| {
+            a.bbox.centroid()[axis].total_cmp(&b.bbox.centroid()[axis])
+        });
+
+        // reserve the parent slot so the root stays at index 0
+        let index = nodes.len();
+        nodes.push((Node::Leaf(0), bounds));
+        let (left, right) = items.split_at_mut(mid);
+        let children = [Self::build(nodes, left), Self::build(nodes, right)];
+        nodes[index].0 = Node::Interior(children);
+        index
+    }
+
+    // picks a light for `p`, with its selection probability
+    fn pick(&self, p: &Point3, rng: &mut PCG32RNG) -> Option<(usize, f64)> {
+        let mut index = 0;
+        let mut pmf = 1.0;
+        loop {
+            let (node, bounds) = self.nodes.get(index)?;
+            match node {
+                Node::Leaf(light) => {
+                    return (bounds.importance(p) > 0.0).then_some((*light, pmf));
+                }
+                Node::Interior(children) => {
+                    let importance = children.map(|c| self.nodes[c].1.importance(p));
+                    let total = importance[0] + importance[1];
+                    if total <= 0.0 {
+                        return None;
+                    }
+                    let p0 = importance[0] / total;
+                    if rng.random_f64() < p0 {
+                        index = children[0];
+                        pmf *= p0;
+                    } else {
+                        index = children[1];
+                        pmf *= 1.0 - p0;
+                    }
+                }
+            }
+        }
+    }
+
+    // density of sampling `q` from `p` through the subtree at `index`: the
+    // probability of `pick` reaching each light whose bounds hold `q`,
+    // times that light's own pdf
+    fn subtree_pdf(&self, index: usize, p: &Point3, q: &Point3) -> f64 {
+        let (node, bounds) = &self.nodes[index];
+        let bbox = &bounds.bbox;
+        let tolerance = 1e-6 * (bbox.max() - bbox.min()).len();
+        let holds = (0..3).all(|axis| {
+            let extent = bbox.axis_interval(axis);
+            Interval::new(extent.min - tolerance, extent.max + tolerance).contains(q[axis])
+        });
+        if !holds {
+            return 0.0;
+        }
+
+        match node {
+            Node::Leaf(light) => {
+                if bounds.importance(p) > 0.0 {
+                    self.lights[*light].pdf(p, q)
+                } else {
+                    0.0
+                }
+            }
+            Node::Interior(children) => {
+                let importance = children.map(|c| self.nodes[c].1.importance(p));
+                let total = importance[0] + importance[1];
+                if total <= 0.0 {
+                    return 0.0;
+                }
+                (0..2)
+                    .filter(|&i| importance[i] > 0.0)
+                    .map(|i| importance[i] / total * self.subtree_pdf(children[i], p, q))
+                    .sum()
+            }
+        }
+    }
+}
+
+impl Light for LightBvh {
+    fn sample(&self, p: &Point3, rng: &mut PCG32RNG) -> Option<LightSample> {
+        let groups = self.infinite.len() + usize::from(!self.nodes.is_empty());
+        if groups == 0 {
+            return None;
+        }
+        let pick = ((rng.random_f64() * groups as f64) as usize).min(groups - 1);
+
+        if let Some(light) = self.infinite.get(pick) {
+            let mut sample = light.sample(p, rng)?;
+            sample.pdf /= groups as f64;
+            return Some(sample);
+        }
+
+        let (i, pmf) = self.pick(p, rng)?;
+        let mut sample = self.lights[i].sample(p, rng)?;
+        sample.pdf *= pmf / groups as f64;
+        Some(sample)
+    }
+
+    fn pdf(&self, p: &Point3, q: &Point3) -> f64 {
+        let groups = self.infinite.len() + usize::from(!self.nodes.is_empty());
+        if groups == 0 {
+            return 0.0;
+        }
+        let infinite: f64 = self.infinite.iter().map(|l| l.pdf(p, q)).sum();
+        let finite = if self.nodes.is_empty() {
+            0.0
+        } else {
+            self.subtree_pdf(0, p, q)
+        };
+        (infinite + finite) / groups as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::Color,
+        light::{
+            directional::DirectionalLight, list::LightList, point::PointLight,
+            power::PowerLightSampler, sphere::SphereLight,
+        },
+        vec3::Vec3,
+    };
+
+    fn lights() -> Vec<Rc<dyn Light>> {
+        let mut lights: Vec<Rc<dyn Light>> = (0..10)
+            .map(|i| {
+                let x = i as f64 - 4.5;
+                Rc::new(PointLight::new(
+                    Point3::new(x, 1.0, 0.5 * x),
+                    Color::new(1.0 + i as f64, 1.0, 1.0),
+                )) as Rc<dyn Light>
+            })
+            .collect();
+        lights.push(Rc::new(DirectionalLight::new(
+            Vec3::new(0.0, 1.0, 0.0),
+            Color::new(0.5, 0.5, 0.5),
+        )));
+        // never sampled, as it emits nothing
+        lights.push(Rc::new(PointLight::new(
+            Point3::default(),
+            Color::default(),
+        )));
+        lights
+    }
+
+    // sum of the lights' irradiance at `p`, and its estimate from `sampler`
+    fn irradiance(sampler: &dyn Light, p: &Point3) -> (f64, f64) {
+        let mut rng = PCG32RNG::default();
+        let exact = lights()
+            .iter()
+            .filter_map(|l| l.sample(p, &mut rng))
+            .map(|s| s.radiance.x())
+            .sum();
+        let n = 100000;
+        let estimate = (0..n)
+            .filter_map(|_| sampler.sample(p, &mut rng))
+            .map(|s| s.radiance.x() / s.pdf)
+            .sum::<f64>()
+            / n as f64;
+        (exact, estimate)
+    }
+
+    #[test]
+    fn samplers_are_unbiased() {
+        let bvh = LightBvh::new(lights());
+        let power = PowerLightSampler::new(lights());
+        for p in [Point3::default(), Point3::new(3.0, 0.0, 1.0)] {
+            for sampler in [&bvh as &dyn Light, &power] {
+                let (exact, estimate) = irradiance(sampler, &p);
+                assert!(
+                    (estimate - exact).abs() < 0.02 * exact,
+                    "{estimate} != {exact}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bvh_prefers_nearby_lights() {
+        let bvh = LightBvh::new(lights());
+        assert_eq!(bvh.lights.len(), 10);
+        assert_eq!(bvh.infinite.len(), 1);
+
+        // right below the last light, which is brightest too
+        let p = Point3::new(4.5, 0.9, 2.25);
+        let mut rng = PCG32RNG::default();
+        let n = 1000;
+        let near = (0..n)
+            .filter_map(|_| bvh.pick(&p, &mut rng))
+            .filter(|&(i, _)| i == 9)
+            .count();
+        assert!(near as f64 / n as f64 > 0.8, "{near}");
+    }
+
+    #[test]
+    fn pdf_matches_the_sampled_pdf() {
+        let spheres = || {
+            let mut lights: Vec<Rc<dyn Light>> = (0..6)
+                .map(|i| {
+                    let x = 2.0 * i as f64 - 5.0;
+                    Rc::new(SphereLight::new(
+                        Point3::new(x, 2.0, 0.3 * x),
+                        0.2 + 0.1 * i as f64,
+                        Color::new(1.0 + i as f64, 1.0, 1.0),
+                    )) as Rc<dyn Light>
+                })
+                .collect();
+            lights.push(Rc::new(DirectionalLight::new(
+                Vec3::new(0.0, 1.0, 0.0),
+                Color::new(0.5, 0.5, 0.5),
+            )));
+            lights
+        };
+        let mut list = LightList::default();
+        for light in spheres() {
+            list.add(light);
+        }
+        let bvh = LightBvh::new(spheres());
+        let power = PowerLightSampler::new(spheres());
+
+        let mut rng = PCG32RNG::default();
+        for p in [Point3::default(), Point3::new(3.0, 0.0, 1.0)] {
+            for sampler in [&list as &dyn Light, &bvh, &power] {
+                for _ in 0..1000 {
+                    let Some(s) = sampler.sample(&p, &mut rng) else {
+                        continue;
+                    };
+                    // the directional light is never found by scattered rays
+                    if !s.distance.is_finite() {
+                        continue;
+                    }
+                    let pdf = sampler.pdf(&p, &(p + s.distance * s.direction));
+                    assert!((pdf - s.pdf).abs() < 1e-6 * s.pdf, "{pdf} != {}", s.pdf);
+                }
+                // nor are points off every light
+                assert_eq!(sampler.pdf(&p, &Point3::new(0.0, -3.0, 0.0)), 0.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::Color,
+    light::{Light, LightSample},
+    onb::Onb,
+    util::{self, rand::PCG32RNG},
+    vec3::{Point3, Vec3},
+};
+
+// light from infinitely far away (the sun), arriving from `direction`.
+// `irradiance` is measured perpendicular to the light
+pub struct DirectionalLight {
+    direction: Vec3,
+    irradiance: Color,
+    // cosine of the angular radius of the light's disk, 1 for a delta light
+    cos_radius: f64,
+}
+
+impl DirectionalLight {
+    // `direction` points from the scene towards the light
+    pub fn new(direction: Vec3, irradiance: Color) -> Self {
+        Self {
+            direction: direction.unit_vector(),
+            irradiance,
+            cos_radius: 1.0,
+        }
+    }
+
+    // spreads the light over a disk of `angular_diameter` degrees, which
+    // softens shadows (the sun is about 0.53 degrees)
+    pub fn with_angular_diameter(mut self, angular_diameter: f64) -> Self {
+        self.cos_radius = util::deg_to_rad(angular_diameter / 2.0).cos();
+        self
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _p: &Point3, rng: &mut PCG32RNG) -> Option<LightSample> {
+        if self.cos_radius >= 1.0 {
+            return Some(LightSample {
+                direction: self.direction,
+                distance: f64::INFINITY,
+                radiance: self.irradiance,
+                pdf: 1.0,
+            });
+        }
+
+        // uniform over the cone subtended by the disk, whose radiance gives
+        // the same irradiance as the delta light
+        let solid_angle = 2.0 * PI * (1.0 - self.cos_radius);
+        let cos_theta = 1.0 - rng.random_f64() * (1.0 - self.cos_radius);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * rng.random_f64();
+        let direction = Onb::new(&self.direction).transform(&Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ));
+
+        Some(LightSample {
+            direction,
+            distance: f64::INFINITY,
+            radiance: self.irradiance / solid_angle,
+            pdf: 1.0 / solid_angle,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_light_arrives_from_its_direction() {
+        let light = DirectionalLight::new(Vec3::new(0.0, 2.0, 0.0), Color::new(3.0, 3.0, 3.0));
+        let mut rng = PCG32RNG::default();
+        let sample = light.sample(&Point3::default(), &mut rng).unwrap();
+        assert_eq!(sample.direction, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(sample.distance, f64::INFINITY);
+        assert_eq!(
+            (sample.radiance, sample.pdf),
+            (Color::new(3.0, 3.0, 3.0), 1.0)
+        );
+    }
+
+    #[test]
+    fn disk_keeps_the_irradiance() {
+        let irradiance = Color::new(3.0, 2.0, 1.0);
+        let light =
+            DirectionalLight::new(Vec3::new(1.0, 1.0, 0.0), irradiance).with_angular_diameter(10.0);
+        let axis = Vec3::new(1.0, 1.0, 0.0).unit_vector();
+        let cos_radius = util::deg_to_rad(5.0).cos();
+        let mut rng = PCG32RNG::default();
+        for _ in 0..1000 {
+            let sample = light.sample(&Point3::default(), &mut rng).unwrap();
+            assert!(Vec3::dot(&sample.direction, &axis) >= cos_radius - 1e-12);
+            let estimate = sample.radiance / sample.pdf;
+            assert!((estimate - irradiance).len() < 1e-9);
+        }
+    }
+}
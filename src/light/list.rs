@@ -0,0 +1,82 @@
+use std::rc::Rc;
+
+use crate::{
+    light::{Light, LightSample},
+    util::rand::PCG32RNG,
+    vec3::Point3,
+};
+
+#[derive(Default)]
+pub struct LightList {
+    lights: Vec<Rc<dyn Light>>,
+}
+
+impl LightList {
+    pub fn new(light: Rc<dyn Light>) -> Self {
+        Self {
+            lights: vec![light],
+        }
+    }
+
+    pub fn add(&mut self, light: Rc<dyn Light>) {
+        self.lights.push(light);
+    }
+}
+
+impl Light for LightList {
+    // picks one light uniformly, folding the selection probability into the
+    // sample's pdf
+    fn sample(&self, p: &Point3, rng: &mut PCG32RNG) -> Option<LightSample> {
+        if self.lights.is_empty() {
+            return None;
+        }
+        let n = self.lights.len();
+        let i = ((rng.random_f64() * n as f64) as usize).min(n - 1);
+
+        let mut sample = self.lights[i].sample(p, rng)?;
+        sample.pdf /= n as f64;
+        Some(sample)
+    }
+
+    fn pdf(&self, p: &Point3, q: &Point3) -> f64 {
+        if self.lights.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self.lights.iter().map(|l| l.pdf(p, q)).sum();
+        sum / self.lights.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, light::point::PointLight};
+
+    #[test]
+    fn uniform_choice_sums_the_lights() {
+        let mut list = LightList::default();
+        let mut rng = PCG32RNG::default();
+        assert!(list.sample(&Point3::default(), &mut rng).is_none());
+
+        list.add(Rc::new(PointLight::new(
+            Point3::new(0.0, 1.0, 0.0),
+            Color::new(1.0, 0.0, 0.0),
+        )));
+        list.add(Rc::new(PointLight::new(
+            Point3::new(0.0, 2.0, 0.0),
+            Color::new(0.0, 4.0, 0.0),
+        )));
+
+        let n = 20000;
+        let mut total = Color::default();
+        for _ in 0..n {
+            let sample = list.sample(&Point3::default(), &mut rng).unwrap();
+            assert_eq!(sample.pdf, 0.5);
+            total += sample.radiance / sample.pdf / n as f64;
+        }
+        assert!(
+            (total - Color::new(1.0, 1.0, 0.0)).len() < 0.02,
+            "{total:?}"
+        );
+    }
+}
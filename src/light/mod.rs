@@ -0,0 +1,46 @@
+pub mod bounds;
+pub mod bvh;
+pub mod directional;
+pub mod list;
+pub mod photometric;
+pub mod point;
+pub mod power;
+pub mod sphere;
+pub mod spot;
+
+use crate::{
+    color::Color,
+    light::bounds::LightBounds,
+    util::rand::PCG32RNG,
+    vec3::{Point3, Vec3},
+};
+
+// illumination arriving at a shading point from a sampled point on a light
+pub struct LightSample {
+    // unit direction from the shading point towards the light
+    pub direction: Vec3,
+    // distance to the light, infinite for lights at infinity
+    pub distance: f64,
+    // incident radiance, or for delta lights the irradiance at normal incidence
+    pub radiance: Color,
+    // solid angle density of `direction`, 1 for delta lights
+    pub pdf: f64,
+}
+
+// light source sampled by next-event estimation. lights without geometry are
+// never seen by camera and scattered rays
+pub trait Light {
+    fn sample(&self, p: &Point3, rng: &mut PCG32RNG) -> Option<LightSample>;
+
+    // solid angle density with which `sample` picks the direction from `p`
+    // towards `q`, a point on an emitter found by a scattered ray. zero if
+    // `q` is not on this light, and always for lights without geometry
+    fn pdf(&self, _p: &Point3, _q: &Point3) -> f64 {
+        0.0
+    }
+
+    // extent and power of the emission, None for lights at infinity
+    fn bounds(&self) -> Option<LightBounds> {
+        None
+    }
+}
@@ -0,0 +1,258 @@
+use std::{f64::consts::PI, rc::Rc};
+
+use crate::{
+    aabb::Aabb,
+    color::{Color, luminance},
+    ies::IesProfile,
+    light::{Light, LightSample, bounds::LightBounds},
+    onb::Onb,
+    sky::PHOTOMETRIC_SCALE,
+    util::rand::PCG32RNG,
+    vec3::{Point3, Vec3},
+};
+
+// point light whose intensity follows a measured ies distribution. the
+// profile's nadir points at `target` and its 0 degree horizontal plane
+// towards `c0`, the luminaire's length axis in most files. only the part of
+// `c0` perpendicular to the nadir counts, and horizontal angles then run
+// counterclockwise seen from above (from the side opposite the nadir).
+// `intensity` is the radiant intensity at the profile's peak
+pub struct PhotometricLight {
+    position: Point3,
+    // z is the nadir, x the 0 degree horizontal direction
+    frame: Onb,
+    profile: Rc<IesProfile>,
+    intensity: Color,
+    // horizontal rotation of the profile around its axis, in degrees
+    rotation: f64,
+}
+
+impl PhotometricLight {
+    pub fn new(
+        position: Point3,
+        target: Point3,
+        c0: Vec3,
+        profile: Rc<IesProfile>,
+        intensity: Color,
+    ) -> Self {
+        Self {
+            position,
+            frame: Onb::from_tangent(&(target - position), &c0),
+            profile,
+            intensity,
+            rotation: 0.0,
+        }
+    }
+
+    // uses the profile's absolute candela values, so the light has the
+    // measured luminaire's brightness in the exposure of `PreethamSky`.
+    // `meters_per_unit` is the size of a scene unit and `tint` the light's
+    // color, normalized to unit luminance
+    pub fn from_candela(
+        position: Point3,
+        target: Point3,
+        c0: Vec3,
+        profile: Rc<IesProfile>,
+        tint: Color,
+        meters_per_unit: f64,
+    ) -> Self {
+        // candela to klux at one meter, then to one scene unit
+        let peak = profile.max_candela() / 1000.0 * PHOTOMETRIC_SCALE
+            / (meters_per_unit * meters_per_unit);
+        let tint = match luminance(&tint) {
+            l if l > 0.0 => tint / l,
+            _ => Color::default(),
+        };
+        Self::new(position, target, c0, profile, peak * tint)
+    }
+
+    pub fn with_rotation(mut self, deg: f64) -> Self {
+        self.rotation = deg;
+        self
+    }
+
+    // fraction of the peak intensity emitted towards `w`, a unit vector
+    fn scale(&self, w: &Vec3) -> f64 {
+        let max = self.profile.max_candela();
+        if max <= 0.0 {
+            return 0.0;
+        }
+        let local = self.frame.to_local(w);
+        let theta = local.z().clamp(-1.0, 1.0).acos().to_degrees();
+        let phi = local.y().atan2(local.x()).to_degrees() - self.rotation;
+        self.profile.candela(theta, phi) / max
+    }
+}
+
+impl Light for PhotometricLight {
+    fn sample(&self, p: &Point3, _rng: &mut PCG32RNG) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.len_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        let direction = to_light / distance;
+
+        let scale = self.scale(&-direction);
+        if scale <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: scale * self.intensity / distance_squared,
+            pdf: 1.0,
+        })
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let max = self.profile.max_candela();
+        let mean = if max > 0.0 {
+            self.profile.mean_candela() / max
+        } else {
+            0.0
+        };
+        Some(LightBounds::omni(
+            Aabb::from_points(&self.position, &self.position),
+            4.0 * PI * mean * luminance(&self.intensity),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_IES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/ies/test.ies");
+
+    fn downlight() -> PhotometricLight {
+        PhotometricLight::new(
+            Point3::new(0.0, 2.0, 0.0),
+            Point3::default(),
+            Vec3::new(1.0, 0.0, 0.0),
+            Rc::new(IesProfile::load(TEST_IES).unwrap()),
+            Color::new(4.0, 8.0, 12.0),
+        )
+    }
+
+    // unit direction at `theta` from the nadir and `phi` around it, in the
+    // light's frame
+    fn direction(light: &PhotometricLight, theta: f64, phi: f64) -> Vec3 {
+        let (theta, phi) = (theta.to_radians(), phi.to_radians());
+        light.frame.transform(&Vec3::new(
+            theta.sin() * phi.cos(),
+            theta.sin() * phi.sin(),
+            theta.cos(),
+        ))
+    }
+
+    #[test]
+    fn nadir_receives_the_peak_intensity() {
+        let light = downlight();
+        let mut rng = PCG32RNG::default();
+        let sample = light.sample(&Point3::default(), &mut rng).unwrap();
+        assert!((sample.direction - Vec3::new(0.0, 1.0, 0.0)).len() < 1e-12);
+        assert_eq!(sample.distance, 2.0);
+        assert!((sample.radiance - Color::new(1.0, 2.0, 3.0)).len() < 1e-9);
+        assert_eq!(sample.pdf, 1.0);
+
+        // the profile emits nothing above the horizon
+        assert!(
+            light
+                .sample(&Point3::new(0.0, 4.0, 0.0), &mut rng)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn scale_follows_the_profile() {
+        let light = downlight();
+        for (theta, phi, candela) in [(30.0, 0.0, 900.0), (30.0, 45.0, 675.0), (30.0, 90.0, 379.7)]
+        {
+            let scale = light.scale(&direction(&light, theta, phi));
+            assert!(
+                (scale - candela / 1200.0).abs() < 1e-6,
+                "{theta} {phi}: {scale}"
+            );
+        }
+    }
+
+    #[test]
+    fn from_candela_uses_the_measured_intensity() {
+        let profile = Rc::new(IesProfile::load(TEST_IES).unwrap());
+        let mut rng = PCG32RNG::default();
+        let white = Color::new(1.0, 1.0, 1.0);
+        // 1200 cd at 2 meters is 300 lux, and at 1 meter 1200 lux
+        for (meters_per_unit, lux) in [(1.0, 300.0), (0.5, 1200.0)] {
+            let light = PhotometricLight::from_candela(
+                Point3::new(0.0, 2.0, 0.0),
+                Point3::default(),
+                Vec3::new(1.0, 0.0, 0.0),
+                profile.clone(),
+                white,
+                meters_per_unit,
+            );
+            let sample = light.sample(&Point3::default(), &mut rng).unwrap();
+            let expected = lux / 1000.0 * PHOTOMETRIC_SCALE;
+            assert!((sample.radiance - expected * white).len() < 1e-12);
+        }
+
+        // tints keep the luminance
+        let light = PhotometricLight::from_candela(
+            Point3::new(0.0, 2.0, 0.0),
+            Point3::default(),
+            Vec3::new(1.0, 0.0, 0.0),
+            profile,
+            Color::new(2.0, 1.0, 0.5),
+            1.0,
+        );
+        let sample = light.sample(&Point3::default(), &mut rng).unwrap();
+        assert!((luminance(&sample.radiance) - 0.3 * PHOTOMETRIC_SCALE).abs() < 1e-12);
+    }
+
+    #[test]
+    fn c0_fixes_the_horizontal_planes_in_the_world() {
+        let (sin, cos) = 30.0_f64.to_radians().sin_cos();
+        let light = downlight();
+        // wide along x, narrow along z, with 45 degrees counterclockwise
+        // from x seen from above lying towards -z
+        let wide = light.scale(&Vec3::new(sin, -cos, 0.0));
+        assert!((wide - 900.0 / 1200.0).abs() < 1e-6);
+        let narrow = light.scale(&Vec3::new(0.0, -cos, sin));
+        assert!((narrow - 379.7 / 1200.0).abs() < 1e-6);
+        let diagonal = Vec3::new(sin * 0.5_f64.sqrt(), -cos, -sin * 0.5_f64.sqrt());
+        assert!((diagonal - direction(&light, 30.0, 45.0)).len() < 1e-12);
+
+        // the part of c0 along the nadir is ignored
+        let tilted = PhotometricLight::new(
+            Point3::new(0.0, 2.0, 0.0),
+            Point3::default(),
+            Vec3::new(1.0, 3.0, 0.0),
+            light.profile.clone(),
+            light.intensity,
+        );
+        assert!((tilted.scale(&Vec3::new(sin, -cos, 0.0)) - wide).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rotation_turns_the_profile_around_its_axis() {
+        let light = downlight().with_rotation(90.0);
+        // the narrow 90 degree plane now lies along the frame's x axis
+        let scale = light.scale(&direction(&light, 30.0, 0.0));
+        assert!((scale - 379.7 / 1200.0).abs() < 1e-6);
+        let scale = light.scale(&direction(&light, 30.0, 90.0));
+        assert!((scale - 900.0 / 1200.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn bounds_power_uses_the_mean_intensity() {
+        let light = downlight();
+        let bounds = light.bounds().unwrap();
+        let mean = light.profile.mean_candela() / 1200.0;
+        assert!(mean > 0.0 && mean < 1.0);
+        let expected = 4.0 * PI * mean * luminance(&Color::new(4.0, 8.0, 12.0));
+        assert!((bounds.phi - expected).abs() < 1e-9 * expected);
+    }
+}
@@ -0,0 +1,73 @@
+use std::f64::consts::PI;
+
+use crate::{
+    aabb::Aabb,
+    color::{Color, luminance},
+    light::{Light, LightSample, bounds::LightBounds},
+    util::rand::PCG32RNG,
+    vec3::Point3,
+};
+
+// isotropic point light. `intensity` is the radiant intensity, so the
+// irradiance falls off with the squared distance
+pub struct PointLight {
+    position: Point3,
+    intensity: Color,
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, p: &Point3, _rng: &mut PCG32RNG) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.len_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+
+        Some(LightSample {
+            direction: to_light / distance,
+            distance,
+            radiance: self.intensity / distance_squared,
+            pdf: 1.0,
+        })
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        Some(LightBounds::omni(
+            Aabb::from_points(&self.position, &self.position),
+            4.0 * PI * luminance(&self.intensity),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn irradiance_falls_off_with_squared_distance() {
+        let light = PointLight::new(Point3::new(0.0, 2.0, 0.0), Color::new(4.0, 8.0, 12.0));
+        let mut rng = PCG32RNG::default();
+        let sample = light.sample(&Point3::default(), &mut rng).unwrap();
+        assert_eq!(sample.direction, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(sample.distance, 2.0);
+        assert_eq!(sample.radiance, Color::new(1.0, 2.0, 3.0));
+        assert_eq!(sample.pdf, 1.0);
+
+        assert!(
+            light
+                .sample(&Point3::new(0.0, 2.0, 0.0), &mut rng)
+                .is_none()
+        );
+    }
+}
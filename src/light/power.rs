@@ -0,0 +1,66 @@
+use std::rc::Rc;
+
+use crate::{
+    light::{Light, LightSample},
+    util::{alias_table::AliasTable, rand::PCG32RNG},
+    vec3::Point3,
+};
+
+// picks lights in proportion to their emitted power. lights at infinity have
+// no finite power and share the choice uniformly with the finite ones as a
+// group
+pub struct PowerLightSampler {
+    lights: Vec<Rc<dyn Light>>,
+    table: Option<AliasTable>,
+    infinite: Vec<Rc<dyn Light>>,
+}
+
+impl PowerLightSampler {
+    pub fn new(lights: Vec<Rc<dyn Light>>) -> Self {
+        let (finite, infinite): (Vec<_>, Vec<_>) =
+            lights.into_iter().partition(|l| l.bounds().is_some());
+        let power: Vec<f64> = finite.iter().map(|l| l.bounds().unwrap().phi).collect();
+        Self {
+            table: AliasTable::new(&power),
+            lights: finite,
+            infinite,
+        }
+    }
+}
+
+impl Light for PowerLightSampler {
+    fn sample(&self, p: &Point3, rng: &mut PCG32RNG) -> Option<LightSample> {
+        let groups = self.infinite.len() + usize::from(self.table.is_some());
+        if groups == 0 {
+            return None;
+        }
+        let pick = ((rng.random_f64() * groups as f64) as usize).min(groups - 1);
+
+        if let Some(light) = self.infinite.get(pick) {
+            let mut sample = light.sample(p, rng)?;
+            sample.pdf /= groups as f64;
+            return Some(sample);
+        }
+
+        let (i, pmf) = self.table.as_ref()?.sample(rng);
+        let mut sample = self.lights[i].sample(p, rng)?;
+        sample.pdf *= pmf / groups as f64;
+        Some(sample)
+    }
+
+    fn pdf(&self, p: &Point3, q: &Point3) -> f64 {
+        let groups = self.infinite.len() + usize::from(self.table.is_some());
+        if groups == 0 {
+            return 0.0;
+        }
+        let infinite: f64 = self.infinite.iter().map(|l| l.pdf(p, q)).sum();
+        let finite: f64 = self.table.as_ref().map_or(0.0, |table| {
+            self.lights
+                .iter()
+                .enumerate()
+                .map(|(i, l)| table.pmf(i) * l.pdf(p, q))
+                .sum()
+        });
+        (infinite + finite) / groups as f64
+    }
+}
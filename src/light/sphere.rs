@@ -0,0 +1,166 @@
+use std::{f64::consts::PI, rc::Rc};
+
+use crate::{
+    aabb::Aabb,
+    color::{Color, luminance},
+    hittable::{HitRecord, Hittable, sphere::Sphere},
+    interval::Interval,
+    light::{Light, LightSample, bounds::LightBounds},
+    material::DiffuseLight,
+    onb::Onb,
+    ray::Ray,
+    util::rand::PCG32RNG,
+    vec3::{Point3, Vec3},
+};
+
+// sphere emitting `emission` radiance from its surface. add it to both the
+// world and the lights: it is sampled by the solid angle it subtends and
+// seen directly by camera and specular rays
+pub struct SphereLight {
+    center: Point3,
+    radius: f64,
+    emission: Color,
+    shape: Sphere,
+}
+
+impl SphereLight {
+    pub fn new(center: Point3, radius: f64, emission: Color) -> Self {
+        Self {
+            center,
+            radius,
+            emission,
+            shape: Sphere::new(center, radius, Rc::new(DiffuseLight::new(emission))),
+        }
+    }
+
+    // 1 - cos of the half angle of the cone the sphere subtends from `p`,
+    // None from inside the sphere
+    fn one_minus_cos_max(&self, p: &Point3) -> Option<f64> {
+        let dist2 = (self.center - *p).len_squared();
+        let r2 = self.radius * self.radius;
+        if dist2 <= r2 {
+            return None;
+        }
+        let sin2_theta_max = r2 / dist2;
+        // without the cancellation for tiny cones
+        Some(if sin2_theta_max < 1e-4 {
+            0.5 * sin2_theta_max
+        } else {
+            1.0 - (1.0 - sin2_theta_max).sqrt()
+        })
+    }
+}
+
+impl Hittable for SphereLight {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rng: &mut PCG32RNG) -> Option<HitRecord> {
+        self.shape.hit(r, ray_t, rng)
+    }
+}
+
+impl Light for SphereLight {
+    fn sample(&self, p: &Point3, rng: &mut PCG32RNG) -> Option<LightSample> {
+        // uniform over the cone of directions hitting the sphere
+        let one_minus_cos_max = self.one_minus_cos_max(p)?;
+        let to_center = self.center - *p;
+        let dist2 = to_center.len_squared();
+        let r2 = self.radius * self.radius;
+
+        let cos_theta = 1.0 - rng.random_f64() * one_minus_cos_max;
+        let sin2_theta = (1.0 - cos_theta * cos_theta).max(0.0);
+        let phi = 2.0 * PI * rng.random_f64();
+        let sin_theta = sin2_theta.sqrt();
+        let frame = Onb::new(&to_center);
+        let direction = frame
+            .transform(&Vec3::new(
+                sin_theta * phi.cos(),
+                sin_theta * phi.sin(),
+                cos_theta,
+            ))
+            .unit_vector();
+
+        let dist = dist2.sqrt();
+        let distance = dist * cos_theta - (r2 - dist2 * sin2_theta).max(0.0).sqrt();
+
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: self.emission,
+            pdf: 1.0 / (2.0 * PI * one_minus_cos_max),
+        })
+    }
+
+    fn pdf(&self, p: &Point3, q: &Point3) -> f64 {
+        let off_surface = ((*q - self.center).len() - self.radius).abs();
+        // also rejects the nan of a degenerate `q`
+        if off_surface.is_nan() || off_surface > 1e-6 * self.radius {
+            return 0.0;
+        }
+        self.one_minus_cos_max(p).map_or(0.0, |one_minus_cos_max| {
+            1.0 / (2.0 * PI * one_minus_cos_max)
+        })
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let extent = Vec3::new(self.radius, self.radius, self.radius);
+        let bbox = Aabb::from_points(&(self.center - extent), &(self.center + extent));
+        // lambertian emitter: pi * L per unit area
+        let area = 4.0 * PI * self.radius * self.radius;
+        Some(LightBounds::omni(
+            bbox,
+            PI * area * luminance(&self.emission),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_land_on_the_sphere() {
+        let light = SphereLight::new(Point3::new(0.0, 3.0, 0.0), 1.0, Color::new(1.0, 1.0, 1.0));
+        let p = Point3::new(0.5, 0.0, 0.0);
+        let mut rng = PCG32RNG::default();
+        for _ in 0..1000 {
+            let sample = light.sample(&p, &mut rng).unwrap();
+            let rec = light
+                .hit(
+                    &Ray::new(p, sample.direction, 0.0),
+                    &Interval::new(0.0, f64::INFINITY),
+                    &mut rng,
+                )
+                .unwrap();
+            assert!((rec.t - sample.distance).abs() < 1e-9);
+        }
+        // nothing to sample from inside
+        assert!(
+            light
+                .sample(&Point3::new(0.0, 2.5, 0.0), &mut rng)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn irradiance_matches_the_subtended_cap() {
+        // on a surface facing the center, E = pi * L * (r / d)^2
+        let (r, d) = (0.5, 2.0);
+        let light = SphereLight::new(Point3::new(0.0, d, 0.0), r, Color::new(2.0, 2.0, 2.0));
+        let mut rng = PCG32RNG::default();
+        let n = 20000;
+        let mut irradiance = 0.0;
+        for _ in 0..n {
+            let sample = light.sample(&Point3::default(), &mut rng).unwrap();
+            irradiance += sample.radiance.x() * sample.direction.y() / sample.pdf / n as f64;
+        }
+        let expected = PI * 2.0 * (r / d) * (r / d);
+        assert!(
+            (irradiance - expected).abs() < 1e-3 * expected,
+            "{irradiance}"
+        );
+
+        // far away lights keep their precision
+        let tiny = SphereLight::new(Point3::new(0.0, 1e4, 0.0), 1.0, Color::new(1.0, 1.0, 1.0));
+        let sample = tiny.sample(&Point3::default(), &mut rng).unwrap();
+        assert!((1.0 / sample.pdf - PI * 1e-8).abs() < 1e-6 * PI * 1e-8);
+    }
+}
@@ -0,0 +1,138 @@
+use std::f64::consts::PI;
+
+use crate::{
+    aabb::Aabb,
+    color::{Color, luminance},
+    light::{Light, LightSample, bounds::LightBounds},
+    util::{self, rand::PCG32RNG},
+    vec3::{Point3, Vec3},
+};
+
+// point light restricted to a cone around the direction of `target`. the
+// intensity is full inside `falloff_start` degrees off the axis and eases
+// to zero at `cone_angle` degrees
+pub struct SpotLight {
+    position: Point3,
+    axis: Vec3,
+    intensity: Color,
+    cos_cone_angle: f64,
+    cos_falloff_start: f64,
+}
+
+impl SpotLight {
+    pub fn new(
+        position: Point3,
+        target: Point3,
+        intensity: Color,
+        cone_angle: f64,
+        falloff_start: f64,
+    ) -> Self {
+        let cos_cone_angle = util::deg_to_rad(cone_angle).cos();
+        Self {
+            position,
+            axis: (target - position).unit_vector(),
+            intensity,
+            cos_cone_angle,
+            cos_falloff_start: util::deg_to_rad(falloff_start.min(cone_angle))
+                .cos()
+                .max(cos_cone_angle),
+        }
+    }
+
+    // fraction of the intensity emitted towards `w`, a unit vector
+    fn falloff(&self, w: &Vec3) -> f64 {
+        let cos_theta = Vec3::dot(w, &self.axis);
+        if cos_theta >= self.cos_falloff_start {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_cone_angle {
+            return 0.0;
+        }
+        let t = (cos_theta - self.cos_cone_angle) / (self.cos_falloff_start - self.cos_cone_angle);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, p: &Point3, _rng: &mut PCG32RNG) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.len_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        let direction = to_light / distance;
+
+        let falloff = self.falloff(&-direction);
+        if falloff <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: falloff * self.intensity / distance_squared,
+            pdf: 1.0,
+        })
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        // like pbrt, the power of the matching point light keeps spot and
+        // point lights comparable in the importance estimate
+        Some(LightBounds {
+            bbox: Aabb::from_points(&self.position, &self.position),
+            w: self.axis,
+            cos_theta_o: self.cos_falloff_start,
+            cos_theta_e: (self.cos_cone_angle.acos() - self.cos_falloff_start.acos()).cos(),
+            phi: 4.0 * PI * luminance(&self.intensity),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spot() -> SpotLight {
+        // pointing straight down from a height of 1
+        SpotLight::new(
+            Point3::new(0.0, 1.0, 0.0),
+            Point3::default(),
+            Color::new(1.0, 1.0, 1.0),
+            45.0,
+            20.0,
+        )
+    }
+
+    // irradiance on the floor `deg` degrees off the axis, at unit distance
+    fn falloff_at(light: &SpotLight, deg: f64) -> f64 {
+        let (sin, cos) = util::deg_to_rad(deg).sin_cos();
+        let p = Point3::new(sin, 1.0 - cos, 0.0);
+        let mut rng = PCG32RNG::default();
+        light.sample(&p, &mut rng).map_or(0.0, |s| s.radiance.x())
+    }
+
+    #[test]
+    fn cone_eases_out_between_the_angles() {
+        let light = spot();
+        assert!((falloff_at(&light, 0.0) - 1.0).abs() < 1e-12);
+        assert!((falloff_at(&light, 19.0) - 1.0).abs() < 1e-12);
+        assert_eq!(falloff_at(&light, 46.0), 0.0);
+        assert_eq!(falloff_at(&light, 120.0), 0.0);
+
+        let mut last = 1.0;
+        for deg in [25.0, 30.0, 35.0, 40.0, 44.0] {
+            let f = falloff_at(&light, deg);
+            assert!(f > 0.0 && f < last, "{deg}: {f}");
+            last = f;
+        }
+    }
+
+    #[test]
+    fn bounds_cover_the_cone() {
+        let b = spot().bounds().unwrap();
+        assert_eq!(b.w, Vec3::new(0.0, -1.0, 0.0));
+        assert!(b.importance(&Point3::new(0.3, 0.0, 0.0)) > 0.0);
+        assert_eq!(b.importance(&Point3::new(0.0, 2.0, 0.0)), 0.0);
+    }
+}
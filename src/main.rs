@@ -1,97 +1,42 @@
+mod aabb;
+mod aperture;
 mod camera;
 mod color;
 mod hittable;
+mod ies;
+mod image;
 mod interval;
+mod lens;
+mod light;
+mod mat4;
 mod material;
+mod onb;
+mod phase;
+mod quat;
 mod ray;
+mod scenes;
+mod sky;
+mod spectrum;
+mod texture;
 mod util;
 mod vec3;
+mod volume;
 
-use std::{io, rc::Rc};
+use std::{env, io};
 
-use crate::{
-    camera::Camera,
-    color::Color,
-    hittable::{list::HittableList, sphere::Sphere},
-    material::{Dielectric, Lambertian, Material, Metal},
-    util::rand::PCG32RNG,
-    vec3::{Point3, Vec3},
-};
+use crate::util::rand::PCG32RNG;
 
+// usage: raytrsing [scene] [asset] > image.ppm
 fn main() -> io::Result<()> {
-    let mut world = HittableList::default();
     let mut rng = PCG32RNG::default();
-
-    let sp_ground = Rc::new(Sphere::new(
-        Point3::new(0.0, -1000.0, 0.0),
-        1000.0,
-        Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
-    ));
-    world.add(sp_ground.clone());
-
-    let mut choose_mat: f64;
-    let mut center: Point3;
-    for a in -11..11 {
-        for b in -11..11 {
-            let a = a as f64;
-            let b = b as f64;
-
-            choose_mat = rng.random_f64();
-            center = Point3::new(a + 0.9 * rng.random_f64(), 0.2, b + 0.9 * rng.random_f64());
-
-            if (center - Point3::new(4.0, 0.2, 0.0)).len() > 0.9 {
-                let sphere_mat: Rc<dyn Material> = {
-                    if choose_mat < 0.8 {
-                        Rc::new(Lambertian::new(
-                            Color::random(&mut rng) * Color::random(&mut rng),
-                        ))
-                    } else if choose_mat < 0.95 {
-                        Rc::new(Metal::new(
-                            Color::random_bounded(&mut rng, 0.5, 1.0),
-                            rng.random_bounded_f64(0.0, 0.5),
-                        ))
-                    } else {
-                        Rc::new(Dielectric::new(1.5))
-                    }
-                };
-                let sp = Rc::new(Sphere::new(center, 0.2, sphere_mat));
-                world.add(sp.clone());
-            }
-        }
-    }
-
-    let sp1 = Rc::new(Sphere::new(
-        Point3::new(0.0, 1.0, 0.0),
-        1.0,
-        Rc::new(Dielectric::new(1.5)),
-    ));
-    world.add(sp1.clone());
-
-    let sp2 = Rc::new(Sphere::new(
-        Point3::new(-4.0, 1.0, 0.0),
-        1.0,
-        Rc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1))),
-    ));
-    world.add(sp2.clone());
-
-    let sp3 = Rc::new(Sphere::new(
-        Point3::new(4.0, 1.0, 0.0),
-        1.0,
-        Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0)),
-    ));
-    world.add(sp3.clone());
-
-    let cam = Camera::new(
-        16.0 / 10.0,
-        1200,
-        10,
-        50,
-        20.0,
-        Point3::new(13.0, 2.0, 3.0),
-        Point3::new(0.0, 0.0, 0.0),
-        Vec3::new(0.0, 1.0, 0.0),
-        0.6,
-        10.0,
-    );
-    cam.render(&world, &mut rng)
+    let name = env::args().nth(1);
+    let asset = env::args().nth(2);
+    let scene = scenes::load(
+        name.as_deref().unwrap_or(scenes::NAMES[0]),
+        asset.as_deref(),
+        &mut rng,
+    )?;
+    scene
+        .camera
+        .render(&scene.world, scene.lights.as_ref(), &mut rng)
 }
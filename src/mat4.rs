@@ -0,0 +1,342 @@
+use std::ops;
+
+use crate::{
+    quat::Quat,
+    util,
+    vec3::{Point3, Vec3},
+};
+
+// row-major 4x4 matrix for affine transforms. points are treated as column
+// vectors with an implicit w = 1, vectors with an implicit w = 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    m: [[f64; 4]; 4],
+}
+
+impl Mat4 {
+    pub const IDENTITY: Self = Self {
+        m: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub fn new(m: [[f64; 4]; 4]) -> Self {
+        Self { m }
+    }
+
+    pub fn translate(offset: &Vec3) -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0, offset.x()],
+            [0.0, 1.0, 0.0, offset.y()],
+            [0.0, 0.0, 1.0, offset.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scale(factors: &Vec3) -> Self {
+        Self::new([
+            [factors.x(), 0.0, 0.0, 0.0],
+            [0.0, factors.y(), 0.0, 0.0],
+            [0.0, 0.0, factors.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // rotation of `deg` degrees about `axis` (right-handed, Rodrigues' formula)
+    pub fn rotate(axis: &Vec3, deg: f64) -> Self {
+        let a = axis.unit_vector();
+        let (x, y, z) = (a.x(), a.y(), a.z());
+        let theta = util::deg_to_rad(deg);
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let t = 1.0 - cos_theta;
+
+        Self::new([
+            [
+                t * x * x + cos_theta,
+                t * x * y - sin_theta * z,
+                t * x * z + sin_theta * y,
+                0.0,
+            ],
+            [
+                t * x * y + sin_theta * z,
+                t * y * y + cos_theta,
+                t * y * z - sin_theta * x,
+                0.0,
+            ],
+            [
+                t * x * z - sin_theta * y,
+                t * y * z + sin_theta * x,
+                t * z * z + cos_theta,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut out = [[0.0; 4]; 4];
+        for (i, row) in out.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = self.m[j][i];
+            }
+        }
+        Self::new(out)
+    }
+
+    // gauss-jordan elimination with partial pivoting.
+    // returns None for singular matrices (e.g. a zero scale factor)
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Self::IDENTITY.m;
+
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap();
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let d = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= d;
+                inv[col][j] *= d;
+            }
+
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let f = a[row][col];
+                if f == 0.0 {
+                    continue;
+                }
+                for j in 0..4 {
+                    a[row][j] -= f * a[col][j];
+                    inv[row][j] -= f * inv[col][j];
+                }
+            }
+        }
+
+        Some(Self::new(inv))
+    }
+
+    // determinant of the upper 3x3
+    fn det3(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    // splits an affine transform into translation * rotation * stretch, with
+    // the rotation from a polar decomposition of the upper 3x3 (shoemake and
+    // duff 1992). a mirroring is left in the stretch. None for singular
+    // matrices
+    pub fn decompose(&self) -> Option<(Vec3, Quat, Self)> {
+        let translation = Vec3::new(self.m[0][3], self.m[1][3], self.m[2][3]);
+        let mut m = *self;
+        for i in 0..3 {
+            m.m[i][3] = 0.0;
+            m.m[3][i] = 0.0;
+        }
+        m.m[3][3] = 1.0;
+
+        // average the matrix with its inverse transpose until it converges
+        // to the closest orthogonal matrix
+        let mut r = m;
+        for _ in 0..100 {
+            let next = (r + r.transpose().inverse()?) * 0.5;
+            let diff = (0..3)
+                .map(|i| {
+                    (0..3)
+                        .map(|j| (next.m[i][j] - r.m[i][j]).abs())
+                        .sum::<f64>()
+                })
+                .fold(0.0, f64::max);
+            r = next;
+            if diff < 1e-10 {
+                break;
+            }
+        }
+        if r.det3() < 0.0 {
+            r = r * Self::scale(&Vec3::new(-1.0, -1.0, -1.0));
+        }
+
+        let stretch = r.inverse()? * m;
+        Some((translation, Quat::from_mat4(&r), stretch))
+    }
+
+    pub fn transform_point(&self, p: &Point3) -> Point3 {
+        let m = &self.m;
+        let x = m[0][0] * p.x() + m[0][1] * p.y() + m[0][2] * p.z() + m[0][3];
+        let y = m[1][0] * p.x() + m[1][1] * p.y() + m[1][2] * p.z() + m[1][3];
+        let z = m[2][0] * p.x() + m[2][1] * p.y() + m[2][2] * p.z() + m[2][3];
+        let w = m[3][0] * p.x() + m[3][1] * p.y() + m[3][2] * p.z() + m[3][3];
+
+        if w == 1.0 {
+            Point3::new(x, y, z)
+        } else {
+            Point3::new(x, y, z) / w
+        }
+    }
+
+    pub fn transform_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+            m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+            m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+        )
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl ops::Index<usize> for Mat4 {
+    type Output = [f64; 4];
+
+    fn index(&self, row: usize) -> &Self::Output {
+        &self.m[row]
+    }
+}
+
+impl ops::Mul for Mat4 {
+    type Output = Self;
+
+    // `a * b` applies `b` first, then `a`
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut out = [[0.0; 4]; 4];
+        for (i, row) in out.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Self::new(out)
+    }
+}
+
+impl ops::Add for Mat4 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let mut out = self.m;
+        for (row, rhs_row) in out.iter_mut().zip(rhs.m) {
+            for (v, r) in row.iter_mut().zip(rhs_row) {
+                *v += r;
+            }
+        }
+        Self::new(out)
+    }
+}
+
+impl ops::Mul<f64> for Mat4 {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        let mut out = self.m;
+        for v in out.iter_mut().flatten() {
+            *v *= rhs;
+        }
+        Self::new(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_mat_eq(a: &Mat4, b: &Mat4) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((a[i][j] - b[i][j]).abs() < 1e-9, "{a:?} != {b:?}");
+            }
+        }
+    }
+
+    fn affine() -> Mat4 {
+        Mat4::translate(&Vec3::new(1.0, -2.0, 3.0))
+            * Mat4::rotate(&Vec3::new(1.0, 1.0, 0.0), 40.0)
+            * Mat4::scale(&Vec3::new(2.0, 0.5, 3.0))
+    }
+
+    #[test]
+    fn inverse_undoes_the_transform() {
+        let m = affine();
+        let inv = m.inverse().unwrap();
+        assert_mat_eq(&(m * inv), &Mat4::IDENTITY);
+        assert_mat_eq(&(inv * m), &Mat4::IDENTITY);
+
+        let p = Point3::new(0.3, -1.2, 4.0);
+        let q = inv.transform_point(&m.transform_point(&p));
+        assert!((q - p).len() < 1e-12);
+    }
+
+    #[test]
+    fn inverse_pivots_around_zero_diagonals() {
+        // a permutation has only zeros on its diagonal
+        let m = Mat4::new([
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        assert_mat_eq(&m.inverse().unwrap(), &m.transpose());
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        assert!(Mat4::scale(&Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+        assert!(Mat4::new([[1.0; 4]; 4]).inverse().is_none());
+    }
+
+    #[test]
+    fn multiplication_applies_the_right_operand_first() {
+        let t = Mat4::translate(&Vec3::new(1.0, 0.0, 0.0));
+        let r = Mat4::rotate(&Vec3::new(0.0, 0.0, 1.0), 90.0);
+        let p = (t * r).transform_point(&Point3::new(1.0, 0.0, 0.0));
+        assert!((p - Point3::new(1.0, 1.0, 0.0)).len() < 1e-12);
+
+        // vectors ignore the translation
+        let v = (t * r).transform_vector(&Vec3::new(1.0, 0.0, 0.0));
+        assert!((v - Vec3::new(0.0, 1.0, 0.0)).len() < 1e-12);
+    }
+
+    #[test]
+    fn rotations_are_orthonormal() {
+        let r = Mat4::rotate(&Vec3::new(-0.3, 2.0, 0.7), 123.0);
+        assert_mat_eq(&r.transpose(), &r.inverse().unwrap());
+        assert!((r.det3() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn decompose_recovers_the_factors() {
+        let (translation, rotation, stretch) = affine().decompose().unwrap();
+        assert!((translation - Vec3::new(1.0, -2.0, 3.0)).len() < 1e-12);
+        assert_mat_eq(
+            &rotation.to_mat4(),
+            &Mat4::rotate(&Vec3::new(1.0, 1.0, 0.0), 40.0),
+        );
+        assert_mat_eq(&stretch, &Mat4::scale(&Vec3::new(2.0, 0.5, 3.0)));
+    }
+
+    #[test]
+    fn decompose_keeps_mirroring_in_the_stretch() {
+        let m =
+            Mat4::rotate(&Vec3::new(0.0, 1.0, 0.0), 30.0) * Mat4::scale(&Vec3::new(-1.0, 1.0, 1.0));
+        let (_, rotation, stretch) = m.decompose().unwrap();
+        let r = rotation.to_mat4();
+        assert!((r.det3() - 1.0).abs() < 1e-9);
+        assert!(stretch.det3() < 0.0);
+        assert_mat_eq(&(r * stretch), &m);
+    }
+}
@@ -0,0 +1,146 @@
+use std::rc::Rc;
+
+use crate::{
+    color::Color,
+    hittable::HitRecord,
+    material::{Material, Scatter},
+    phase::PhaseFunction,
+    ray::Ray,
+    texture::Texture,
+    util::rand::PCG32RNG,
+    vec3::Point3,
+};
+
+// cuts holes into another material with an opacity texture (foliage,
+// fences). the opacity is the average of the texture's channels
+pub struct AlphaMask {
+    material: Rc<dyn Material>,
+    opacity: Rc<dyn Texture>,
+    // hard alpha test threshold; without one the opacity is used as the
+    // probability of a hit
+    cutoff: Option<f64>,
+}
+
+impl AlphaMask {
+    pub fn new(material: Rc<dyn Material>, opacity: Rc<dyn Texture>) -> Self {
+        Self {
+            material,
+            opacity,
+            cutoff: None,
+        }
+    }
+
+    pub fn with_cutoff(mut self, cutoff: f64) -> Self {
+        self.cutoff = Some(cutoff);
+        self
+    }
+}
+
+impl Material for AlphaMask {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter> {
+        self.material.scatter(r_in, rec, rng)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        self.material.eval(r_in, rec, scattered)
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        self.material.pdf(r_in, rec, scattered)
+    }
+
+    fn absorption(&self) -> Option<Color> {
+        self.material.absorption()
+    }
+
+    fn scattering(&self) -> Option<(Color, Rc<dyn PhaseFunction>)> {
+        self.material.scattering()
+    }
+
+    fn dispersive(&self) -> bool {
+        self.material.dispersive()
+    }
+
+    fn alpha(&self, u: f64, v: f64, p: &Point3) -> f64 {
+        let c = self.opacity.value(u, v, p);
+        let alpha = ((c.x() + c.y() + c.z()) / 3.0).clamp(0.0, 1.0);
+        match self.cutoff {
+            Some(cutoff) if alpha >= cutoff => 1.0,
+            Some(_) => 0.0,
+            None => alpha,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::{Hittable, quad::Quad},
+        interval::Interval,
+        material::{
+            Lambertian,
+            test_util::{assert_pdf_matches_scatter, incoming, record},
+        },
+        texture::SolidColor,
+        vec3::Vec3,
+    };
+
+    fn pane(mask: AlphaMask) -> Quad {
+        Quad::new(
+            Point3::new(-1.0, -1.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Rc::new(mask),
+        )
+    }
+
+    fn mask(opacity: f64) -> AlphaMask {
+        AlphaMask::new(
+            Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+            Rc::new(SolidColor::new(Color::new(opacity, opacity, opacity))),
+        )
+    }
+
+    // fraction of rays through the center that hit the pane
+    fn hit_rate(quad: &Quad) -> f64 {
+        let r = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let mut rng = PCG32RNG::default();
+        let n = 10000;
+        let hits = (0..n)
+            .filter(|_| quad.hit(&r, &Interval::new(0.0, 2.0), &mut rng).is_some())
+            .count();
+        hits as f64 / n as f64
+    }
+
+    #[test]
+    fn cutoff_makes_hits_binary() {
+        assert_eq!(hit_rate(&pane(mask(0.6).with_cutoff(0.5))), 1.0);
+        assert_eq!(hit_rate(&pane(mask(0.4).with_cutoff(0.5))), 0.0);
+    }
+
+    #[test]
+    fn opacity_is_the_hit_probability() {
+        assert!((hit_rate(&pane(mask(0.3))) - 0.3).abs() < 0.02);
+        assert_eq!(hit_rate(&pane(mask(0.0))), 0.0);
+        assert_eq!(hit_rate(&pane(mask(1.0))), 1.0);
+
+        // shadow rays agree on average
+        let quad = pane(mask(0.3));
+        let r = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let mut rng = PCG32RNG::default();
+        let n = 10000;
+        let tr: f64 = (0..n)
+            .map(|_| quad.transmittance(&r, &Interval::new(0.0, 2.0), &mut rng))
+            .sum::<f64>()
+            / n as f64;
+        assert!((tr - 0.7).abs() < 0.02);
+    }
+
+    #[test]
+    fn surviving_hits_scatter_like_the_material() {
+        let mask = Rc::new(mask(0.5));
+        let rec = record(mask.clone());
+        assert_pdf_matches_scatter(mask.as_ref(), &incoming(0.7), &rec, 200000);
+    }
+}
@@ -0,0 +1,153 @@
+use std::rc::Rc;
+
+use crate::{
+    color::Color,
+    hittable::HitRecord,
+    material::{Material, Scatter, fresnel},
+    phase::PhaseFunction,
+    ray::Ray,
+    util::rand::PCG32RNG,
+    vec3::{Point3, Vec3},
+};
+
+// smooth dielectric clear coat over any base material (car paint, varnish).
+// light is specularly reflected by the coat with the fresnel probability,
+// otherwise it reaches the base and is weighted by the transmission through
+// the coat on the way out. refraction inside the thin layer is ignored
+pub struct Coated {
+    base: Rc<dyn Material>,
+    refractive_index: f64,
+    // transmittance of a single pass through the coat at normal incidence
+    tint: Color,
+}
+
+impl Coated {
+    pub fn new(base: Rc<dyn Material>, refractive_index: f64) -> Self {
+        Self {
+            base,
+            refractive_index,
+            tint: Color::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    // colored coat, e.g. amber varnish
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    fn fresnel(&self, cos_theta: f64) -> f64 {
+        fresnel::dielectric(cos_theta.abs(), self.refractive_index)
+    }
+
+    // absorption in the coat for light entering at cos_in and leaving at
+    // cos_out (beer-lambert over the slanted path lengths)
+    fn coat_transmittance(&self, cos_in: f64, cos_out: f64) -> Color {
+        let path = 1.0 / cos_in.abs().max(1e-4) + 1.0 / cos_out.abs().max(1e-4);
+        Color::new(
+            self.tint.x().powf(path),
+            self.tint.y().powf(path),
+            self.tint.z().powf(path),
+        )
+    }
+}
+
+impl Material for Coated {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter> {
+        let unit_direction = r_in.direction().unit_vector();
+        let cos_o = Vec3::dot(&-unit_direction, &rec.normal);
+
+        if rng.random_f64() < self.fresnel(cos_o) {
+            let reflected = Vec3::reflect(&unit_direction, &rec.normal);
+            return Some(Scatter::specular(
+                Color::new(1.0, 1.0, 1.0),
+                Ray::new(rec.p, reflected, r_in.time()),
+            ));
+        }
+
+        // the (1 - F) of entering the coat cancels with the probability of
+        // choosing the base
+        let mut scatter = self.base.scatter(r_in, rec, rng)?;
+        let cos_i = Vec3::dot(&scatter.ray.direction().unit_vector(), &rec.normal);
+        let weight = (1.0 - self.fresnel(cos_i)) * self.coat_transmittance(cos_o, cos_i);
+        scatter.attenuation = weight * scatter.attenuation;
+
+        Some(scatter)
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let cos_o = Vec3::dot(&-r_in.direction().unit_vector(), &rec.normal);
+        let cos_i = Vec3::dot(&scattered.direction().unit_vector(), &rec.normal);
+
+        // the specular coat itself is a delta lobe and never evaluated
+        (1.0 - self.fresnel(cos_o))
+            * (1.0 - self.fresnel(cos_i))
+            * self.coat_transmittance(cos_o, cos_i)
+            * self.base.eval(r_in, rec, scattered)
+    }
+
+    // the base is only sampled when the coat does not reflect
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cos_o = Vec3::dot(&-r_in.direction().unit_vector(), &rec.normal);
+        (1.0 - self.fresnel(cos_o)) * self.base.pdf(r_in, rec, scattered)
+    }
+
+    // the thin coat does not change the medium enclosed by the base
+    fn absorption(&self) -> Option<Color> {
+        self.base.absorption()
+    }
+
+    fn scattering(&self) -> Option<(Color, Rc<dyn PhaseFunction>)> {
+        self.base.scattering()
+    }
+
+    fn dispersive(&self) -> bool {
+        self.base.dispersive()
+    }
+
+    fn alpha(&self, u: f64, v: f64, p: &Point3) -> f64 {
+        self.base.alpha(u, v, p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::{
+        Conductor, Dielectric, Lambertian, Subsurface,
+        test_util::{assert_pdf_matches_scatter, incoming, record},
+    };
+
+    #[test]
+    fn coat_keeps_the_base_medium() {
+        let absorption = Color::new(0.1, 0.2, 0.3);
+        let glass = Rc::new(Dielectric::new(1.5).with_absorption(absorption));
+        assert_eq!(Coated::new(glass, 1.5).absorption(), Some(absorption));
+
+        let skin = Rc::new(Subsurface::new(
+            Color::new(0.8, 0.5, 0.4),
+            Color::new(1.0, 0.5, 0.25),
+            1.4,
+        ));
+        let coated = Coated::new(skin.clone(), 1.5);
+        assert_eq!(coated.absorption(), skin.absorption());
+        assert_eq!(
+            coated.scattering().map(|(sigma_s, _)| sigma_s),
+            skin.scattering().map(|(sigma_s, _)| sigma_s)
+        );
+    }
+
+    #[test]
+    fn pdf_matches_sampling() {
+        let paint: Rc<dyn Material> = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let copper: Rc<dyn Material> = Rc::new(Conductor::copper(0.5));
+        for base in [paint, copper] {
+            let coated = Rc::new(Coated::new(base, 1.5));
+            let rec = record(coated.clone());
+            // grazing, where the coat reflects much of the light
+            for cos_theta in [0.8, 0.2] {
+                assert_pdf_matches_scatter(coated.as_ref(), &incoming(cos_theta), &rec, 200000);
+            }
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{
+    color::Color,
+    hittable::HitRecord,
+    material::{
+        Material, Scatter, fresnel,
+        microfacet::{TrowbridgeReitz, reflect},
+    },
+    onb::Onb,
+    ray::Ray,
+    util::rand::PCG32RNG,
+    vec3::Vec3,
+};
+
+// physically based metal: ggx microfacet reflection with the fresnel
+// reflectance of a complex index of refraction eta + i k (per rgb channel)
+pub struct Conductor {
+    eta: Color,
+    k: Color,
+    distrib: TrowbridgeReitz,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: f64) -> Self {
+        Self::anisotropic(eta, k, roughness, roughness)
+    }
+
+    // roughness_u applies along the surface's dpdu and roughness_v across it
+    pub fn anisotropic(eta: Color, k: Color, roughness_u: f64, roughness_v: f64) -> Self {
+        Self {
+            eta,
+            k,
+            distrib: TrowbridgeReitz::from_roughness(roughness_u, roughness_v),
+        }
+    }
+
+    pub fn gold(roughness: f64) -> Self {
+        Self::new(
+            Color::new(0.143119, 0.374957, 1.44248),
+            Color::new(3.98316, 2.38572, 1.60322),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Self::new(
+            Color::new(0.200438, 0.924033, 1.10221),
+            Color::new(3.91295, 2.45285, 2.14219),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: f64) -> Self {
+        Self::new(
+            Color::new(1.65746, 0.880369, 0.521229),
+            Color::new(9.22387, 6.26952, 4.837),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Self {
+        Self::new(
+            Color::new(0.155265, 0.116723, 0.138342),
+            Color::new(4.82835, 3.12225, 2.14696),
+            roughness,
+        )
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter> {
+        let frame = Onb::from_tangent(&rec.normal, &rec.dpdu);
+        let wo = frame.to_local(&-r_in.direction().unit_vector());
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        if self.distrib.effectively_smooth() {
+            let wi = Vec3::new(-wo.x(), -wo.y(), wo.z());
+            let attenuation = fresnel::conductor(wi.z(), &self.eta, &self.k);
+            return Some(Scatter::specular(
+                attenuation,
+                Ray::new(rec.p, frame.transform(&wi), r_in.time()),
+            ));
+        }
+
+        let wm = self.distrib.sample_wm(&wo, rng);
+        let wi = reflect(&wo, &wm);
+        if wi.z() <= 0.0 {
+            return None;
+        }
+
+        // f * cos / pdf for visible normal sampling reduces to F * G / G1
+        let cos_om = Vec3::dot(&wo, &wm);
+        let attenuation = fresnel::conductor(cos_om, &self.eta, &self.k) * self.distrib.g(&wo, &wi)
+            / self.distrib.g1(&wo);
+
+        Some(Scatter::new(
+            attenuation,
+            Ray::new(rec.p, frame.transform(&wi), r_in.time()),
+        ))
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        if self.distrib.effectively_smooth() {
+            return Color::default();
+        }
+
+        let frame = Onb::from_tangent(&rec.normal, &rec.dpdu);
+        let wo = frame.to_local(&-r_in.direction().unit_vector());
+        let wi = frame.to_local(&scattered.direction().unit_vector());
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::default();
+        }
+
+        let wm = wo + wi;
+        if wm.near_zero() {
+            return Color::default();
+        }
+        let wm = wm.unit_vector();
+
+        let f = fresnel::conductor(Vec3::dot(&wo, &wm), &self.eta, &self.k);
+        // cos(theta_i) cancels with the bsdf denominator
+        f * (self.distrib.d(&wm) * self.distrib.g(&wo, &wi) / (4.0 * wo.z()))
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        if self.distrib.effectively_smooth() {
+            return 0.0;
+        }
+
+        let frame = Onb::from_tangent(&rec.normal, &rec.dpdu);
+        let wo = frame.to_local(&-r_in.direction().unit_vector());
+        let wi = frame.to_local(&scattered.direction().unit_vector());
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let wm = wo + wi;
+        if wm.near_zero() {
+            return 0.0;
+        }
+        let wm = wm.unit_vector();
+
+        // jacobian of the reflection about wm
+        self.distrib.pdf(&wo, &wm) / (4.0 * Vec3::dot(&wo, &wm).abs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        material::test_util::{
+            assert_color_near, assert_pdf_matches_scatter, eval_albedo, incoming, record,
+            scatter_albedo,
+        },
+        vec3::Point3,
+    };
+
+    #[test]
+    fn smooth_conductor_is_a_fresnel_mirror() {
+        let gold = Rc::new(Conductor::gold(0.0));
+        let rec = record(gold.clone());
+        let r = incoming(0.6);
+        let mut rng = PCG32RNG::default();
+        let scatter = gold.scatter(&r, &rec, &mut rng).unwrap();
+        assert!(scatter.specular);
+        assert!((scatter.ray.direction().unit_vector() - Vec3::new(0.8, 0.0, 0.6)).len() < 1e-12);
+        let expected = fresnel::conductor(0.6, &gold.eta, &gold.k);
+        assert_color_near(&scatter.attenuation, &expected, 1e-12);
+        assert_eq!(gold.eval(&r, &rec, &scatter.ray), Color::default());
+    }
+
+    #[test]
+    fn eval_matches_sampling() {
+        for (mat, cos_theta) in [
+            (Conductor::copper(0.5), 0.8),
+            (Conductor::aluminium(0.7), 0.3),
+            (
+                Conductor::anisotropic(
+                    Color::new(0.2, 0.9, 1.1),
+                    Color::new(3.9, 2.4, 2.1),
+                    0.6,
+                    0.3,
+                ),
+                0.5,
+            ),
+        ] {
+            let mat = Rc::new(mat);
+            let rec = record(mat.clone());
+            let r = incoming(cos_theta);
+            let sampled = scatter_albedo(mat.as_ref(), &r, &rec, 100000);
+            let integrated = eval_albedo(mat.as_ref(), &r, &rec, 400000);
+            assert_color_near(&sampled, &integrated, 0.02);
+        }
+    }
+
+    #[test]
+    fn roughness_u_spreads_along_dpdu() {
+        let mat = Rc::new(Conductor::anisotropic(
+            Color::new(0.2, 0.2, 0.2),
+            Color::new(3.9, 3.9, 3.9),
+            0.6,
+            0.05,
+        ));
+        // dpdu along y, so the wide lobe lies in the yz plane
+        let mut rec = record(mat.clone());
+        rec.dpdu = Vec3::new(0.0, 2.0, 0.0);
+        let r = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let mut rng = PCG32RNG::default();
+        let (mut spread_x, mut spread_y) = (0.0, 0.0);
+        for _ in 0..1000 {
+            if let Some(scatter) = mat.scatter(&r, &rec, &mut rng) {
+                let d = scatter.ray.direction().unit_vector();
+                spread_x += d.x().abs();
+                spread_y += d.y().abs();
+            }
+        }
+        assert!(spread_y > 5.0 * spread_x, "{spread_x} {spread_y}");
+    }
+
+    #[test]
+    fn pdf_matches_sampling() {
+        let copper = Rc::new(Conductor::anisotropic(
+            Color::new(0.2, 0.92, 1.1),
+            Color::new(3.9, 2.45, 2.14),
+            0.6,
+            0.3,
+        ));
+        let rec = record(copper.clone());
+        assert_pdf_matches_scatter(copper.as_ref(), &incoming(0.6), &rec, 200000);
+    }
+}
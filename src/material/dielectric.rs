@@ -1,19 +1,49 @@
 use crate::{
     color::Color,
     hittable::HitRecord,
-    material::Material,
+    material::{Ior, Material, Scatter},
     ray::Ray,
     util::rand::PCG32RNG,
     vec3::Vec3,
 };
 
 pub struct Dielectric {
-    refractive_index: f64,
+    ior: Ior,
+    // beer-lambert absorption coefficient of the interior, per unit length
+    absorption: Color,
 }
 
 impl Dielectric {
     pub fn new(refractive_index: f64) -> Self {
-        Self { refractive_index }
+        Self::from_ior(Ior::Constant(refractive_index))
+    }
+
+    // a wavelength dependent ior disperses light in spectral rendering
+    pub fn from_ior(ior: Ior) -> Self {
+        Self {
+            ior,
+            absorption: Color::default(),
+        }
+    }
+
+    pub fn with_absorption(mut self, absorption: Color) -> Self {
+        self.absorption = absorption;
+        self
+    }
+
+    // absorption that tints light to `color` after travelling `distance`
+    // inside the dielectric
+    pub fn with_transmission_color(self, color: Color, distance: f64) -> Self {
+        assert!(
+            distance > 0.0,
+            "transmission color distance must be positive"
+        );
+        let sigma = |c: f64| -c.max(1e-6).ln() / distance;
+        self.with_absorption(Color::new(
+            sigma(color.x()),
+            sigma(color.y()),
+            sigma(color.z()),
+        ))
     }
 
     fn reflectance(cosine: f64, refractive_index: f64) -> f64 {
@@ -24,11 +54,12 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<(Color, Ray)> {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter> {
+        let refractive_index = self.ior.at(r_in.wavelengths().map(|l| l.hero()));
         let ri = if rec.front_face {
-            1.0 / self.refractive_index
+            1.0 / refractive_index
         } else {
-            self.refractive_index
+            refractive_index
         };
 
         let unit_diection = r_in.direction().unit_vector();
@@ -43,6 +74,83 @@ impl Material for Dielectric {
             Vec3::refract(&unit_diection, &rec.normal, ri)
         };
 
-        Some((Color::new(1.0, 1.0, 1.0), Ray::new(rec.p, direction)))
+        Some(Scatter::specular(
+            Color::new(1.0, 1.0, 1.0),
+            Ray::new(rec.p, direction, r_in.time()),
+        ))
+    }
+
+    // clear glass leaves the medium around it in place
+    fn absorption(&self) -> Option<Color> {
+        (!self.absorption.near_zero()).then_some(self.absorption)
+    }
+
+    fn dispersive(&self) -> bool {
+        self.ior.is_dispersive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        material::test_util::{incoming, record},
+        spectrum::Wavelengths,
+    };
+
+    #[test]
+    fn transmission_color_is_reached_at_distance() {
+        let color = Color::new(0.8, 0.5, 0.1);
+        let glass = Dielectric::new(1.5).with_transmission_color(color, 2.0);
+        let sigma = glass.absorption().unwrap();
+        for i in 0..3 {
+            assert!(((-sigma[i] * 2.0).exp() - color[i]).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn clear_glass_bounds_no_medium() {
+        assert_eq!(Dielectric::new(1.5).absorption(), None);
+        let absorption = Color::new(0.0, 0.1, 0.0);
+        assert_eq!(
+            Dielectric::new(1.5)
+                .with_absorption(absorption)
+                .absorption(),
+            Some(absorption)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn transmission_color_rejects_zero_distance() {
+        let _ = Dielectric::new(1.5).with_transmission_color(Color::new(0.5, 0.5, 0.5), 0.0);
+    }
+
+    #[test]
+    fn dispersion_bends_each_hero_wavelength_by_its_ior() {
+        let glass = Dielectric::from_ior(Ior::diamond());
+        assert!(glass.dispersive());
+        assert!(!Dielectric::new(1.5).dispersive());
+
+        let rec = record(Rc::new(Dielectric::from_ior(Ior::diamond())));
+        let sin_in = 0.6f64;
+        let mut rng = PCG32RNG::default();
+        let mut refracted = 0;
+        for _ in 0..1000 {
+            let lambda = Wavelengths::sample(&mut rng);
+            let r = incoming((1.0 - sin_in * sin_in).sqrt()).with_wavelengths(Some(lambda));
+            let scatter = glass.scatter(&r, &rec, &mut rng).unwrap();
+            let d = scatter.ray.direction().unit_vector();
+            if d.z() > 0.0 {
+                continue;
+            }
+            refracted += 1;
+            // snell's law at the hero wavelength
+            let n = Ior::diamond().at(Some(lambda.hero()));
+            assert!((d.x() - sin_in / n).abs() < 1e-9, "{} nm", lambda.hero());
+        }
+        assert!(refracted > 700);
     }
 }
@@ -0,0 +1,58 @@
+use crate::{
+    color::Color,
+    hittable::HitRecord,
+    material::{Material, Scatter},
+    ray::Ray,
+    util::rand::PCG32RNG,
+};
+
+// emits `emit` uniformly from the front side of the surface and absorbs all
+// incoming light. shapes with it are only sampled by next-event estimation
+// when they are also added to the lights, see `SphereLight`; others are
+// still found by scattered rays, at the cost of more noise
+pub struct DiffuseLight {
+    emit: Color,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _r_in: &Ray, _rec: &HitRecord, _rng: &mut PCG32RNG) -> Option<Scatter> {
+        None
+    }
+
+    fn emitted(&self, _r_in: &Ray, rec: &HitRecord) -> Color {
+        if rec.front_face {
+            self.emit
+        } else {
+            Color::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::material::test_util::{incoming, record};
+
+    #[test]
+    fn emits_from_the_front_side_only() {
+        let emit = Color::new(1.0, 2.0, 3.0);
+        let light = DiffuseLight::new(emit);
+        let mut rec = record(Rc::new(DiffuseLight::new(emit)));
+        let r = incoming(0.5);
+        let mut rng = PCG32RNG::default();
+
+        assert_eq!(light.emitted(&r, &rec), emit);
+        assert!(light.scatter(&r, &rec, &mut rng).is_none());
+
+        rec.front_face = false;
+        assert_eq!(light.emitted(&r, &rec), Color::default());
+    }
+}
@@ -0,0 +1,167 @@
+use std::f64::consts::PI;
+
+use crate::color::Color;
+
+// unpolarized fresnel reflectance of a dielectric interface. `eta` is the
+// relative index of refraction (transmitted over incident side); a negative
+// cos_theta_i means the light arrives from the transmitted side
+pub fn dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let (cos_theta_i, eta) = if cos_theta_i < 0.0 {
+        (-cos_theta_i, 1.0 / eta)
+    } else {
+        (cos_theta_i, eta)
+    };
+    let cos_theta_i = cos_theta_i.min(1.0);
+
+    let sin2_theta_i = 1.0 - cos_theta_i * cos_theta_i;
+    let sin2_theta_t = sin2_theta_i / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        // total internal reflection
+        return 1.0;
+    }
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+
+    let r_parl = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perp = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (r_parl * r_parl + r_perp * r_perp) / 2.0
+}
+
+// amplitude reflection coefficients (s, p) between media of indices n_i and
+// n_t, given the cosines of the incident and transmitted angles
+fn amplitudes(n_i: f64, n_t: f64, cos_i: f64, cos_t: f64) -> (f64, f64) {
+    let rs = (n_i * cos_i - n_t * cos_t) / (n_i * cos_i + n_t * cos_t);
+    let rp = (n_t * cos_i - n_i * cos_t) / (n_t * cos_i + n_i * cos_t);
+    (rs, rp)
+}
+
+// reflectance of a dielectric interface of relative index `eta` coated with a
+// film of index `film_eta` and `thickness` nanometers, at wavelength `lambda`
+// nanometers. sums the interfering reflections inside the film (airy)
+pub fn thin_film(cos_theta_i: f64, film_eta: f64, eta: f64, thickness: f64, lambda: f64) -> f64 {
+    let cos0 = cos_theta_i.abs().min(1.0);
+    let sin2_0 = 1.0 - cos0 * cos0;
+
+    // total internal reflection at either interface leaves nothing to the
+    // substrate. (tunnelling through a film thinner than a wavelength is
+    // ignored)
+    let sin2_1 = sin2_0 / (film_eta * film_eta);
+    let sin2_2 = sin2_0 / (eta * eta);
+    if sin2_1 >= 1.0 || sin2_2 >= 1.0 {
+        return 1.0;
+    }
+    let cos1 = (1.0 - sin2_1).sqrt();
+    let cos2 = (1.0 - sin2_2).sqrt();
+
+    // phase difference between successive reflections
+    let delta = 4.0 * PI * film_eta * thickness * cos1 / lambda;
+
+    let (rs01, rp01) = amplitudes(1.0, film_eta, cos0, cos1);
+    let (rs12, rp12) = amplitudes(film_eta, eta, cos1, cos2);
+    let airy = |r01: f64, r12: f64| {
+        let cross = 2.0 * r01 * r12 * delta.cos();
+        (r01 * r01 + r12 * r12 + cross) / (1.0 + r01 * r01 * r12 * r12 + cross)
+    };
+    (airy(rs01, rs12) + airy(rp01, rp12)) / 2.0
+}
+
+// fresnel reflectance of a conductor with complex index of refraction
+// eta + i k, for a single wavelength
+fn conductor_channel(cos_theta_i: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cos_theta_i * cos_theta_i;
+    let sin2 = 1.0 - cos2;
+    let eta2 = eta * eta;
+    let k2 = k * k;
+
+    let t0 = eta2 - k2 - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+
+    let t1 = a2_plus_b2 + cos2;
+    let t2 = 2.0 * cos_theta_i * a;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+
+    (rp + rs) / 2.0
+}
+
+pub fn conductor(cos_theta_i: f64, eta: &Color, k: &Color) -> Color {
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    Color::new(
+        conductor_channel(cos_theta_i, eta.x(), k.x()),
+        conductor_channel(cos_theta_i, eta.y(), k.y()),
+        conductor_channel(cos_theta_i, eta.z(), k.z()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dielectric_at_normal_incidence() {
+        // ((n - 1) / (n + 1))^2 from either side
+        assert!((dielectric(1.0, 1.5) - 0.04).abs() < 1e-12);
+        assert!((dielectric(-1.0, 1.5) - 0.04).abs() < 1e-12);
+        assert!(dielectric(0.3, 1.0) < 1e-12);
+        // everything is reflected at grazing angles
+        assert!((dielectric(0.0, 1.5) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn dielectric_total_internal_reflection() {
+        // the critical angle of glass to air is asin(1 / 1.5)
+        let cos_critical = (1.0f64 - 1.0 / (1.5 * 1.5)).sqrt();
+        assert_eq!(dielectric(-(cos_critical - 1e-6), 1.5), 1.0);
+        assert!(dielectric(-(cos_critical + 1e-3), 1.5) < 1.0);
+        // no critical angle entering the denser medium
+        assert!(dielectric(1e-3, 1.5) < 1.0);
+    }
+
+    #[test]
+    fn conductor_at_normal_incidence() {
+        let (eta, k) = (0.2, 3.9);
+        let expected = ((eta - 1.0) * (eta - 1.0) + k * k) / ((eta + 1.0) * (eta + 1.0) + k * k);
+        let r = conductor(1.0, &Color::new(eta, eta, eta), &Color::new(k, k, k));
+        for i in 0..3 {
+            assert!((r[i] - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn conductor_matches_dielectric_without_absorption() {
+        for cos_theta in [0.05, 0.3, 0.7, 1.0] {
+            let r = conductor(cos_theta, &Color::new(1.5, 1.5, 1.5), &Color::default());
+            assert!((r.x() - dielectric(cos_theta, 1.5)).abs() < 1e-9);
+        }
+        let grazing = conductor(0.0, &Color::new(0.2, 0.9, 1.1), &Color::new(3.9, 2.4, 2.1));
+        for i in 0..3 {
+            assert!((grazing[i] - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn thin_film_without_thickness_is_the_bare_interface() {
+        for cos_theta in [0.1, 0.5, 1.0] {
+            let film = thin_film(cos_theta, 1.33, 1.5, 0.0, 550.0);
+            assert!((film - dielectric(cos_theta, 1.5)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn quarter_wave_film_cancels_reflection() {
+        // an index of sqrt(eta) and a quarter wavelength of optical
+        // thickness is an ideal anti-reflection coating
+        let film_eta = 1.5f64.sqrt();
+        let thickness = 550.0 / (4.0 * film_eta);
+        assert!(thin_film(1.0, film_eta, 1.5, thickness, 550.0) < 1e-12);
+        // away from the design wavelength some light is reflected again
+        let off = thin_film(1.0, film_eta, 1.5, thickness, 400.0);
+        assert!(off > 1e-3 && off < dielectric(1.0, 1.5));
+        // and a half wave film reflects like the bare interface
+        let half = thin_film(1.0, film_eta, 1.5, 2.0 * thickness, 550.0);
+        assert!((half - dielectric(1.0, 1.5)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,83 @@
+// index of refraction as a function of wavelength
+#[derive(Debug, Clone, Copy)]
+pub enum Ior {
+    Constant(f64),
+    // n = a + b / lambda^2, lambda in micrometers
+    Cauchy { a: f64, b: f64 },
+    // n^2 = 1 + sum(b_i lambda^2 / (lambda^2 - c_i)), lambda in micrometers
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Ior {
+    // wavelength used when rendering in rgb (fraunhofer d line)
+    pub const REFERENCE_WAVELENGTH: f64 = 587.6;
+
+    pub fn bk7() -> Self {
+        Self::Sellmeier {
+            b: [1.03961212, 0.231792344, 1.01046945],
+            c: [0.00600069867, 0.0200179144, 103.560653],
+        }
+    }
+
+    pub fn fused_silica() -> Self {
+        Self::Sellmeier {
+            b: [0.6961663, 0.4079426, 0.8974794],
+            c: [0.00467914826, 0.0135120631, 97.9340025],
+        }
+    }
+
+    pub fn diamond() -> Self {
+        Self::Sellmeier {
+            b: [0.3306, 4.3356, 0.0],
+            c: [0.030625, 0.011236, 0.0],
+        }
+    }
+
+    // index of refraction at `lambda` nanometers, or at the reference
+    // wavelength outside of spectral rendering
+    pub fn at(&self, lambda: Option<f64>) -> f64 {
+        let um = lambda.unwrap_or(Self::REFERENCE_WAVELENGTH) / 1000.0;
+        let um2 = um * um;
+        match *self {
+            Self::Constant(n) => n,
+            Self::Cauchy { a, b } => a + b / um2,
+            Self::Sellmeier { b, c } => {
+                let n2 = 1.0 + (0..3).map(|i| b[i] * um2 / (um2 - c[i])).sum::<f64>();
+                n2.sqrt()
+            }
+        }
+    }
+
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, Self::Constant(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sellmeier_matches_catalog_indices() {
+        // refractive index at the fraunhofer d line, n_d
+        assert!((Ior::bk7().at(None) - 1.5168).abs() < 1e-4);
+        assert!((Ior::fused_silica().at(None) - 1.4585).abs() < 1e-4);
+        assert!((Ior::diamond().at(None) - 2.417).abs() < 2e-3);
+    }
+
+    #[test]
+    fn blue_light_is_refracted_more() {
+        for ior in [Ior::bk7(), Ior::fused_silica(), Ior::diamond()] {
+            assert!(ior.is_dispersive());
+            assert!(ior.at(Some(450.0)) > ior.at(Some(550.0)));
+            assert!(ior.at(Some(550.0)) > ior.at(Some(650.0)));
+        }
+
+        let cauchy = Ior::Cauchy { a: 1.5, b: 0.004 };
+        assert!((cauchy.at(Some(500.0)) - 1.516).abs() < 1e-12);
+
+        let constant = Ior::Constant(1.33);
+        assert!(!constant.is_dispersive());
+        assert_eq!(constant.at(Some(450.0)), constant.at(None));
+    }
+}
@@ -0,0 +1,64 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::Color,
+    hittable::HitRecord,
+    material::{Material, Scatter},
+    ray::Ray,
+    util::rand::PCG32RNG,
+    vec3::Vec3,
+};
+
+// phase function scattering uniformly in all directions, for participating media
+pub struct Isotropic {
+    albedo: Color,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter> {
+        let scattered = Ray::new(rec.p, Vec3::random_unit_vector(rng), r_in.time());
+        Some(Scatter::new(self.albedo, scattered))
+    }
+
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> Color {
+        self.albedo / (4.0 * PI)
+    }
+
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::material::test_util::{
+        assert_color_near, assert_pdf_matches_scatter, eval_albedo, incoming, record,
+        scatter_albedo,
+    };
+
+    #[test]
+    fn eval_matches_sampling() {
+        let albedo = Color::new(0.9, 0.5, 0.1);
+        let mat = Rc::new(Isotropic::new(albedo));
+        let rec = record(mat.clone());
+        let r = incoming(0.3);
+        assert_color_near(&scatter_albedo(mat.as_ref(), &r, &rec, 100), &albedo, 1e-12);
+        assert_color_near(&eval_albedo(mat.as_ref(), &r, &rec, 100), &albedo, 1e-12);
+    }
+
+    #[test]
+    fn pdf_matches_sampling() {
+        let mat = Rc::new(Isotropic::new(Color::new(0.5, 0.5, 0.5)));
+        let rec = record(mat.clone());
+        assert_pdf_matches_scatter(mat.as_ref(), &incoming(0.3), &rec, 200000);
+    }
+}
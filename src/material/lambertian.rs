@@ -1,5 +1,11 @@
+use std::f64::consts::PI;
+
 use crate::{
-    color::Color, hittable::HitRecord, material::Material, ray::Ray, util::rand::PCG32RNG,
+    color::Color,
+    hittable::HitRecord,
+    material::{Material, Scatter},
+    ray::Ray,
+    util::rand::PCG32RNG,
     vec3::Vec3,
 };
 
@@ -14,12 +20,25 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<(Color, Ray)> {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter> {
         let scatter_direction = {
             let sd = rec.normal + Vec3::random_unit_vector(rng);
             if sd.near_zero() { rec.normal } else { sd }
         };
 
-        Some((self.albedo, Ray::new(rec.p, scatter_direction)))
+        Some(Scatter::new(
+            self.albedo,
+            Ray::new(rec.p, scatter_direction, r_in.time()),
+        ))
+    }
+
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let cos_theta = Vec3::dot(&scattered.direction().unit_vector(), &rec.normal);
+        self.albedo / PI * cos_theta.max(0.0)
+    }
+
+    fn pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cos_theta = Vec3::dot(&scattered.direction().unit_vector(), &rec.normal);
+        cos_theta.max(0.0) / PI
     }
 }
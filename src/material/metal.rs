@@ -1,5 +1,9 @@
 use crate::{
-    color::Color, hittable::HitRecord, material::Material, ray::Ray, util::rand::PCG32RNG,
+    color::Color,
+    hittable::HitRecord,
+    material::{Material, Scatter},
+    ray::Ray,
+    util::rand::PCG32RNG,
     vec3::Vec3,
 };
 
@@ -15,13 +19,13 @@ impl Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<(Color, Ray)> {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter> {
         let mut reflected = Vec3::reflect(r_in.direction(), &rec.normal);
         reflected = reflected.unit_vector() + (self.fuzz * Vec3::random_unit_vector(rng));
 
-        let scattered = Ray::new(rec.p, reflected);
+        let scattered = Ray::new(rec.p, reflected, r_in.time());
         if Vec3::dot(scattered.direction(), &rec.normal) > 0.0 {
-            Some((self.albedo, scattered))
+            Some(Scatter::specular(self.albedo, scattered))
         } else {
             None
         }
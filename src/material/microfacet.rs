@@ -0,0 +1,217 @@
+use std::f64::consts::PI;
+
+use crate::{util::rand::PCG32RNG, vec3::Vec3};
+
+// all directions are in the local shading frame, with the normal along +z
+
+#[inline]
+fn cos2_theta(w: &Vec3) -> f64 {
+    w.z() * w.z()
+}
+
+#[inline]
+fn tan2_theta(w: &Vec3) -> f64 {
+    (1.0 - cos2_theta(w)).max(0.0) / cos2_theta(w)
+}
+
+// cos^2 and sin^2 of the azimuth of `w`
+#[inline]
+fn cos2_sin2_phi(w: &Vec3) -> (f64, f64) {
+    let sin2_theta = w.x() * w.x() + w.y() * w.y();
+    if sin2_theta == 0.0 {
+        (1.0, 0.0)
+    } else {
+        (w.x() * w.x() / sin2_theta, w.y() * w.y() / sin2_theta)
+    }
+}
+
+#[inline]
+pub fn reflect(wo: &Vec3, n: &Vec3) -> Vec3 {
+    -*wo + 2.0 * Vec3::dot(wo, n) * *n
+}
+
+// refraction of `wo` through a surface with normal `n` on the same side,
+// where eta is the transmitted over incident index of refraction.
+// None on total internal reflection
+#[inline]
+pub fn refract(wo: &Vec3, n: &Vec3, eta: f64) -> Option<Vec3> {
+    let cos_theta_i = Vec3::dot(n, wo);
+    let sin2_theta_i = (1.0 - cos_theta_i * cos_theta_i).max(0.0);
+    let sin2_theta_t = sin2_theta_i / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return None;
+    }
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+    Some(-*wo / eta + (cos_theta_i / eta - cos_theta_t) * *n)
+}
+
+// trowbridge-reitz (ggx) microfacet distribution with smith
+// masking-shadowing, after pbrt-v4
+#[derive(Debug, Clone, Copy)]
+pub struct TrowbridgeReitz {
+    alpha_x: f64,
+    alpha_y: f64,
+}
+
+impl TrowbridgeReitz {
+    pub fn new(alpha_x: f64, alpha_y: f64) -> Self {
+        Self { alpha_x, alpha_y }
+    }
+
+    // perceptually linear roughness in [0, 1] to distribution alpha
+    pub fn roughness_to_alpha(roughness: f64) -> f64 {
+        let r = roughness.clamp(0.0, 1.0);
+        r * r
+    }
+
+    pub fn from_roughness(roughness_u: f64, roughness_v: f64) -> Self {
+        Self::new(
+            Self::roughness_to_alpha(roughness_u),
+            Self::roughness_to_alpha(roughness_v),
+        )
+    }
+
+    // below this the surface is treated as a perfect specular interface
+    pub fn effectively_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < 1e-3
+    }
+
+    pub fn d(&self, wm: &Vec3) -> f64 {
+        let tan2 = tan2_theta(wm);
+        if tan2.is_infinite() {
+            return 0.0;
+        }
+        let cos4 = cos2_theta(wm) * cos2_theta(wm);
+        let (cos2_phi, sin2_phi) = cos2_sin2_phi(wm);
+        let e = tan2
+            * (cos2_phi / (self.alpha_x * self.alpha_x) + sin2_phi / (self.alpha_y * self.alpha_y));
+        1.0 / (PI * self.alpha_x * self.alpha_y * cos4 * (1.0 + e) * (1.0 + e))
+    }
+
+    fn lambda(&self, w: &Vec3) -> f64 {
+        let tan2 = tan2_theta(w);
+        if tan2.is_infinite() {
+            return 0.0;
+        }
+        let (cos2_phi, sin2_phi) = cos2_sin2_phi(w);
+        let alpha2 =
+            self.alpha_x * self.alpha_x * cos2_phi + self.alpha_y * self.alpha_y * sin2_phi;
+        ((1.0 + alpha2 * tan2).sqrt() - 1.0) / 2.0
+    }
+
+    // masking of microfacets seen from `w`
+    pub fn g1(&self, w: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    // height-correlated masking-shadowing
+    pub fn g(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // distribution of normals visible from `w`
+    pub fn d_visible(&self, w: &Vec3, wm: &Vec3) -> f64 {
+        self.g1(w) / w.z().abs() * self.d(wm) * Vec3::dot(w, wm).abs()
+    }
+
+    pub fn pdf(&self, w: &Vec3, wm: &Vec3) -> f64 {
+        self.d_visible(w, wm)
+    }
+
+    // samples a microfacet normal visible from `w` (heitz 2018)
+    pub fn sample_wm(&self, w: &Vec3, rng: &mut PCG32RNG) -> Vec3 {
+        // transform w to the hemispherical configuration
+        let mut wh = Vec3::new(self.alpha_x * w.x(), self.alpha_y * w.y(), w.z()).unit_vector();
+        if wh.z() < 0.0 {
+            wh = -wh;
+        }
+
+        let t1 = if wh.z() < 0.99999 {
+            Vec3::cross(&Vec3::new(0.0, 0.0, 1.0), &wh).unit_vector()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = Vec3::cross(&wh, &t1);
+
+        // uniform disk sample, warped to the projected visible hemisphere
+        let r = rng.random_f64().sqrt();
+        let phi = 2.0 * PI * rng.random_f64();
+        let px = r * phi.cos();
+        let mut py = r * phi.sin();
+        let h = (1.0 - px * px).sqrt();
+        let s = (1.0 + wh.z()) / 2.0;
+        py = (1.0 - s) * h + s * py;
+
+        let pz = (1.0 - px * px - py * py).max(0.0).sqrt();
+        let nh = px * t1 + py * t2 + pz * wh;
+
+        // back to the ellipsoid configuration
+        Vec3::new(
+            self.alpha_x * nh.x(),
+            self.alpha_y * nh.y(),
+            nh.z().max(1e-6),
+        )
+        .unit_vector()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // integral of D(wm) cos(theta_m) over the hemisphere, which is one
+    fn projected_area(distrib: &TrowbridgeReitz) -> f64 {
+        let (n_theta, n_phi) = (2000, 64);
+        let mut sum = 0.0;
+        for i in 0..n_theta {
+            let theta = (i as f64 + 0.5) / n_theta as f64 * PI / 2.0;
+            for j in 0..n_phi {
+                let phi = (j as f64 + 0.5) / n_phi as f64 * 2.0 * PI;
+                let wm = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                );
+                sum += distrib.d(&wm) * theta.cos() * theta.sin();
+            }
+        }
+        sum * (PI / 2.0 / n_theta as f64) * (2.0 * PI / n_phi as f64)
+    }
+
+    #[test]
+    fn distribution_is_normalized() {
+        for (ax, ay) in [(0.3, 0.3), (0.5, 0.1), (0.8, 0.8)] {
+            let area = projected_area(&TrowbridgeReitz::new(ax, ay));
+            assert!((area - 1.0).abs() < 0.01, "({ax}, {ay}): {area}");
+        }
+    }
+
+    #[test]
+    fn sampled_normals_are_visible() {
+        let distrib = TrowbridgeReitz::new(0.6, 0.2);
+        let w = Vec3::new(0.6, -0.3, 0.5).unit_vector();
+        let mut rng = PCG32RNG::default();
+        for _ in 0..1000 {
+            let wm = distrib.sample_wm(&w, &mut rng);
+            assert!((wm.len() - 1.0).abs() < 1e-9);
+            assert!(wm.z() > 0.0 && Vec3::dot(&w, &wm) >= -1e-9);
+        }
+        // masking only hides microfacets
+        assert!(distrib.g(&w, &w) <= distrib.g1(&w) && distrib.g1(&w) <= 1.0);
+    }
+
+    #[test]
+    fn refraction_follows_snell() {
+        let n = Vec3::new(0.0, 0.0, 1.0);
+        let wo = Vec3::new(0.6, 0.0, 0.8);
+        let wt = refract(&wo, &n, 1.5).unwrap();
+        assert!((wt.len() - 1.0).abs() < 1e-12);
+        assert!(wt.z() < 0.0);
+        // sin(theta_t) = sin(theta_i) / eta, on the other side of the normal
+        assert!((wt.x() + 0.6 / 1.5).abs() < 1e-12);
+
+        assert!(refract(&wo, &n, 1.0 / 1.5).is_some());
+        assert!(refract(&Vec3::new(0.8, 0.0, 0.6), &n, 1.0 / 1.5).is_none());
+        assert_eq!(reflect(&wo, &n), Vec3::new(-0.6, 0.0, 0.8));
+    }
+}
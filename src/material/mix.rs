@@ -0,0 +1,178 @@
+use std::rc::Rc;
+
+use crate::{
+    color::Color,
+    hittable::HitRecord,
+    material::{Material, Scatter},
+    phase::PhaseFunction,
+    ray::Ray,
+    texture::{SolidColor, Texture},
+    util::{self, rand::PCG32RNG},
+    vec3::Point3,
+};
+
+// blends two materials: each scatter picks `b` with probability `weight`
+// and `a` otherwise, so the result is their weighted average
+pub struct Mix {
+    a: Rc<dyn Material>,
+    b: Rc<dyn Material>,
+    weight: Rc<dyn Texture>,
+}
+
+impl Mix {
+    pub fn new(a: Rc<dyn Material>, b: Rc<dyn Material>, weight: f64) -> Self {
+        let w = weight.clamp(0.0, 1.0);
+        Self::textured(a, b, Rc::new(SolidColor::new(Color::new(w, w, w))))
+    }
+
+    // the weight is the average of the texture's channels
+    pub fn textured(a: Rc<dyn Material>, b: Rc<dyn Material>, weight: Rc<dyn Texture>) -> Self {
+        Self { a, b, weight }
+    }
+
+    fn weight(&self, u: f64, v: f64, p: &Point3) -> f64 {
+        let w = self.weight.value(u, v, p);
+        ((w.x() + w.y() + w.z()) / 3.0).clamp(0.0, 1.0)
+    }
+}
+
+impl Material for Mix {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter> {
+        if rng.random_f64() < self.weight(rec.u, rec.v, &rec.p) {
+            self.b.scatter(r_in, rec, rng)
+        } else {
+            self.a.scatter(r_in, rec, rng)
+        }
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        util::lerp(
+            self.a.eval(r_in, rec, scattered),
+            self.b.eval(r_in, rec, scattered),
+            self.weight(rec.u, rec.v, &rec.p),
+        )
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        util::lerp(
+            self.a.pdf(r_in, rec, scattered),
+            self.b.pdf(r_in, rec, scattered),
+            self.weight(rec.u, rec.v, &rec.p),
+        )
+    }
+
+    // the enclosed medium cannot be blended, so the interior of `a` is used
+    // when both materials bound one
+    fn absorption(&self) -> Option<Color> {
+        self.a.absorption().or_else(|| self.b.absorption())
+    }
+
+    fn scattering(&self) -> Option<(Color, Rc<dyn PhaseFunction>)> {
+        if self.a.absorption().is_some() {
+            self.a.scattering()
+        } else {
+            self.b.scattering()
+        }
+    }
+
+    fn dispersive(&self) -> bool {
+        self.a.dispersive() || self.b.dispersive()
+    }
+
+    fn alpha(&self, u: f64, v: f64, p: &Point3) -> f64 {
+        util::lerp(
+            self.a.alpha(u, v, p),
+            self.b.alpha(u, v, p),
+            self.weight(u, v, p),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        image::Image,
+        material::{
+            Conductor, Dielectric, Lambertian,
+            test_util::{
+                assert_color_near, assert_pdf_matches_scatter, eval_albedo, incoming, record,
+                scatter_albedo,
+            },
+        },
+        texture::ImageTexture,
+    };
+
+    #[test]
+    fn mix_keeps_the_enclosed_medium() {
+        let absorption = Color::new(0.1, 0.2, 0.3);
+        let glass: Rc<dyn Material> = Rc::new(Dielectric::new(1.5).with_absorption(absorption));
+        let paint: Rc<dyn Material> = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+
+        assert_eq!(
+            Mix::new(glass.clone(), paint.clone(), 0.5).absorption(),
+            Some(absorption)
+        );
+        assert_eq!(
+            Mix::new(paint.clone(), glass, 0.5).absorption(),
+            Some(absorption)
+        );
+        assert_eq!(Mix::new(paint.clone(), paint, 0.5).absorption(), None);
+    }
+
+    #[test]
+    fn pdf_blends_the_materials() {
+        let paint: Rc<dyn Material> = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let gold: Rc<dyn Material> = Rc::new(Conductor::gold(0.4));
+        // the glass is all delta lobes and adds nothing to the pdf
+        let glass: Rc<dyn Material> = Rc::new(Dielectric::new(1.5));
+        for mix in [
+            Mix::new(paint.clone(), gold, 0.3),
+            Mix::new(paint, glass, 0.6),
+        ] {
+            let mix = Rc::new(mix);
+            let rec = record(mix.clone());
+            assert_pdf_matches_scatter(mix.as_ref(), &incoming(0.6), &rec, 200000);
+        }
+    }
+
+    #[test]
+    fn textured_weight_blends_the_materials() {
+        let red: Rc<dyn Material> = Rc::new(Lambertian::new(Color::new(0.8, 0.0, 0.0)));
+        let blue: Rc<dyn Material> = Rc::new(Lambertian::new(Color::new(0.0, 0.0, 0.8)));
+        // left half weight 1/4 on average over the channels, right half 1
+        let weight = Image::new(
+            2,
+            1,
+            vec![Color::new(0.75, 0.0, 0.0), Color::new(1.0, 1.0, 1.0)],
+        );
+        let mix = Rc::new(Mix::textured(
+            red,
+            blue,
+            Rc::new(ImageTexture::linear(weight)),
+        ));
+
+        let r = incoming(0.5);
+        let mut rec = record(mix.clone());
+        rec.u = 0.25;
+        let expected = Color::new(0.6, 0.0, 0.2);
+        assert_color_near(
+            &scatter_albedo(mix.as_ref(), &r, &rec, 20000),
+            &expected,
+            0.01,
+        );
+        assert_color_near(
+            &eval_albedo(mix.as_ref(), &r, &rec, 200000),
+            &expected,
+            0.01,
+        );
+
+        rec.u = 0.75;
+        let expected = Color::new(0.0, 0.0, 0.8);
+        assert_color_near(
+            &scatter_albedo(mix.as_ref(), &r, &rec, 1000),
+            &expected,
+            1e-12,
+        );
+    }
+}
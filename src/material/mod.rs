@@ -1,12 +1,225 @@
+mod alpha_mask;
+mod coated;
+mod conductor;
 mod dielectric;
+mod diffuse_light;
+mod fresnel;
+mod ior;
+mod isotropic;
 mod lambertian;
 mod metal;
+mod microfacet;
+mod mix;
+mod normal_map;
+mod oren_nayar;
+mod principled;
+mod rough_dielectric;
+mod subsurface;
+mod thin_dielectric;
+mod volumetric;
 
-use crate::{color::Color, hittable::HitRecord, ray::Ray, util::rand::PCG32RNG};
+use std::rc::Rc;
 
-pub use self::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal};
+use crate::{
+    color::Color, hittable::HitRecord, phase::PhaseFunction, ray::Ray, util::rand::PCG32RNG,
+    vec3::Point3,
+};
+
+pub use self::{
+    alpha_mask::AlphaMask, coated::Coated, conductor::Conductor, dielectric::Dielectric,
+    diffuse_light::DiffuseLight, ior::Ior, isotropic::Isotropic, lambertian::Lambertian,
+    metal::Metal, mix::Mix, normal_map::NormalMap, oren_nayar::OrenNayar, principled::Principled,
+    rough_dielectric::RoughDielectric, subsurface::Subsurface, thin_dielectric::ThinDielectric,
+    volumetric::Volumetric,
+};
+
+// a direction sampled by `Material::scatter`
+pub struct Scatter {
+    pub attenuation: Color,
+    pub ray: Ray,
+    // sampled from a delta lobe (mirrors, smooth glass, fuzzy `Metal`) that
+    // `eval` leaves out, so next-event estimation does not cover it
+    pub specular: bool,
+}
+
+impl Scatter {
+    pub fn new(attenuation: Color, ray: Ray) -> Self {
+        Self {
+            attenuation,
+            ray,
+            specular: false,
+        }
+    }
+
+    pub fn specular(attenuation: Color, ray: Ray) -> Self {
+        Self {
+            attenuation,
+            ray,
+            specular: true,
+        }
+    }
+}
 
 pub trait Material {
-    // returns: attenuatoin, scattered ray
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<(Color, Ray)>;
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter>;
+
+    // bsdf times |cos theta| of `scattered`, for directions not chosen by
+    // `scatter` (e.g. towards a light). zero for purely specular materials
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> Color {
+        Color::default()
+    }
+
+    // solid angle density with which `scatter` picks `scattered` outside of
+    // its delta lobes, weighing next-event estimation against scattered rays
+    // that hit a light. zero leaves emitters among the lights to next-event
+    // estimation alone
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        0.0
+    }
+
+    // absorption coefficient of the medium enclosed by surfaces of this
+    // material. None if the surface does not bound a medium
+    fn absorption(&self) -> Option<Color> {
+        None
+    }
+
+    // scattering coefficient and phase function of the enclosed medium, for
+    // materials whose interior scatters light. requires `absorption`
+    fn scattering(&self) -> Option<(Color, Rc<dyn PhaseFunction>)> {
+        None
+    }
+
+    // whether the scattered direction depends on the ray's hero wavelength,
+    // in which case spectral rendering drops the other wavelengths
+    fn dispersive(&self) -> bool {
+        false
+    }
+
+    // radiance emitted from the surface towards -r_in
+    fn emitted(&self, _r_in: &Ray, _rec: &HitRecord) -> Color {
+        Color::default()
+    }
+
+    // opacity at a surface point, in [0, 1]. hits are kept with this
+    // probability, so fractional values give stochastic transparency
+    fn alpha(&self, _u: f64, _v: f64, _p: &Point3) -> f64 {
+        1.0
+    }
+}
+
+// shared fixtures for the material tests: a hit on the z = 0 plane at the
+// origin, facing +z, and albedo estimates from `scatter` and from `eval`
+#[cfg(test)]
+pub(crate) mod test_util {
+    use std::{f64::consts::PI, rc::Rc};
+
+    use super::*;
+    use crate::vec3::Vec3;
+
+    pub fn record(mat: Rc<dyn Material>) -> HitRecord {
+        HitRecord {
+            t: 1.0,
+            p: Point3::default(),
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            geometric_normal: Vec3::new(0.0, 0.0, 1.0),
+            u: 0.5,
+            v: 0.5,
+            dpdu: Vec3::new(1.0, 0.0, 0.0),
+            dpdv: Vec3::new(0.0, 1.0, 0.0),
+            front_face: true,
+            mat,
+        }
+    }
+
+    // ray hitting the origin from above at `cos_theta` to the normal
+    pub fn incoming(cos_theta: f64) -> Ray {
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let from = Point3::new(-sin_theta, 0.0, cos_theta);
+        Ray::new(from, -from, 0.0)
+    }
+
+    // mean scatter weight, with absorbed samples counting as zero
+    pub fn scatter_albedo(mat: &dyn Material, r_in: &Ray, rec: &HitRecord, n: usize) -> Color {
+        let mut rng = PCG32RNG::default();
+        let mut sum = Color::default();
+        for _ in 0..n {
+            if let Some(scatter) = mat.scatter(r_in, rec, &mut rng) {
+                assert!((0..3).all(|i| scatter.attenuation[i].is_finite()));
+                sum += scatter.attenuation;
+            }
+        }
+        sum / n as f64
+    }
+
+    // integral of `eval` over the sphere of directions, which matches
+    // `scatter_albedo` for materials without delta lobes
+    pub fn eval_albedo(mat: &dyn Material, r_in: &Ray, rec: &HitRecord, n: usize) -> Color {
+        let mut rng = PCG32RNG::new(7, 11);
+        let mut sum = Color::default();
+        for _ in 0..n {
+            let dir = Vec3::random_unit_vector(&mut rng);
+            sum += mat.eval(r_in, rec, &Ray::new(rec.p, dir, r_in.time()));
+        }
+        sum * (4.0 * PI / n as f64)
+    }
+
+    // checks `pdf` against a histogram of the directions `scatter` picks
+    // outside of its delta lobes, on bins of equal solid angle
+    pub fn assert_pdf_matches_scatter(mat: &dyn Material, r_in: &Ray, rec: &HitRecord, n: usize) {
+        // bins in cos theta and phi, and midpoints per bin and axis
+        const NZ: usize = 10;
+        const NPHI: usize = 20;
+        const STEPS: usize = 8;
+        let bin = |d: &Vec3| {
+            let d = d.unit_vector();
+            let iz = (((d.z() + 1.0) / 2.0 * NZ as f64) as usize).min(NZ - 1);
+            let phi = d.y().atan2(d.x()) + PI;
+            let iphi = ((phi / (2.0 * PI) * NPHI as f64) as usize).min(NPHI - 1);
+            iz * NPHI + iphi
+        };
+
+        let mut rng = PCG32RNG::default();
+        let mut frequency = vec![0.0; NZ * NPHI];
+        for _ in 0..n {
+            if let Some(scatter) = mat.scatter(r_in, rec, &mut rng)
+                && !scatter.specular
+            {
+                frequency[bin(scatter.ray.direction())] += 1.0 / n as f64;
+            }
+        }
+
+        // midpoint rule over each bin, against five standard deviations of
+        // the bin's frequency
+        let step_area = 4.0 * PI / (NZ * NPHI * STEPS * STEPS) as f64;
+        let (mut total_frequency, mut total_expected) = (0.0, 0.0);
+        for (i, frequency) in frequency.iter().enumerate() {
+            let (iz, iphi) = (i / NPHI, i % NPHI);
+            let mut expected = 0.0;
+            for (k, l) in (0..STEPS).flat_map(|k| (0..STEPS).map(move |l| (k, l))) {
+                let z = -1.0 + 2.0 * (iz as f64 + (k as f64 + 0.5) / STEPS as f64) / NZ as f64;
+                let phi =
+                    2.0 * PI * (iphi as f64 + (l as f64 + 0.5) / STEPS as f64) / NPHI as f64 - PI;
+                let r = (1.0 - z * z).sqrt();
+                let dir = Vec3::new(r * phi.cos(), r * phi.sin(), z);
+                expected += mat.pdf(r_in, rec, &Ray::new(rec.p, dir, r_in.time())) * step_area;
+            }
+            let sigma = (expected.max(1e-6) / n as f64).sqrt();
+            assert!(
+                (frequency - expected).abs() < 5.0 * sigma + 0.03 * expected,
+                "bin {i}: {frequency} != {expected}"
+            );
+            total_frequency += frequency;
+            total_expected += expected;
+        }
+        assert!(
+            (total_frequency - total_expected).abs() < 0.01,
+            "{total_frequency} != {total_expected}"
+        );
+    }
+
+    pub fn assert_color_near(a: &Color, b: &Color, tolerance: f64) {
+        for i in 0..3 {
+            assert!((a[i] - b[i]).abs() < tolerance, "{a:?} != {b:?}");
+        }
+    }
 }
@@ -0,0 +1,245 @@
+use std::rc::Rc;
+
+use crate::{
+    color::Color,
+    hittable::HitRecord,
+    material::{Material, Scatter},
+    onb::Onb,
+    phase::PhaseFunction,
+    ray::Ray,
+    texture::Texture,
+    util::rand::PCG32RNG,
+    vec3::{Point3, Vec3},
+};
+
+// uv offset used for the finite differences of bump maps
+const BUMP_DELTA: f64 = 0.0005;
+
+enum Perturbation {
+    // tangent space normals encoded as rgb = (n + 1) / 2, with x along dpdu
+    Normals(Rc<dyn Texture>),
+    // grayscale height field, displacing the surface by scale * height
+    Heights(Rc<dyn Texture>, f64),
+}
+
+// perturbs the shading normal of another material with a normal map or a
+// bump map. geometry is unchanged; the geometric normal still decides which
+// side of the surface a direction is on
+pub struct NormalMap {
+    material: Rc<dyn Material>,
+    perturbation: Perturbation,
+}
+
+impl NormalMap {
+    // `normals` should be a linear texture, see `ImageTexture::linear`
+    pub fn new(material: Rc<dyn Material>, normals: Rc<dyn Texture>) -> Self {
+        Self {
+            material,
+            perturbation: Perturbation::Normals(normals),
+        }
+    }
+
+    // the height is the average of the texture's channels
+    pub fn bump(material: Rc<dyn Material>, heights: Rc<dyn Texture>, scale: f64) -> Self {
+        Self {
+            material,
+            perturbation: Perturbation::Heights(heights, scale),
+        }
+    }
+
+    // perturbed outward normal, or None where the surface has no
+    // parameterization to build a tangent frame from
+    fn shading_normal(&self, rec: &HitRecord) -> Option<Vec3> {
+        let n = if rec.front_face {
+            rec.normal
+        } else {
+            -rec.normal
+        };
+        if Vec3::cross(&rec.dpdu, &rec.dpdv).near_zero() {
+            return None;
+        }
+
+        let ns = match &self.perturbation {
+            Perturbation::Normals(normals) => {
+                let c = normals.value(rec.u, rec.v, &rec.p);
+                let local = 2.0 * c - Color::new(1.0, 1.0, 1.0);
+
+                // gram-schmidt tangent frame, with the bitangent following
+                // dpdv so mirrored uvs keep their handedness
+                let t = rec.dpdu - Vec3::dot(&n, &rec.dpdu) * n;
+                let t = if t.near_zero() {
+                    *Onb::new(&n).u()
+                } else {
+                    t.unit_vector()
+                };
+                let b = Vec3::cross(&n, &t);
+                let b = if Vec3::dot(&b, &rec.dpdv) < 0.0 {
+                    -b
+                } else {
+                    b
+                };
+                local.x() * t + local.y() * b + local.z() * n
+            }
+            Perturbation::Heights(heights, scale) => {
+                let height = |u: f64, v: f64, offset: Vec3| {
+                    let h = heights.value(u, v, &(rec.p + offset));
+                    scale * (h.x() + h.y() + h.z()) / 3.0
+                };
+                let h = height(rec.u, rec.v, Vec3::default());
+                let h_u = height(rec.u + BUMP_DELTA, rec.v, BUMP_DELTA * rec.dpdu);
+                let h_v = height(rec.u, rec.v + BUMP_DELTA, BUMP_DELTA * rec.dpdv);
+
+                // derivatives of the displaced surface p + h * n, ignoring
+                // the change of n itself
+                let dpdu = rec.dpdu + (h_u - h) / BUMP_DELTA * n;
+                let dpdv = rec.dpdv + (h_v - h) / BUMP_DELTA * n;
+                let ns = Vec3::cross(&dpdu, &dpdv);
+                if Vec3::dot(&ns, &n) < 0.0 { -ns } else { ns }
+            }
+        };
+
+        (!ns.near_zero()).then(|| ns.unit_vector())
+    }
+
+    // hit record with the perturbed shading normal. falls back to the
+    // original normal when the viewer would be behind the perturbed one
+    fn perturb(&self, r_in: &Ray, rec: &HitRecord) -> HitRecord {
+        let mut shading = rec.clone();
+        if let Some(ns) = self.shading_normal(rec) {
+            shading.set_shading_normal(&ns);
+            if Vec3::dot(r_in.direction(), &shading.normal) >= 0.0 {
+                shading.normal = rec.normal;
+            }
+        }
+        shading
+    }
+
+    // directions on different sides of the shading and geometric surfaces
+    // would leak light through the surface, so they are discarded
+    fn consistent(rec: &HitRecord, direction: &Vec3) -> bool {
+        Vec3::dot(direction, &rec.normal) * Vec3::dot(direction, &rec.geometric_normal) > 0.0
+    }
+}
+
+impl Material for NormalMap {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter> {
+        let shading = self.perturb(r_in, rec);
+        self.material
+            .scatter(r_in, &shading, rng)
+            .filter(|scatter| Self::consistent(&shading, scatter.ray.direction()))
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let shading = self.perturb(r_in, rec);
+        if !Self::consistent(&shading, scattered.direction()) {
+            return Color::default();
+        }
+        self.material.eval(r_in, &shading, scattered)
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let shading = self.perturb(r_in, rec);
+        if !Self::consistent(&shading, scattered.direction()) {
+            return 0.0;
+        }
+        self.material.pdf(r_in, &shading, scattered)
+    }
+
+    fn absorption(&self) -> Option<Color> {
+        self.material.absorption()
+    }
+
+    fn scattering(&self) -> Option<(Color, Rc<dyn PhaseFunction>)> {
+        self.material.scattering()
+    }
+
+    fn dispersive(&self) -> bool {
+        self.material.dispersive()
+    }
+
+    fn alpha(&self, u: f64, v: f64, p: &Point3) -> f64 {
+        self.material.alpha(u, v, p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        material::{
+            Lambertian,
+            test_util::{assert_pdf_matches_scatter, incoming, record},
+        },
+        texture::SolidColor,
+    };
+
+    // height rising along u
+    struct Ramp;
+
+    impl Texture for Ramp {
+        fn value(&self, u: f64, _v: f64, _p: &Point3) -> Color {
+            Color::new(u, u, u)
+        }
+    }
+
+    fn base() -> Rc<dyn Material> {
+        Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+    }
+
+    fn shading_normal(map: NormalMap) -> Vec3 {
+        let rec = record(base());
+        map.shading_normal(&rec).unwrap()
+    }
+
+    #[test]
+    fn flat_normal_map_keeps_the_normal() {
+        let flat = Rc::new(SolidColor::new(Color::new(0.5, 0.5, 1.0)));
+        let ns = shading_normal(NormalMap::new(base(), flat));
+        assert!((ns - Vec3::new(0.0, 0.0, 1.0)).len() < 1e-12);
+    }
+
+    #[test]
+    fn normal_map_tilts_towards_dpdu() {
+        // 30 degrees towards the tangent
+        let (sin, cos) = (0.5, 3.0f64.sqrt() / 2.0);
+        let tilted = Rc::new(SolidColor::new(Color::new(
+            (sin + 1.0) / 2.0,
+            0.5,
+            (cos + 1.0) / 2.0,
+        )));
+        let ns = shading_normal(NormalMap::new(base(), tilted));
+        assert!((ns - Vec3::new(sin, 0.0, cos)).len() < 1e-12);
+    }
+
+    #[test]
+    fn bump_map_tilts_against_the_slope() {
+        // a slope of 0.5 along dpdu tilts the normal back by atan(0.5)
+        let ns = shading_normal(NormalMap::bump(base(), Rc::new(Ramp), 0.5));
+        assert!((ns - Vec3::new(-0.5, 0.0, 1.0).unit_vector()).len() < 1e-9);
+    }
+
+    #[test]
+    fn directions_below_the_geometry_are_dropped() {
+        let tilted = Rc::new(SolidColor::new(Color::new(0.95, 0.5, 0.72)));
+        let map = NormalMap::new(base(), tilted);
+        let rec = record(base());
+        let r = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let mut rng = PCG32RNG::default();
+        for _ in 0..1000 {
+            if let Some(scatter) = map.scatter(&r, &rec, &mut rng) {
+                assert!(scatter.ray.direction().z() > 0.0);
+            }
+        }
+        let below = Ray::new(rec.p, Vec3::new(1.0, 0.0, -0.1), 0.0);
+        assert_eq!(map.eval(&r, &rec, &below), Color::default());
+    }
+
+    #[test]
+    fn pdf_matches_sampling() {
+        // tilted far enough for some directions to be dropped
+        let tilted = Rc::new(SolidColor::new(Color::new(0.95, 0.5, 0.72)));
+        let map = Rc::new(NormalMap::new(base(), tilted));
+        let rec = record(map.clone());
+        assert_pdf_matches_scatter(map.as_ref(), &incoming(0.7), &rec, 200000);
+    }
+}
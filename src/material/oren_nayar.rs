@@ -0,0 +1,152 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::Color,
+    hittable::HitRecord,
+    material::{Material, Scatter},
+    onb::Onb,
+    ray::Ray,
+    util::{self, rand::PCG32RNG},
+    vec3::Vec3,
+};
+
+// rough diffuse reflection from v-shaped lambertian microfacets
+// (oren and nayar 1994, qualitative model). sigma is the standard deviation
+// of the facet slope angle in degrees; zero reduces to `Lambertian`
+pub struct OrenNayar {
+    albedo: Color,
+    a: f64,
+    b: f64,
+}
+
+impl OrenNayar {
+    pub fn new(albedo: Color, sigma: f64) -> Self {
+        let sigma = util::deg_to_rad(sigma);
+        let sigma2 = sigma * sigma;
+        Self {
+            albedo,
+            a: 1.0 - sigma2 / (2.0 * (sigma2 + 0.33)),
+            b: 0.45 * sigma2 / (sigma2 + 0.09),
+        }
+    }
+
+    // brdf divided by albedo / pi, for local directions above the surface
+    fn factor(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        let sin_theta_i = (1.0 - wi.z() * wi.z()).max(0.0).sqrt();
+        let sin_theta_o = (1.0 - wo.z() * wo.z()).max(0.0).sqrt();
+
+        // cosine of the azimuth difference
+        let max_cos = if sin_theta_i > 1e-4 && sin_theta_o > 1e-4 {
+            let cos_dphi = (wi.x() * wo.x() + wi.y() * wo.y()) / (sin_theta_i * sin_theta_o);
+            cos_dphi.max(0.0)
+        } else {
+            0.0
+        };
+
+        let (sin_alpha, tan_beta) = if wi.z() > wo.z() {
+            (sin_theta_o, sin_theta_i / wi.z())
+        } else {
+            (sin_theta_i, sin_theta_o / wo.z())
+        };
+
+        self.a + self.b * max_cos * sin_alpha * tan_beta
+    }
+
+    fn local(r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> (Vec3, Vec3) {
+        let frame = Onb::new(&rec.normal);
+        (
+            frame.to_local(&-r_in.direction().unit_vector()),
+            frame.to_local(&scattered.direction().unit_vector()),
+        )
+    }
+}
+
+impl Material for OrenNayar {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter> {
+        let frame = Onb::new(&rec.normal);
+        let wo = frame.to_local(&-r_in.direction().unit_vector());
+        let wi = Vec3::random_cosine_direction(rng);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return None;
+        }
+
+        // with cosine sampling f * cos / pdf = albedo * factor
+        Some(Scatter::new(
+            self.albedo * self.factor(&wo, &wi),
+            Ray::new(rec.p, frame.transform(&wi), r_in.time()),
+        ))
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let (wo, wi) = Self::local(r_in, rec, scattered);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::default();
+        }
+        self.albedo / PI * self.factor(&wo, &wi) * wi.z()
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let (wo, wi) = Self::local(r_in, rec, scattered);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        wi.z() / PI
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::material::test_util::{
+        assert_color_near, assert_pdf_matches_scatter, eval_albedo, incoming, record,
+        scatter_albedo,
+    };
+
+    #[test]
+    fn smooth_facets_are_lambertian() {
+        let mat = OrenNayar::new(Color::new(0.5, 0.5, 0.5), 0.0);
+        let wo = Vec3::new(0.6, 0.0, 0.8);
+        let wi = Vec3::new(-0.3, 0.4, (1.0f64 - 0.25).sqrt());
+        assert_eq!(mat.factor(&wo, &wi), 1.0);
+    }
+
+    #[test]
+    fn factor_is_reciprocal() {
+        let mat = OrenNayar::new(Color::new(0.5, 0.5, 0.5), 30.0);
+        let wo = Vec3::new(0.6, 0.0, 0.8);
+        for wi in [
+            Vec3::new(0.8, 0.0, 0.6),
+            Vec3::new(0.3, 0.3, (1.0f64 - 0.18).sqrt()),
+            Vec3::new(-0.9, 0.1, (1.0f64 - 0.82).sqrt()),
+        ] {
+            assert!((mat.factor(&wo, &wi) - mat.factor(&wi, &wo)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn eval_matches_sampling() {
+        let mat = Rc::new(OrenNayar::new(Color::new(0.9, 0.6, 0.3), 25.0));
+        let rec = record(mat.clone());
+        for cos_theta in [1.0, 0.5, 0.1] {
+            let r = incoming(cos_theta);
+            let sampled = scatter_albedo(mat.as_ref(), &r, &rec, 100000);
+            let integrated = eval_albedo(mat.as_ref(), &r, &rec, 400000);
+            assert_color_near(&sampled, &integrated, 0.01);
+        }
+
+        // rough surfaces look darker head on and brighter towards grazing
+        // light than a lambertian one
+        let head_on = scatter_albedo(mat.as_ref(), &incoming(1.0), &rec, 1000);
+        let grazing = scatter_albedo(mat.as_ref(), &incoming(0.1), &rec, 100000);
+        assert!(head_on.x() < 0.9 && grazing.x() > head_on.x());
+    }
+
+    #[test]
+    fn pdf_matches_sampling() {
+        let mat = Rc::new(OrenNayar::new(Color::new(0.9, 0.6, 0.3), 25.0));
+        let rec = record(mat.clone());
+        assert_pdf_matches_scatter(mat.as_ref(), &incoming(0.5), &rec, 200000);
+    }
+}
@@ -0,0 +1,381 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::{Color, luminance},
+    hittable::HitRecord,
+    material::{
+        Material, Scatter, fresnel,
+        microfacet::{TrowbridgeReitz, reflect, refract},
+    },
+    onb::Onb,
+    ray::Ray,
+    util::{self, rand::PCG32RNG},
+    vec3::Vec3,
+};
+
+// keeps the microfacet lobes away from delta distributions, so every lobe
+// can be evaluated and mixed
+const MIN_ROUGHNESS: f64 = 0.04;
+
+#[inline]
+fn schlick_weight(cos_theta: f64) -> f64 {
+    (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5)
+}
+
+// generalized trowbridge-reitz with gamma = 1, used by the clearcoat
+fn gtr1(cos_theta_m: f64, alpha: f64) -> f64 {
+    let a2 = alpha * alpha;
+    (a2 - 1.0) / (PI * a2.ln() * (1.0 + (a2 - 1.0) * cos_theta_m * cos_theta_m))
+}
+
+// disney "principled" bsdf (burley 2012, 2015): a single artist facing
+// material with diffuse, sheen, specular, clearcoat and specular
+// transmission lobes. all parameters except `ior` are in [0, 1]
+pub struct Principled {
+    base_color: Color,
+    metallic: f64,
+    roughness: f64,
+    // normal incidence reflectance of dielectrics, 0.5 is 4%
+    specular: f64,
+    sheen: f64,
+    sheen_tint: f64,
+    clearcoat: f64,
+    clearcoat_gloss: f64,
+    transmission: f64,
+    ior: f64,
+}
+
+// lobe weights in the bsdf
+struct Weights {
+    diffuse: f64,
+    specular: f64,
+    transmission: f64,
+    clearcoat: f64,
+}
+
+impl Principled {
+    pub fn new(base_color: Color) -> Self {
+        Self {
+            base_color,
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+            clearcoat: 0.0,
+            clearcoat_gloss: 1.0,
+            transmission: 0.0,
+            ior: 1.5,
+        }
+    }
+
+    pub fn with_metallic(mut self, metallic: f64) -> Self {
+        self.metallic = metallic.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: f64) -> Self {
+        self.roughness = roughness.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_specular(mut self, specular: f64) -> Self {
+        self.specular = specular.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_sheen(mut self, sheen: f64, sheen_tint: f64) -> Self {
+        self.sheen = sheen.clamp(0.0, 1.0);
+        self.sheen_tint = sheen_tint.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_clearcoat(mut self, clearcoat: f64, clearcoat_gloss: f64) -> Self {
+        self.clearcoat = clearcoat.clamp(0.0, 1.0);
+        self.clearcoat_gloss = clearcoat_gloss.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_transmission(mut self, transmission: f64, ior: f64) -> Self {
+        self.transmission = transmission.clamp(0.0, 1.0);
+        self.ior = ior;
+        self
+    }
+
+    fn weights(&self) -> Weights {
+        let dielectric = 1.0 - self.metallic;
+        Weights {
+            diffuse: dielectric * (1.0 - self.transmission),
+            specular: 1.0,
+            transmission: dielectric * self.transmission,
+            clearcoat: 0.25 * self.clearcoat,
+        }
+    }
+
+    fn distrib(&self) -> TrowbridgeReitz {
+        let r = self.roughness.max(MIN_ROUGHNESS);
+        TrowbridgeReitz::from_roughness(r, r)
+    }
+
+    fn clearcoat_alpha(&self) -> f64 {
+        util::lerp(0.1, 0.001, self.clearcoat_gloss)
+    }
+
+    // hue and saturation of the base color, at unit luminance
+    fn tint(&self) -> Color {
+        let lum = luminance(&self.base_color);
+        if lum > 0.0 {
+            self.base_color / lum
+        } else {
+            Color::new(1.0, 1.0, 1.0)
+        }
+    }
+
+    fn local(&self, r_in: &Ray, rec: &HitRecord) -> (Onb, Vec3, f64) {
+        let frame = Onb::new(&rec.normal);
+        let wo = frame.to_local(&-r_in.direction().unit_vector());
+        let eta = if rec.front_face {
+            self.ior
+        } else {
+            1.0 / self.ior
+        };
+        (frame, wo, eta)
+    }
+
+    // bsdf times |cos(theta_i)| and the matching mixture pdf, for local
+    // directions with wo in the upper hemisphere
+    fn eval_pdf(&self, wo: &Vec3, wi: &Vec3, eta: f64) -> (Color, f64) {
+        let w = self.weights();
+        let total = w.diffuse + w.specular + w.transmission + w.clearcoat;
+        let cos_theta_o = wo.z();
+        let cos_theta_i = wi.z();
+        if cos_theta_o <= 0.0 || cos_theta_i == 0.0 {
+            return (Color::default(), 0.0);
+        }
+
+        let mut f = Color::default();
+        let mut pdf = 0.0;
+
+        if cos_theta_i < 0.0 {
+            // specular transmission through a rough dielectric interface
+            if w.transmission <= 0.0 {
+                return (f, pdf);
+            }
+            let wm = *wo + *wi * eta;
+            if wm.near_zero() {
+                return (f, pdf);
+            }
+            let wm = wm.unit_vector();
+            let wm = if wm.z() < 0.0 { -wm } else { wm };
+            let wi_m = Vec3::dot(wi, &wm);
+            let wo_m = Vec3::dot(wo, &wm);
+            if wi_m >= 0.0 || wo_m <= 0.0 {
+                return (f, pdf);
+            }
+
+            let distrib = self.distrib();
+            let denom = (wi_m + wo_m / eta).powi(2);
+            let value = (1.0 - fresnel::dielectric(wo_m, eta))
+                * distrib.d(&wm)
+                * distrib.g(wo, wi)
+                * (wi_m * wo_m / (denom * cos_theta_o)).abs();
+
+            // transmitted light is tinted by the base color
+            f = w.transmission * value * self.base_color;
+            pdf = w.transmission / total * distrib.pdf(wo, &wm) * wi_m.abs() / denom;
+            return (f, pdf);
+        }
+
+        let wh = (*wo + *wi).unit_vector();
+        let cos_theta_d = Vec3::dot(wi, &wh);
+
+        if w.diffuse > 0.0 {
+            // burley diffuse with roughness dependent retro-reflection
+            let fd90 = 0.5 + 2.0 * self.roughness * cos_theta_d * cos_theta_d;
+            let fl = schlick_weight(cos_theta_i);
+            let fv = schlick_weight(cos_theta_o);
+            let diffuse =
+                self.base_color / PI * ((1.0 + (fd90 - 1.0) * fl) * (1.0 + (fd90 - 1.0) * fv));
+
+            let sheen_color = util::lerp(Color::new(1.0, 1.0, 1.0), self.tint(), self.sheen_tint);
+            let sheen = self.sheen * schlick_weight(cos_theta_d) * sheen_color;
+
+            f += w.diffuse * (diffuse + sheen) * cos_theta_i;
+            pdf += w.diffuse / total * cos_theta_i / PI;
+        }
+
+        let cos_theta_oh = Vec3::dot(wo, &wh);
+        if cos_theta_oh > 0.0 {
+            let distrib = self.distrib();
+            let spec0 = util::lerp(
+                0.08 * self.specular * Color::new(1.0, 1.0, 1.0),
+                self.base_color,
+                self.metallic,
+            );
+            let fresnel = util::lerp(
+                spec0,
+                Color::new(1.0, 1.0, 1.0),
+                schlick_weight(cos_theta_d),
+            );
+
+            f += w.specular * fresnel * (distrib.d(&wh) * distrib.g(wo, wi) / (4.0 * cos_theta_o));
+            pdf += w.specular / total * distrib.pdf(wo, &wh) / (4.0 * cos_theta_oh);
+
+            if w.clearcoat > 0.0 {
+                let alpha = self.clearcoat_alpha();
+                let d = gtr1(wh.z(), alpha);
+                let g = TrowbridgeReitz::new(0.25, 0.25).g(wo, wi);
+                let fresnel = util::lerp(0.04, 1.0, schlick_weight(cos_theta_d));
+                let value = d * g * fresnel / (4.0 * cos_theta_o);
+
+                f += w.clearcoat * Color::new(value, value, value);
+                pdf += w.clearcoat / total * d * wh.z() / (4.0 * cos_theta_oh);
+            }
+        }
+
+        (f, pdf)
+    }
+
+    fn sample_wi(&self, wo: &Vec3, eta: f64, rng: &mut PCG32RNG) -> Option<Vec3> {
+        let w = self.weights();
+        let total = w.diffuse + w.specular + w.transmission + w.clearcoat;
+        let mut u = rng.random_f64() * total;
+
+        if u < w.diffuse {
+            return Some(Vec3::random_cosine_direction(rng));
+        }
+        u -= w.diffuse;
+
+        // reflections must stay above the surface and refractions below it,
+        // as eval_pdf only accounts for each lobe on its own side
+        if u < w.specular {
+            let wm = self.distrib().sample_wm(wo, rng);
+            return Some(reflect(wo, &wm)).filter(|wi| wi.z() > 0.0);
+        }
+        u -= w.specular;
+
+        if u < w.transmission {
+            let wm = self.distrib().sample_wm(wo, rng);
+            return refract(wo, &wm, eta).filter(|wi| wi.z() < 0.0);
+        }
+
+        // clearcoat: sample the gtr1 distribution directly
+        let a2 = self.clearcoat_alpha().powi(2);
+        let cos_theta = ((1.0 - a2.powf(1.0 - rng.random_f64())) / (1.0 - a2))
+            .max(0.0)
+            .sqrt();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * rng.random_f64();
+        let wm = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+        Some(reflect(wo, &wm)).filter(|wi| wi.z() > 0.0)
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter> {
+        let (frame, wo, eta) = self.local(r_in, rec);
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        // one lobe is sampled, but the weight uses the whole mixture so the
+        // result matches eval / pdf
+        let wi = self.sample_wi(&wo, eta, rng)?;
+        let (f, pdf) = self.eval_pdf(&wo, &wi, eta);
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        Some(Scatter::new(
+            f / pdf,
+            Ray::new(rec.p, frame.transform(&wi), r_in.time()),
+        ))
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let (frame, wo, eta) = self.local(r_in, rec);
+        let wi = frame.to_local(&scattered.direction().unit_vector());
+        self.eval_pdf(&wo, &wi, eta).0
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let (frame, wo, eta) = self.local(r_in, rec);
+        let wi = frame.to_local(&scattered.direction().unit_vector());
+        self.eval_pdf(&wo, &wi, eta).1
+    }
+
+    fn absorption(&self) -> Option<Color> {
+        // transmissive principled surfaces bound a clear medium
+        (self.transmission > 0.0).then(Color::default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::material::test_util::{
+        assert_color_near, assert_pdf_matches_scatter, eval_albedo, incoming, record,
+        scatter_albedo,
+    };
+
+    fn materials() -> Vec<Principled> {
+        let base = Color::new(0.8, 0.4, 0.2);
+        vec![
+            Principled::new(base),
+            Principled::new(base).with_metallic(1.0).with_roughness(0.6),
+            Principled::new(base)
+                .with_sheen(1.0, 0.5)
+                .with_clearcoat(1.0, 0.0),
+            Principled::new(base)
+                .with_roughness(0.7)
+                .with_transmission(0.8, 1.5),
+        ]
+    }
+
+    #[test]
+    fn eval_matches_sampling() {
+        for mat in materials() {
+            let mat = Rc::new(mat);
+            let rec = record(mat.clone());
+            let r = incoming(0.7);
+            let sampled = scatter_albedo(mat.as_ref(), &r, &rec, 100000);
+            let integrated = eval_albedo(mat.as_ref(), &r, &rec, 1000000);
+            assert_color_near(&sampled, &integrated, 0.02);
+            for i in 0..3 {
+                assert!(sampled[i] <= 1.05, "{sampled:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn pdf_matches_sampling() {
+        for mat in materials() {
+            let mat = Rc::new(mat);
+            let rec = record(mat.clone());
+            assert_pdf_matches_scatter(mat.as_ref(), &incoming(0.7), &rec, 200000);
+        }
+    }
+
+    #[test]
+    fn mixture_pdf_integrates_to_the_sampled_fraction() {
+        let mut rng = PCG32RNG::default();
+        let wo = Vec3::new(0.6, 0.0, 0.8);
+        for mat in materials() {
+            // lobes lose the directions they sample on the wrong side of the
+            // surface, which the pdf leaves out too
+            let n = 400000;
+            let kept = (0..n)
+                .filter(|_| mat.sample_wi(&wo, mat.ior, &mut rng).is_some())
+                .count() as f64
+                / n as f64;
+            let mut integral = 0.0;
+            for _ in 0..n {
+                let wi = Vec3::random_unit_vector(&mut rng);
+                integral += mat.eval_pdf(&wo, &wi, mat.ior).1 * 4.0 * PI / n as f64;
+            }
+            assert!((integral - kept).abs() < 0.02, "{integral} != {kept}");
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{
+    color::Color,
+    hittable::HitRecord,
+    material::{
+        Material, Scatter, fresnel,
+        microfacet::{TrowbridgeReitz, reflect, refract},
+    },
+    onb::Onb,
+    ray::Ray,
+    util::rand::PCG32RNG,
+    vec3::Vec3,
+};
+
+// frosted glass: ggx microfacet reflection and transmission with exact
+// dielectric fresnel (after pbrt-v4's DielectricBxDF)
+pub struct RoughDielectric {
+    refractive_index: f64,
+    distrib: TrowbridgeReitz,
+    // beer-lambert absorption coefficient of the interior, per unit length
+    absorption: Color,
+}
+
+impl RoughDielectric {
+    pub fn new(refractive_index: f64, roughness: f64) -> Self {
+        Self {
+            refractive_index,
+            distrib: TrowbridgeReitz::from_roughness(roughness, roughness),
+            absorption: Color::default(),
+        }
+    }
+
+    pub fn with_absorption(mut self, absorption: Color) -> Self {
+        self.absorption = absorption;
+        self
+    }
+
+    // the shading frame uses the face-forward normal, so wo is always in the
+    // upper hemisphere and eta is relative to the side the ray came from
+    fn local(&self, r_in: &Ray, rec: &HitRecord) -> (Onb, Vec3, f64) {
+        let frame = Onb::new(&rec.normal);
+        let wo = frame.to_local(&-r_in.direction().unit_vector());
+        let eta = if rec.front_face {
+            self.refractive_index
+        } else {
+            1.0 / self.refractive_index
+        };
+        (frame, wo, eta)
+    }
+
+    // generalized half vector of a reflected or transmitted pair, facing +z.
+    // None for degenerate or back-facing configurations
+    fn half_vector(wo: &Vec3, wi: &Vec3, eta: f64) -> Option<Vec3> {
+        let cos_theta_o = wo.z();
+        let cos_theta_i = wi.z();
+        if cos_theta_o == 0.0 || cos_theta_i == 0.0 {
+            return None;
+        }
+
+        let etap = if cos_theta_i > 0.0 { 1.0 } else { eta };
+        let wm = *wi * etap + *wo;
+        if wm.near_zero() {
+            return None;
+        }
+        let wm = wm.unit_vector();
+        let wm = if wm.z() < 0.0 { -wm } else { wm };
+
+        if Vec3::dot(&wm, wi) * cos_theta_i < 0.0 || Vec3::dot(&wm, wo) * cos_theta_o < 0.0 {
+            return None;
+        }
+        Some(wm)
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter> {
+        let (frame, wo, eta) = self.local(r_in, rec);
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        let white = Color::new(1.0, 1.0, 1.0);
+
+        if self.distrib.effectively_smooth() {
+            let n = Vec3::new(0.0, 0.0, 1.0);
+            let r = fresnel::dielectric(wo.z(), eta);
+            let wi = match refract(&wo, &n, eta) {
+                Some(wt) if rng.random_f64() >= r => wt,
+                _ => reflect(&wo, &n),
+            };
+            return Some(Scatter::specular(
+                white,
+                Ray::new(rec.p, frame.transform(&wi), r_in.time()),
+            ));
+        }
+
+        // pick a lobe in proportion to the fresnel terms; with visible normal
+        // sampling both estimators reduce to G / G1
+        let wm = self.distrib.sample_wm(&wo, rng);
+        let r = fresnel::dielectric(Vec3::dot(&wo, &wm), eta);
+
+        let wi = if rng.random_f64() < r {
+            let wi = reflect(&wo, &wm);
+            if wi.z() <= 0.0 {
+                return None;
+            }
+            wi
+        } else {
+            let wi = refract(&wo, &wm, eta)?;
+            if wi.z() >= 0.0 {
+                return None;
+            }
+            wi
+        };
+
+        let attenuation = white * (self.distrib.g(&wo, &wi) / self.distrib.g1(&wo));
+        Some(Scatter::new(
+            attenuation,
+            Ray::new(rec.p, frame.transform(&wi), r_in.time()),
+        ))
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        if self.distrib.effectively_smooth() {
+            return Color::default();
+        }
+
+        let (frame, wo, eta) = self.local(r_in, rec);
+        let wi = frame.to_local(&scattered.direction().unit_vector());
+        let Some(wm) = Self::half_vector(&wo, &wi, eta) else {
+            return Color::default();
+        };
+
+        let cos_theta_o = wo.z();
+        let cos_theta_i = wi.z();
+        let f = fresnel::dielectric(Vec3::dot(&wo, &wm), eta);
+        let d = self.distrib.d(&wm);
+        let g = self.distrib.g(&wo, &wi);
+
+        // both lobes are returned multiplied by |cos(theta_i)|
+        let value = if cos_theta_i > 0.0 {
+            d * g * f / (4.0 * cos_theta_o)
+        } else {
+            // light transported in radiance mode, without the 1 / eta^2
+            // scaling, to match the smooth `Dielectric`
+            let denom = (Vec3::dot(&wi, &wm) + Vec3::dot(&wo, &wm) / eta).powi(2) * cos_theta_o;
+            d * (1.0 - f) * g * (Vec3::dot(&wi, &wm) * Vec3::dot(&wo, &wm) / denom).abs()
+        };
+
+        Color::new(value, value, value)
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        if self.distrib.effectively_smooth() {
+            return 0.0;
+        }
+
+        let (frame, wo, eta) = self.local(r_in, rec);
+        let wi = frame.to_local(&scattered.direction().unit_vector());
+        let Some(wm) = Self::half_vector(&wo, &wi, eta) else {
+            return 0.0;
+        };
+
+        let r = fresnel::dielectric(Vec3::dot(&wo, &wm), eta);
+        let pdf_wm = self.distrib.pdf(&wo, &wm);
+
+        // jacobians from the microfacet normal to the reflected or
+        // refracted direction, times the chance of picking that lobe
+        if wi.z() > 0.0 {
+            pdf_wm / (4.0 * Vec3::dot(&wo, &wm).abs()) * r
+        } else {
+            let denom = (Vec3::dot(&wi, &wm) + Vec3::dot(&wo, &wm) / eta).powi(2);
+            pdf_wm * Vec3::dot(&wi, &wm).abs() / denom * (1.0 - r)
+        }
+    }
+
+    fn absorption(&self) -> Option<Color> {
+        Some(self.absorption)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::material::test_util::{
+        assert_color_near, assert_pdf_matches_scatter, eval_albedo, incoming, record,
+        scatter_albedo,
+    };
+
+    #[test]
+    fn eval_matches_sampling() {
+        for (roughness, cos_theta, front_face) in
+            [(0.5, 0.9, true), (0.7, 0.4, true), (0.5, 0.8, false)]
+        {
+            let mat = Rc::new(RoughDielectric::new(1.5, roughness));
+            let mut rec = record(mat.clone());
+            rec.front_face = front_face;
+            let r = incoming(cos_theta);
+            let sampled = scatter_albedo(mat.as_ref(), &r, &rec, 100000);
+            let integrated = eval_albedo(mat.as_ref(), &r, &rec, 1000000);
+            assert_color_near(&sampled, &integrated, 0.02);
+            // microfacets shadowing each other only lose energy
+            assert!(sampled.x() <= 1.0 && sampled.x() > 0.8, "{sampled:?}");
+        }
+    }
+
+    #[test]
+    fn pdf_matches_sampling() {
+        // reflection and transmission, entering and leaving
+        for (cos_theta, front_face) in [(0.7, true), (0.3, true), (0.9, false)] {
+            let mat = Rc::new(RoughDielectric::new(1.5, 0.5));
+            let mut rec = record(mat.clone());
+            rec.front_face = front_face;
+            assert_pdf_matches_scatter(mat.as_ref(), &incoming(cos_theta), &rec, 200000);
+        }
+    }
+
+    #[test]
+    fn smooth_surface_splits_by_fresnel() {
+        let mat = Rc::new(RoughDielectric::new(1.5, 0.0));
+        let rec = record(mat.clone());
+        let r = incoming(1.0);
+        let mut rng = PCG32RNG::default();
+        let n = 20000;
+        let mut reflected = 0;
+        for _ in 0..n {
+            let scatter = mat.scatter(&r, &rec, &mut rng).unwrap();
+            assert!(scatter.specular);
+            assert_eq!(scatter.attenuation, Color::new(1.0, 1.0, 1.0));
+            reflected += usize::from(scatter.ray.direction().z() > 0.0);
+        }
+        assert!((reflected as f64 / n as f64 - 0.04).abs() < 0.005);
+    }
+}
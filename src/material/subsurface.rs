@@ -0,0 +1,112 @@
+use std::rc::Rc;
+
+use crate::{
+    color::Color,
+    hittable::HitRecord,
+    material::{Dielectric, Material, Scatter},
+    phase::{IsotropicPhase, PhaseFunction},
+    ray::Ray,
+    util::rand::PCG32RNG,
+};
+
+// translucent solid (skin, wax, marble) behind a smooth dielectric boundary.
+// light refracted into the closed surface random walks through its interior
+// until it leaves again
+pub struct Subsurface {
+    boundary: Dielectric,
+    sigma_a: Color,
+    sigma_s: Color,
+    phase: Rc<dyn PhaseFunction>,
+}
+
+impl Subsurface {
+    // `albedo` is the single scattering albedo, clamped to [0, 1] so the
+    // medium never emits, and `mean_free_path` the average distance between
+    // collisions, per channel in scene units
+    pub fn new(albedo: Color, mean_free_path: Color, refractive_index: f64) -> Self {
+        let sigma_t = |mfp: f64| 1.0 / mfp.max(1e-6);
+        let sigma_t = Color::new(
+            sigma_t(mean_free_path.x()),
+            sigma_t(mean_free_path.y()),
+            sigma_t(mean_free_path.z()),
+        );
+        let albedo = Color::new(
+            albedo.x().clamp(0.0, 1.0),
+            albedo.y().clamp(0.0, 1.0),
+            albedo.z().clamp(0.0, 1.0),
+        );
+        let sigma_s = albedo * sigma_t;
+        Self {
+            boundary: Dielectric::new(refractive_index),
+            sigma_a: sigma_t - sigma_s,
+            sigma_s,
+            phase: Rc::new(IsotropicPhase),
+        }
+    }
+
+    pub fn with_phase(mut self, phase: Rc<dyn PhaseFunction>) -> Self {
+        self.phase = phase;
+        self
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter> {
+        self.boundary.scatter(r_in, rec, rng)
+    }
+
+    fn absorption(&self) -> Option<Color> {
+        Some(self.sigma_a)
+    }
+
+    fn scattering(&self) -> Option<(Color, Rc<dyn PhaseFunction>)> {
+        Some((self.sigma_s, self.phase.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::test_util::{assert_color_near, incoming, record};
+
+    #[test]
+    fn coefficients_follow_albedo_and_mean_free_path() {
+        let skin = Subsurface::new(Color::new(0.9, 0.5, 0.25), Color::new(0.5, 0.25, 0.1), 1.4);
+        let (sigma_s, _) = skin.scattering().unwrap();
+        let sigma_a = skin.absorption().unwrap();
+        assert_color_near(&sigma_s, &Color::new(1.8, 2.0, 2.5), 1e-12);
+        assert_color_near(&(sigma_a + sigma_s), &Color::new(2.0, 4.0, 10.0), 1e-12);
+    }
+
+    #[test]
+    fn albedo_is_clamped_to_the_unit_range() {
+        let mfp = Color::new(0.5, 0.5, 0.5);
+        let mat = Subsurface::new(Color::new(1.2, -0.1, 1.0), mfp, 1.4);
+        let (sigma_s, _) = mat.scattering().unwrap();
+        let sigma_a = mat.absorption().unwrap();
+        assert_color_near(&sigma_s, &Color::new(2.0, 0.0, 2.0), 1e-12);
+        assert_color_near(&sigma_a, &Color::new(0.0, 2.0, 0.0), 1e-12);
+    }
+
+    #[test]
+    fn boundary_is_a_smooth_dielectric() {
+        let wax = Rc::new(Subsurface::new(
+            Color::new(0.9, 0.9, 0.9),
+            Color::new(0.1, 0.1, 0.1),
+            1.5,
+        ));
+        let rec = record(wax.clone());
+        let mut rng = PCG32RNG::default();
+        for _ in 0..100 {
+            assert!(
+                wax.scatter(&incoming(0.7), &rec, &mut rng)
+                    .unwrap()
+                    .specular
+            );
+        }
+        assert_eq!(
+            wax.eval(&incoming(0.7), &rec, &incoming(0.7)),
+            Color::default()
+        );
+    }
+}
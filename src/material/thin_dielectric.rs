@@ -0,0 +1,142 @@
+use crate::{
+    color::Color,
+    hittable::HitRecord,
+    material::{Material, Scatter, fresnel},
+    ray::Ray,
+    util::rand::PCG32RNG,
+    vec3::Vec3,
+};
+
+// wavelengths, in nanometers, standing in for the r, g and b channels when
+// evaluating interference outside of spectral rendering
+const RGB_WAVELENGTHS: [f64; 3] = [630.0, 532.0, 465.0];
+
+// infinitely thin sheet of glass (window panes, soap bubbles) rendered as a
+// single surface: light is either reflected or passes straight through, with
+// the inter-reflections between the two faces summed up
+pub struct ThinDielectric {
+    refractive_index: f64,
+    // interference film on one face: thickness in nanometers and index
+    film: Option<(f64, f64)>,
+}
+
+impl ThinDielectric {
+    pub fn new(refractive_index: f64) -> Self {
+        Self {
+            refractive_index,
+            film: None,
+        }
+    }
+
+    // a soap bubble is a bare film: `ThinDielectric::new(1.0)` with a film of
+    // a few hundred nanometers and index 1.33
+    pub fn with_thin_film(mut self, thickness: f64, refractive_index: f64) -> Self {
+        self.film = Some((thickness, refractive_index));
+        self
+    }
+
+    // reflectance of the whole sheet at wavelength `lambda`
+    fn reflectance(&self, cos_theta: f64, lambda: f64) -> f64 {
+        let back = fresnel::dielectric(cos_theta, self.refractive_index);
+        let front = match self.film {
+            Some((thickness, film_ior)) => fresnel::thin_film(
+                cos_theta,
+                film_ior,
+                self.refractive_index,
+                thickness,
+                lambda,
+            ),
+            None => back,
+        };
+
+        // incoherent sum over the light bouncing between the two faces
+        let denom = 1.0 - front * back;
+        if denom <= 0.0 {
+            return 1.0;
+        }
+        front + (1.0 - front) * (1.0 - front) * back / denom
+    }
+}
+
+impl Material for ThinDielectric {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter> {
+        let unit_direction = r_in.direction().unit_vector();
+        let cos_theta = Vec3::dot(&-unit_direction, &rec.normal).min(1.0);
+
+        // spectral rays pick the lobe for their hero wavelength only, which
+        // makes the film dispersive
+        let reflectance = match r_in.wavelengths() {
+            Some(lambda) => {
+                let r = self.reflectance(cos_theta, lambda.hero());
+                Color::new(r, r, r)
+            }
+            None => {
+                let [r, g, b] = RGB_WAVELENGTHS.map(|l| self.reflectance(cos_theta, l));
+                Color::new(r, g, b)
+            }
+        };
+
+        // choose a lobe by the average reflectance, weighting each channel
+        // by its own share. random_f64 can return 1, so total reflection is
+        // decided before drawing
+        let p = (reflectance.x() + reflectance.y() + reflectance.z()) / 3.0;
+        let (attenuation, direction) = if p >= 1.0 || rng.random_f64() < p {
+            (reflectance / p, Vec3::reflect(&unit_direction, &rec.normal))
+        } else {
+            (
+                (Color::new(1.0, 1.0, 1.0) - reflectance) / (1.0 - p),
+                unit_direction,
+            )
+        };
+
+        Some(Scatter::specular(
+            attenuation,
+            Ray::new(rec.p, direction, r_in.time()),
+        ))
+    }
+
+    fn dispersive(&self) -> bool {
+        self.film.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{material::test_util::record, vec3::Point3};
+
+    #[test]
+    fn grazing_light_is_reflected() {
+        let sheet = Rc::new(ThinDielectric::new(1.5));
+        let rec = record(sheet.clone());
+        let r = Ray::new(Point3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let mut rng = PCG32RNG::default();
+        for _ in 0..1000 {
+            let scatter = sheet.scatter(&r, &rec, &mut rng).unwrap();
+            assert_eq!(scatter.attenuation, Color::new(1.0, 1.0, 1.0));
+            assert_eq!(*scatter.ray.direction(), Vec3::new(1.0, 0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn lobe_weights_are_unbiased() {
+        let sheet = Rc::new(ThinDielectric::new(1.5).with_thin_film(300.0, 1.33));
+        let rec = record(sheet.clone());
+        let r = Ray::new(Point3::new(0.0, -1.0, 1.0), Vec3::new(0.0, 1.0, -1.0), 0.0);
+        let mut rng = PCG32RNG::default();
+
+        // reflected and transmitted energy add up to one per channel
+        let n = 20000;
+        let mut total = Color::default();
+        for _ in 0..n {
+            let attenuation = sheet.scatter(&r, &rec, &mut rng).unwrap().attenuation;
+            assert!(attenuation.x().is_finite());
+            total += attenuation / n as f64;
+        }
+        for i in 0..3 {
+            assert!((total[i] - 1.0).abs() < 0.05, "{total:?}");
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::rc::Rc;
+
+use crate::{
+    color::Color,
+    hittable::HitRecord,
+    material::{Material, Scatter},
+    phase::PhaseFunction,
+    ray::Ray,
+    util::rand::PCG32RNG,
+};
+
+// participating medium material scattering according to any phase function,
+// e.g. `ConstantMedium::with_material(boundary, density, Rc::new(Volumetric::new(..)))`
+pub struct Volumetric {
+    albedo: Color,
+    phase: Rc<dyn PhaseFunction>,
+}
+
+impl Volumetric {
+    pub fn new(albedo: Color, phase: Rc<dyn PhaseFunction>) -> Self {
+        Self { albedo, phase }
+    }
+}
+
+impl Material for Volumetric {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut PCG32RNG) -> Option<Scatter> {
+        // p / pdf is 1 for the exactly sampled phase functions here
+        let direction = self.phase.sample(r_in.direction(), rng);
+        let pdf = self.phase.pdf(r_in.direction(), &direction);
+        if pdf <= 0.0 {
+            return None;
+        }
+        let weight = self.phase.p(r_in.direction(), &direction) / pdf;
+        Some(Scatter::new(
+            self.albedo * weight,
+            Ray::new(rec.p, direction, r_in.time()),
+        ))
+    }
+
+    fn eval(&self, r_in: &Ray, _rec: &HitRecord, scattered: &Ray) -> Color {
+        self.albedo * self.phase.p(r_in.direction(), scattered.direction())
+    }
+
+    fn pdf(&self, r_in: &Ray, _rec: &HitRecord, scattered: &Ray) -> f64 {
+        self.phase.pdf(r_in.direction(), scattered.direction())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        material::test_util::{
+            assert_color_near, assert_pdf_matches_scatter, eval_albedo, incoming, record,
+        },
+        phase::HenyeyGreenstein,
+        vec3::{Point3, Vec3},
+    };
+
+    #[test]
+    fn scatters_by_the_phase_function() {
+        let albedo = Color::new(0.9, 0.8, 0.7);
+        let mat = Rc::new(Volumetric::new(albedo, Rc::new(HenyeyGreenstein::new(0.6))));
+        let rec = record(mat.clone());
+        let r = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -2.0), 0.0);
+
+        let mut rng = PCG32RNG::default();
+        let n = 20000;
+        let mut mean_cos = 0.0;
+        for _ in 0..n {
+            let scatter = mat.scatter(&r, &rec, &mut rng).unwrap();
+            assert_eq!(scatter.attenuation, albedo);
+            assert!(!scatter.specular);
+            mean_cos -= scatter.ray.direction().z() / n as f64;
+        }
+        assert!((mean_cos - 0.6).abs() < 0.02, "{mean_cos}");
+
+        // the phase function is normalized over the sphere
+        assert_color_near(&eval_albedo(mat.as_ref(), &r, &rec, 400000), &albedo, 0.02);
+    }
+
+    #[test]
+    fn pdf_matches_sampling() {
+        let mat = Rc::new(Volumetric::new(
+            Color::new(0.5, 0.5, 0.5),
+            Rc::new(HenyeyGreenstein::new(0.4)),
+        ));
+        let rec = record(mat.clone());
+        assert_pdf_matches_scatter(mat.as_ref(), &incoming(0.3), &rec, 200000);
+    }
+}
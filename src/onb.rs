@@ -0,0 +1,117 @@
+use crate::vec3::Vec3;
+
+// orthonormal basis with `w` along a given direction
+#[derive(Debug, Clone)]
+pub struct Onb {
+    axis: [Vec3; 3],
+}
+
+impl Onb {
+    pub fn new(n: &Vec3) -> Self {
+        let w = n.unit_vector();
+        let a = if w.x().abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = Vec3::cross(&w, &a).unit_vector();
+        let u = Vec3::cross(&w, &v);
+
+        Self { axis: [u, v, w] }
+    }
+
+    // basis with `w` along `n` and `u` along the part of `tangent`
+    // perpendicular to it, so anisotropic lobes follow the surface
+    // parameterization. falls back to `new` for degenerate tangents
+    pub fn from_tangent(n: &Vec3, tangent: &Vec3) -> Self {
+        let w = n.unit_vector();
+        let t = *tangent - Vec3::dot(tangent, &w) * w;
+        if t.near_zero() {
+            return Self::new(n);
+        }
+        // same handedness as `new`
+        let u = t.unit_vector();
+        let v = Vec3::cross(&u, &w);
+
+        Self { axis: [u, v, w] }
+    }
+
+    pub fn u(&self) -> &Vec3 {
+        &self.axis[0]
+    }
+
+    pub fn v(&self) -> &Vec3 {
+        &self.axis[1]
+    }
+
+    // basis coordinates to world
+    pub fn transform(&self, v: &Vec3) -> Vec3 {
+        (v.x() * self.axis[0]) + (v.y() * self.axis[1]) + (v.z() * self.axis[2])
+    }
+
+    // world to basis coordinates
+    pub fn to_local(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            Vec3::dot(v, &self.axis[0]),
+            Vec3::dot(v, &self.axis[1]),
+            Vec3::dot(v, &self.axis[2]),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_orthonormal(onb: &Onb) {
+        for (i, a) in onb.axis.iter().enumerate() {
+            assert!((a.len() - 1.0).abs() < 1e-12);
+            for b in &onb.axis[i + 1..] {
+                assert!(Vec3::dot(a, b).abs() < 1e-12);
+            }
+        }
+        // handedness matches `new`
+        assert!((Vec3::cross(onb.u(), onb.v()) + onb.axis[2]).len() < 1e-12);
+    }
+
+    #[test]
+    fn normal_frame_is_orthonormal() {
+        for n in [
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(-0.3, 2.0, 0.5),
+        ] {
+            let onb = Onb::new(&n);
+            assert_orthonormal(&onb);
+            assert!((onb.axis[2] - n.unit_vector()).len() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn tangent_frame_follows_the_tangent() {
+        let n = Vec3::new(0.0, 0.0, 2.0);
+        let onb = Onb::from_tangent(&n, &Vec3::new(3.0, 0.0, 1.0));
+        assert_orthonormal(&onb);
+        assert!((*onb.u() - Vec3::new(1.0, 0.0, 0.0)).len() < 1e-12);
+        assert!((onb.axis[2] - Vec3::new(0.0, 0.0, 1.0)).len() < 1e-12);
+    }
+
+    #[test]
+    fn tangent_frame_is_continuous() {
+        // `new` switches its helper axis at |n.x| = 0.9
+        let tangent = Vec3::new(0.0, 0.0, 1.0);
+        let a = Onb::from_tangent(&Vec3::new(0.8999, 0.436, 0.0), &tangent);
+        let b = Onb::from_tangent(&Vec3::new(0.9001, 0.4356, 0.0), &tangent);
+        assert_orthonormal(&a);
+        assert_orthonormal(&b);
+        assert!((*a.u() - *b.u()).len() < 1e-3);
+        assert!((*a.v() - *b.v()).len() < 1e-3);
+    }
+
+    #[test]
+    fn degenerate_tangent_falls_back() {
+        let n = Vec3::new(0.0, 1.0, 0.0);
+        assert_orthonormal(&Onb::from_tangent(&n, &Vec3::default()));
+        assert_orthonormal(&Onb::from_tangent(&n, &Vec3::new(0.0, 5.0, 0.0)));
+    }
+}
@@ -0,0 +1,208 @@
+use std::f64::consts::PI;
+
+use crate::{onb::Onb, util::rand::PCG32RNG, vec3::Vec3};
+
+// angular distribution of light scattered inside a participating medium.
+// directions follow the light: `dir_in` is the direction of the incoming ray
+// and `dir_out` that of the scattered one, so cos_theta = 1 is no deflection
+pub trait PhaseFunction {
+    // phase function value, normalized over the sphere of directions
+    fn p(&self, dir_in: &Vec3, dir_out: &Vec3) -> f64;
+
+    // returns a unit scattered direction
+    fn sample(&self, dir_in: &Vec3, rng: &mut PCG32RNG) -> Vec3;
+
+    // solid angle density with which `sample` picks `dir_out`. every phase
+    // function here is sampled exactly, so this is the phase function itself
+    fn pdf(&self, dir_in: &Vec3, dir_out: &Vec3) -> f64 {
+        self.p(dir_in, dir_out)
+    }
+}
+
+fn cos_theta(dir_in: &Vec3, dir_out: &Vec3) -> f64 {
+    Vec3::dot(&dir_in.unit_vector(), &dir_out.unit_vector())
+}
+
+// direction at polar angle acos(cos_theta) around `axis`, uniform in azimuth
+fn direction_around(axis: &Vec3, cos_theta: f64, rng: &mut PCG32RNG) -> Vec3 {
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * rng.random_f64();
+    Onb::new(axis).transform(&Vec3::new(
+        sin_theta * phi.cos(),
+        sin_theta * phi.sin(),
+        cos_theta,
+    ))
+}
+
+pub struct IsotropicPhase;
+
+impl PhaseFunction for IsotropicPhase {
+    fn p(&self, _dir_in: &Vec3, _dir_out: &Vec3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn sample(&self, _dir_in: &Vec3, rng: &mut PCG32RNG) -> Vec3 {
+        Vec3::random_unit_vector(rng)
+    }
+}
+
+// g in (-1, 1): positive values scatter forward, negative backward,
+// zero is isotropic
+pub struct HenyeyGreenstein {
+    g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(g: f64) -> Self {
+        Self {
+            g: g.clamp(-0.999, 0.999),
+        }
+    }
+}
+
+impl PhaseFunction for HenyeyGreenstein {
+    fn p(&self, dir_in: &Vec3, dir_out: &Vec3) -> f64 {
+        let g = self.g;
+        let denom = 1.0 + g * g - 2.0 * g * cos_theta(dir_in, dir_out);
+        (1.0 - g * g) / (4.0 * PI * denom * denom.max(0.0).sqrt())
+    }
+
+    fn sample(&self, dir_in: &Vec3, rng: &mut PCG32RNG) -> Vec3 {
+        let g = self.g;
+        let xi = rng.random_f64();
+        let cos_theta = if g.abs() < 1e-3 {
+            2.0 * xi - 1.0
+        } else {
+            let sq = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+            ((1.0 + g * g - sq * sq) / (2.0 * g)).clamp(-1.0, 1.0)
+        };
+        direction_around(dir_in, cos_theta, rng)
+    }
+}
+
+// scattering by particles much smaller than the wavelength (clear sky)
+pub struct Rayleigh;
+
+impl PhaseFunction for Rayleigh {
+    fn p(&self, dir_in: &Vec3, dir_out: &Vec3) -> f64 {
+        let mu = cos_theta(dir_in, dir_out);
+        3.0 / (16.0 * PI) * (1.0 + mu * mu)
+    }
+
+    fn sample(&self, dir_in: &Vec3, rng: &mut PCG32RNG) -> Vec3 {
+        // invert the cdf (mu^3 + 3mu + 4) / 8 = xi with cardano's formula
+        let q = 4.0 - 8.0 * rng.random_f64();
+        let u = (-q / 2.0 + (q * q / 4.0 + 1.0).sqrt()).cbrt();
+        let cos_theta = (u - 1.0 / u).clamp(-1.0, 1.0);
+        direction_around(dir_in, cos_theta, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // integral of p over the sphere, in cos_theta (azimuth is uniform)
+    fn integral(phase: &dyn PhaseFunction) -> f64 {
+        let dir_in = Vec3::new(0.0, 0.0, 1.0);
+        let n = 100000;
+        (0..n)
+            .map(|i| {
+                let mu = -1.0 + 2.0 * (i as f64 + 0.5) / n as f64;
+                let dir_out = Vec3::new((1.0 - mu * mu).sqrt(), 0.0, mu);
+                phase.p(&dir_in, &dir_out)
+            })
+            .sum::<f64>()
+            * 2.0
+            / n as f64
+            * 2.0
+            * PI
+    }
+
+    // mean of cos_theta and cos_theta^2 over sampled directions
+    fn sampled_moments(phase: &dyn PhaseFunction) -> (f64, f64) {
+        let dir_in = Vec3::new(1.0, -2.0, 0.5).unit_vector();
+        let mut rng = PCG32RNG::default();
+        let n = 100000;
+        let (mut m1, mut m2) = (0.0, 0.0);
+        for _ in 0..n {
+            let dir_out = phase.sample(&dir_in, &mut rng);
+            assert!((dir_out.len() - 1.0).abs() < 1e-9);
+            let mu = Vec3::dot(&dir_in, &dir_out);
+            m1 += mu / n as f64;
+            m2 += mu * mu / n as f64;
+        }
+        (m1, m2)
+    }
+
+    #[test]
+    fn phase_functions_are_normalized() {
+        for phase in [
+            &IsotropicPhase as &dyn PhaseFunction,
+            &HenyeyGreenstein::new(0.7),
+            &HenyeyGreenstein::new(-0.3),
+            &Rayleigh,
+        ] {
+            assert!((integral(phase) - 1.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn henyey_greenstein_mean_cosine_is_g() {
+        for g in [-0.6, 0.0, 0.3, 0.9] {
+            let (mean, _) = sampled_moments(&HenyeyGreenstein::new(g));
+            assert!((mean - g).abs() < 0.01, "{g}: {mean}");
+        }
+    }
+
+    #[test]
+    fn rayleigh_samples_follow_the_distribution() {
+        // <mu> = 0 and <mu^2> = 2/5 under 3/8 (1 + mu^2)
+        let (m1, m2) = sampled_moments(&Rayleigh);
+        assert!(m1.abs() < 0.01, "{m1}");
+        assert!((m2 - 0.4).abs() < 0.01, "{m2}");
+
+        let (m1, m2) = sampled_moments(&IsotropicPhase);
+        assert!(m1.abs() < 0.01 && (m2 - 1.0 / 3.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn sampled_frequencies_match_the_pdf() {
+        let dir_in = Vec3::new(0.3, 1.0, -0.2).unit_vector();
+        let bins = 20;
+        let n = 200000;
+        for phase in [
+            &IsotropicPhase as &dyn PhaseFunction,
+            &HenyeyGreenstein::new(0.7),
+            &HenyeyGreenstein::new(-0.4),
+            &Rayleigh,
+        ] {
+            // histogram of cos_theta, each bin a band of equal solid angle
+            let mut rng = PCG32RNG::default();
+            let mut histogram = vec![0.0; bins];
+            for _ in 0..n {
+                let mu = Vec3::dot(&dir_in, &phase.sample(&dir_in, &mut rng));
+                let bin = ((mu + 1.0) / 2.0 * bins as f64) as usize;
+                histogram[bin.min(bins - 1)] += 1.0 / n as f64;
+            }
+
+            // pdf integrated over each band, with the midpoint rule in mu
+            let frame = Onb::new(&dir_in);
+            for (bin, frequency) in histogram.into_iter().enumerate() {
+                let steps = 50;
+                let mut expected = 0.0;
+                for k in 0..steps {
+                    let mu =
+                        -1.0 + 2.0 * (bin as f64 + (k as f64 + 0.5) / steps as f64) / bins as f64;
+                    let dir_out = frame.transform(&Vec3::new((1.0 - mu * mu).sqrt(), 0.0, mu));
+                    expected +=
+                        phase.pdf(&dir_in, &dir_out) * 2.0 * PI * 2.0 / (bins * steps) as f64;
+                }
+                assert!(
+                    (frequency - expected).abs() < 0.003 + 0.02 * expected,
+                    "bin {bin}: {frequency} vs {expected}"
+                );
+            }
+        }
+    }
+}
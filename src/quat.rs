@@ -0,0 +1,170 @@
+use std::ops;
+
+use crate::{mat4::Mat4, vec3::Vec3};
+
+// unit quaternion w + v, used to interpolate rotations
+#[derive(Debug, Clone, Copy)]
+pub struct Quat {
+    w: f64,
+    v: Vec3,
+}
+
+impl Quat {
+    pub fn new(w: f64, v: Vec3) -> Self {
+        Self { w, v }
+    }
+
+    pub fn dot(a: &Self, b: &Self) -> f64 {
+        a.w * b.w + Vec3::dot(&a.v, &b.v)
+    }
+
+    pub fn normalize(&self) -> Self {
+        *self * (1.0 / Self::dot(self, self).sqrt())
+    }
+
+    // quaternion of the rotation in the upper 3x3 of `m` (shoemake), which
+    // must be orthonormal with a positive determinant
+    pub fn from_mat4(m: &Mat4) -> Self {
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        if trace > 0.0 {
+            let s = (trace + 1.0).sqrt();
+            let w = 0.5 * s;
+            let s = 0.5 / s;
+            return Self::new(
+                w,
+                Vec3::new(
+                    (m[2][1] - m[1][2]) * s,
+                    (m[0][2] - m[2][0]) * s,
+                    (m[1][0] - m[0][1]) * s,
+                ),
+            );
+        }
+
+        // largest diagonal entry first, for precision
+        let i = if m[1][1] > m[0][0] { 1 } else { 0 };
+        let i = if m[2][2] > m[i][i] { 2 } else { i };
+        let j = (i + 1) % 3;
+        let k = (j + 1) % 3;
+
+        let s = (m[i][i] - m[j][j] - m[k][k] + 1.0).sqrt();
+        let mut v = Vec3::default();
+        v[i] = 0.5 * s;
+        let s = if s != 0.0 { 0.5 / s } else { s };
+        v[j] = (m[j][i] + m[i][j]) * s;
+        v[k] = (m[k][i] + m[i][k]) * s;
+        Self::new((m[k][j] - m[j][k]) * s, v)
+    }
+
+    pub fn to_mat4(self) -> Mat4 {
+        let (x, y, z, w) = (self.v.x(), self.v.y(), self.v.z(), self.w);
+        Mat4::new([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - z * w),
+                2.0 * (x * z + y * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + z * w),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - x * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - y * w),
+                2.0 * (y * z + x * w),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // constant angular velocity interpolation along the shorter arc
+    pub fn slerp(a: &Self, b: &Self, t: f64) -> Self {
+        let mut cos_theta = Self::dot(a, b);
+        let b = if cos_theta < 0.0 {
+            cos_theta = -cos_theta;
+            -*b
+        } else {
+            *b
+        };
+
+        // nearly parallel, where the sine below vanishes
+        if cos_theta > 0.9995 {
+            return (*a * (1.0 - t) + b * t).normalize();
+        }
+
+        let theta = cos_theta.clamp(-1.0, 1.0).acos();
+        let theta_t = theta * t;
+        let perp = (b - *a * cos_theta).normalize();
+        *a * theta_t.cos() + perp * theta_t.sin()
+    }
+}
+
+impl ops::Neg for Quat {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.w, -self.v)
+    }
+}
+
+impl ops::Add for Quat {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.w + rhs.w, self.v + rhs.v)
+    }
+}
+
+impl ops::Sub for Quat {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.w - rhs.w, self.v - rhs.v)
+    }
+}
+
+impl ops::Mul<f64> for Quat {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self::new(self.w * rhs, self.v * rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_mat_eq(a: &Mat4, b: &Mat4) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((a[i][j] - b[i][j]).abs() < 1e-9, "{a:?} != {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn matrix_round_trip() {
+        for (axis, deg) in [
+            (Vec3::new(0.0, 0.0, 1.0), 30.0),
+            (Vec3::new(1.0, 2.0, -0.5), 120.0),
+            (Vec3::new(0.0, 1.0, 0.0), 180.0),
+            (Vec3::new(-1.0, 0.3, 0.2), 250.0),
+        ] {
+            let m = Mat4::rotate(&axis, deg);
+            assert_mat_eq(&Quat::from_mat4(&m).to_mat4(), &m);
+        }
+    }
+
+    #[test]
+    fn slerp_halves_the_angle() {
+        let axis = Vec3::new(1.0, 1.0, 0.0);
+        let a = Quat::from_mat4(&Mat4::IDENTITY);
+        let b = Quat::from_mat4(&Mat4::rotate(&axis, 180.0));
+        let half = Quat::slerp(&a, &b, 0.5).to_mat4();
+        assert_mat_eq(&half, &Mat4::rotate(&axis, 90.0));
+    }
+}
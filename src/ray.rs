@@ -1,14 +1,30 @@
-use crate::vec3::{Point3, Vec3};
+use crate::{
+    spectrum::Wavelengths,
+    vec3::{Point3, Vec3},
+};
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Ray {
     origin: Point3,
     direction: Vec3,
+    time: f64,
+    // set when rendering spectrally
+    wavelengths: Option<Wavelengths>,
 }
 
 impl Ray {
-    pub fn new(origin: Point3, direction: Vec3) -> Self {
-        Self { origin, direction }
+    pub fn new(origin: Point3, direction: Vec3, time: f64) -> Self {
+        Self {
+            origin,
+            direction,
+            time,
+            wavelengths: None,
+        }
+    }
+
+    pub fn with_wavelengths(mut self, wavelengths: Option<Wavelengths>) -> Self {
+        self.wavelengths = wavelengths;
+        self
     }
 
     pub fn origin(&self) -> &Point3 {
@@ -19,6 +35,14 @@ impl Ray {
         &self.direction
     }
 
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn wavelengths(&self) -> Option<&Wavelengths> {
+        self.wavelengths.as_ref()
+    }
+
     pub fn at(&self, t: f64) -> Point3 {
         self.origin + (t * self.direction)
     }
@@ -0,0 +1,1251 @@
+use std::{f64::consts::PI, io, rc::Rc};
+
+use crate::{
+    aperture::Aperture,
+    camera::{Camera, Projection, StereoLayout, StereoMode},
+    color::Color,
+    hittable::{
+        Hittable, constant_medium::ConstantMedium, grid_medium::GridMedium, list::HittableList,
+        quad::Quad, sphere::Sphere, transform::Transform, triangle::Triangle,
+    },
+    ies::IesProfile,
+    image::Image,
+    lens::LensSystem,
+    light::{
+        Light, bvh::LightBvh, directional::DirectionalLight, list::LightList,
+        photometric::PhotometricLight, point::PointLight, power::PowerLightSampler,
+        sphere::SphereLight, spot::SpotLight,
+    },
+    mat4::Mat4,
+    material::{
+        AlphaMask, Coated, Conductor, Dielectric, DiffuseLight, Ior, Lambertian, Material, Metal,
+        Mix, NormalMap, OrenNayar, Principled, RoughDielectric, Subsurface, ThinDielectric,
+        Volumetric,
+    },
+    phase::{HenyeyGreenstein, Rayleigh},
+    sky::PreethamSky,
+    texture::{ImageTexture, SolidColor},
+    util::{self, rand::PCG32RNG},
+    vec3::{Point3, Vec3},
+    volume::DensityGrid,
+};
+
+// names accepted by `load`, the first one is the default
+pub const NAMES: &[&str] = &[
+    "spheres",
+    "bouncing",
+    "instances",
+    "smoke",
+    "cloud",
+    "materials",
+    "dispersion",
+    "textures",
+    "lights",
+    "sky",
+    "many_lights",
+    "photometric",
+    "fisheye",
+    "orthographic",
+    "panorama",
+    "stereo",
+    "toe_in",
+    "stereo_panorama",
+    "bokeh",
+    "lens",
+    "tilt_shift",
+];
+
+// what to render: the objects, the lights sampled by next-event estimation
+// and the camera
+pub struct Scene {
+    pub world: HittableList,
+    pub lights: Rc<dyn Light>,
+    pub camera: Camera,
+}
+
+// builds the scene called `name`. `asset` replaces the built-in input of the
+// scenes that read one: a voxel grid for "cloud", a ppm image for "textures"
+// an ies profile for "photometric", an aperture mask for "bokeh" and a lens
+// prescription for "lens"
+pub fn load(name: &str, asset: Option<&str>, rng: &mut PCG32RNG) -> io::Result<Scene> {
+    match name {
+        "spheres" => Ok(random_spheres(rng, false)),
+        "bouncing" => Ok(random_spheres(rng, true)),
+        "instances" => Ok(instances()),
+        "smoke" => Ok(smoke()),
+        "cloud" => cloud(asset),
+        "materials" => Ok(materials()),
+        "dispersion" => Ok(dispersion()),
+        "textures" => textures(asset),
+        "lights" => Ok(lights()),
+        "sky" => Ok(sky()),
+        "many_lights" => Ok(many_lights(rng)),
+        "photometric" => photometric(asset),
+        "fisheye" => Ok(fisheye()),
+        "orthographic" => Ok(orthographic()),
+        "panorama" => Ok(panorama()),
+        "stereo" => Ok(stereo()),
+        "toe_in" => Ok(toe_in()),
+        "stereo_panorama" => Ok(stereo_panorama()),
+        "bokeh" => bokeh(asset, rng),
+        "lens" => lens(asset),
+        "tilt_shift" => Ok(tilt_shift(rng)),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unknown scene {name}, expected one of {}", NAMES.join(", ")),
+        )),
+    }
+}
+
+fn lambertian(r: f64, g: f64, b: f64) -> Rc<dyn Material> {
+    Rc::new(Lambertian::new(Color::new(r, g, b)))
+}
+
+// sphere 1000 units wide, its top at y = 0
+fn ground(mat: Rc<dyn Material>) -> Rc<dyn Hittable> {
+    Rc::new(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, mat))
+}
+
+// square floor `size` units wide around the origin, with uvs across it
+fn floor(size: f64, mat: Rc<dyn Material>) -> Rc<dyn Hittable> {
+    Rc::new(Quad::new(
+        Point3::new(-size / 2.0, 0.0, size / 2.0),
+        Vec3::new(size, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -size),
+        mat,
+    ))
+}
+
+// white and black checkerboard `squares` squares wide, 8 pixels each
+fn checker(squares: usize) -> Image {
+    let size = 8 * squares;
+    let data = (0..size * size)
+        .map(|i| {
+            if (i % size / 8 + i / size / 8).is_multiple_of(2) {
+                Color::new(1.0, 1.0, 1.0)
+            } else {
+                Color::default()
+            }
+        })
+        .collect();
+    Image::new(size, size, data)
+}
+
+// floor checkered with unit squares
+fn checkered_floor(size: usize) -> Rc<dyn Hittable> {
+    let mat = Mix::textured(
+        lambertian(0.1, 0.1, 0.1),
+        lambertian(0.8, 0.8, 0.8),
+        Rc::new(ImageTexture::new(checker(size))),
+    );
+    floor(size as f64, Rc::new(mat))
+}
+
+// the final scene of "ray tracing in one weekend". with `bouncing` the
+// diffuse spheres jump up while the shutter is open
+fn random_world(rng: &mut PCG32RNG, bouncing: bool) -> HittableList {
+    let mut world = HittableList::default();
+
+    let sp_ground = Rc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    ));
+    world.add(sp_ground.clone());
+
+    let mut choose_mat: f64;
+    let mut center: Point3;
+    for a in -11..11 {
+        for b in -11..11 {
+            let a = a as f64;
+            let b = b as f64;
+
+            choose_mat = rng.random_f64();
+            center = Point3::new(a + 0.9 * rng.random_f64(), 0.2, b + 0.9 * rng.random_f64());
+
+            if (center - Point3::new(4.0, 0.2, 0.0)).len() > 0.9 {
+                if choose_mat < 0.8 {
+                    let sphere_mat =
+                        Rc::new(Lambertian::new(Color::random(rng) * Color::random(rng)));
+                    let sp = if bouncing {
+                        let center2 =
+                            center + Vec3::new(0.0, rng.random_bounded_f64(0.0, 0.5), 0.0);
+                        Sphere::new_moving(center, center2, 0.2, sphere_mat)
+                    } else {
+                        Sphere::new(center, 0.2, sphere_mat)
+                    };
+                    world.add(Rc::new(sp));
+                    continue;
+                }
+
+                let sphere_mat: Rc<dyn Material> = if choose_mat < 0.95 {
+                    Rc::new(Metal::new(
+                        Color::random_bounded(rng, 0.5, 1.0),
+                        rng.random_bounded_f64(0.0, 0.5),
+                    ))
+                } else {
+                    Rc::new(Dielectric::new(1.5))
+                };
+                let sp = Rc::new(Sphere::new(center, 0.2, sphere_mat));
+                world.add(sp.clone());
+            }
+        }
+    }
+
+    let sp1 = Rc::new(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        Rc::new(Dielectric::new(1.5)),
+    ));
+    world.add(sp1.clone());
+
+    let sp2 = Rc::new(Sphere::new(
+        Point3::new(-4.0, 1.0, 0.0),
+        1.0,
+        Rc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1))),
+    ));
+    world.add(sp2.clone());
+
+    let sp3 = Rc::new(Sphere::new(
+        Point3::new(4.0, 1.0, 0.0),
+        1.0,
+        Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0)),
+    ));
+    world.add(sp3.clone());
+
+    world
+}
+
+fn random_spheres(rng: &mut PCG32RNG, bouncing: bool) -> Scene {
+    let world = random_world(rng, bouncing);
+    let camera = Camera::new(
+        16.0 / 10.0,
+        1200,
+        10,
+        50,
+        20.0,
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.6,
+        10.0,
+    );
+    Scene {
+        world,
+        lights: Rc::new(LightList::default()),
+        // a half open shutter, like a 180 degree film camera
+        camera: if bouncing {
+            camera.with_shutter(0.0, 0.5)
+        } else {
+            camera
+        },
+    }
+}
+
+// a unit sphere with two small ones on top, the object instanced by
+// "instances"
+fn figure() -> Rc<dyn Hittable> {
+    let mut figure = HittableList::default();
+    figure.add(Rc::new(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        lambertian(0.6, 0.4, 0.2),
+    )));
+    figure.add(Rc::new(Sphere::new(
+        Point3::new(0.0, 2.3, 0.0),
+        0.5,
+        Rc::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.1)),
+    )));
+    figure.add(Rc::new(Sphere::new(
+        Point3::new(0.0, 3.0, 0.0),
+        0.25,
+        lambertian(0.2, 0.3, 0.7),
+    )));
+    Rc::new(figure)
+}
+
+// one figure instanced around a ring, each copy turned to face the center
+// and squashed or stretched a little more than the last, and a copy tipping
+// over and up in the middle, blurred by its motion
+fn instances() -> Scene {
+    let mut world = HittableList::default();
+    world.add(ground(lambertian(0.5, 0.5, 0.5)));
+
+    let figure = figure();
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    for i in 0..8 {
+        let angle = 45.0 * i as f64;
+        let (sin, cos) = util::deg_to_rad(angle).sin_cos();
+        let stretch = 0.6 + 0.1 * i as f64;
+        let object_to_world = Mat4::translate(&Vec3::new(5.0 * cos, 0.0, 5.0 * sin))
+            * Mat4::rotate(&up, -angle)
+            * Mat4::scale(&Vec3::new(0.9 / stretch, 0.9 * stretch, 0.9));
+        world.add(Rc::new(Transform::new(figure.clone(), object_to_world)));
+    }
+    let end =
+        Mat4::translate(&Vec3::new(0.0, 1.0, 0.0)) * Mat4::rotate(&Vec3::new(0.0, 0.0, 1.0), 30.0);
+    world.add(Rc::new(Transform::animated(figure, Mat4::IDENTITY, end)));
+
+    Scene {
+        world,
+        lights: Rc::new(LightList::default()),
+        camera: Camera::new(
+            16.0 / 9.0,
+            600,
+            100,
+            50,
+            40.0,
+            Point3::new(0.0, 6.0, 12.0),
+            Point3::new(0.0, 1.0, 0.0),
+            up,
+            0.0,
+            12.0,
+        ),
+    }
+}
+
+// a ball of dark smoke, a glass sphere holding a wisp of white smoke and a
+// squashed cloud of fog, the last bounded by an instanced sphere
+fn smoke() -> Scene {
+    let mut world = HittableList::default();
+    world.add(ground(lambertian(0.5, 0.5, 0.5)));
+
+    let boundary = |center: Point3, radius: f64| {
+        Rc::new(Sphere::new(center, radius, lambertian(0.0, 0.0, 0.0)))
+    };
+    world.add(Rc::new(ConstantMedium::new(
+        boundary(Point3::new(-2.5, 1.0, 0.0), 1.0),
+        2.0,
+        Color::new(0.05, 0.05, 0.05),
+    )));
+
+    let center = Point3::new(0.0, 1.0, 0.0);
+    world.add(Rc::new(Sphere::new(
+        center,
+        1.0,
+        Rc::new(Dielectric::new(1.5)),
+    )));
+    world.add(Rc::new(ConstantMedium::new(
+        boundary(center, 0.8),
+        1.0,
+        Color::new(0.9, 0.9, 0.9),
+    )));
+
+    let squashed = Transform::new(
+        boundary(Point3::default(), 1.0),
+        Mat4::translate(&Vec3::new(2.5, 0.6, 0.0)) * Mat4::scale(&Vec3::new(1.2, 0.6, 1.2)),
+    );
+    world.add(Rc::new(ConstantMedium::new(
+        Rc::new(squashed),
+        0.8,
+        Color::new(0.95, 0.95, 0.95),
+    )));
+
+    Scene {
+        world,
+        lights: Rc::new(LightList::default()),
+        camera: Camera::new(
+            16.0 / 9.0,
+            600,
+            200,
+            50,
+            30.0,
+            Point3::new(0.0, 3.0, 10.0),
+            Point3::new(0.0, 0.8, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            10.0,
+        ),
+    }
+}
+
+// cumulus-like blob of overlapping spheres, `n` voxels wide, whose density
+// fades out towards their surfaces
+fn puff(n: usize) -> DensityGrid {
+    let lobes = [
+        (Point3::new(0.5, 0.4, 0.5), 0.3),
+        (Point3::new(0.28, 0.32, 0.45), 0.2),
+        (Point3::new(0.72, 0.32, 0.52), 0.22),
+        (Point3::new(0.55, 0.58, 0.42), 0.2),
+    ];
+    let mut data = Vec::with_capacity(n * n * n);
+    for z in 0..n {
+        for y in 0..n {
+            for x in 0..n {
+                let p = Point3::new(x as f64 + 0.5, y as f64 + 0.5, z as f64 + 0.5) / n as f64;
+                let density = lobes
+                    .iter()
+                    .map(|(center, radius)| (1.0 - (p - *center).len() / radius).clamp(0.0, 1.0))
+                    .fold(0.0, f64::max);
+                data.push(density * density * (3.0 - 2.0 * density));
+            }
+        }
+    }
+    DensityGrid::new([n; 3], data)
+}
+
+// two clouds in a thin rayleigh haze, isotropic on the left and strongly
+// forward scattering on the right. they are read from the voxel grid
+// `asset` or built procedurally
+fn cloud(asset: Option<&str>) -> io::Result<Scene> {
+    let grid = || match asset {
+        Some(path) => DensityGrid::load(path),
+        None => Ok(puff(64)),
+    };
+
+    let mut world = HittableList::default();
+    world.add(ground(lambertian(0.4, 0.45, 0.3)));
+    world.add(Rc::new(GridMedium::new(
+        grid()?,
+        Point3::new(-7.0, 1.0, -3.0),
+        Point3::new(-1.0, 7.0, 3.0),
+        4.0,
+        Color::new(0.99, 0.99, 0.99),
+    )));
+    world.add(Rc::new(GridMedium::with_material(
+        grid()?,
+        Point3::new(1.0, 1.0, -3.0),
+        Point3::new(7.0, 7.0, 3.0),
+        4.0,
+        Rc::new(Volumetric::new(
+            Color::new(0.99, 0.99, 0.99),
+            Rc::new(HenyeyGreenstein::new(0.8)),
+        )),
+    )));
+    world.add(Rc::new(ConstantMedium::with_material(
+        Rc::new(Sphere::new(
+            Point3::default(),
+            100.0,
+            lambertian(0.0, 0.0, 0.0),
+        )),
+        0.005,
+        Rc::new(Volumetric::new(
+            Color::new(1.0, 1.0, 1.0),
+            Rc::new(Rayleigh),
+        )),
+    )));
+
+    Ok(Scene {
+        world,
+        lights: Rc::new(LightList::default()),
+        camera: Camera::new(
+            16.0 / 9.0,
+            600,
+            100,
+            50,
+            50.0,
+            Point3::new(0.0, 3.0, 16.0),
+            Point3::new(0.0, 4.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            16.0,
+        ),
+    })
+}
+
+// rows of spheres, one kind of material per row
+fn materials() -> Scene {
+    let mut world = HittableList::default();
+    world.add(checkered_floor(60));
+
+    let aluminium = (
+        Color::new(1.657, 0.880, 0.521),
+        Color::new(9.224, 6.270, 4.837),
+    );
+    let rows: Vec<Vec<Rc<dyn Material>>> = vec![
+        vec![
+            Rc::new(Conductor::gold(0.1)),
+            Rc::new(Conductor::copper(0.3)),
+            Rc::new(Conductor::aluminium(0.0)),
+            Rc::new(Conductor::silver(0.2)),
+            Rc::new(Conductor::anisotropic(aluminium.0, aluminium.1, 0.05, 0.4)),
+        ],
+        vec![
+            Rc::new(RoughDielectric::new(1.5, 0.15).with_absorption(Color::new(0.05, 0.2, 0.8))),
+            Rc::new(RoughDielectric::new(1.5, 0.4)),
+            Rc::new(Dielectric::new(1.5).with_transmission_color(Color::new(0.2, 0.7, 0.3), 1.0)),
+            // a soap bubble
+            Rc::new(ThinDielectric::new(1.0).with_thin_film(380.0, 1.33)),
+            Rc::new(
+                Coated::new(lambertian(0.6, 0.05, 0.05), 1.5).with_tint(Color::new(0.9, 0.6, 0.2)),
+            ),
+            Rc::new(Mix::new(
+                lambertian(0.1, 0.2, 0.5),
+                Rc::new(Conductor::new(aluminium.0, aluminium.1, 0.2)),
+                0.3,
+            )),
+        ],
+        vec![
+            Rc::new(OrenNayar::new(Color::new(0.7, 0.35, 0.2), 30.0)),
+            Rc::new(
+                Principled::new(Color::new(0.8, 0.5, 0.2))
+                    .with_metallic(1.0)
+                    .with_roughness(0.3),
+            ),
+            Rc::new(Principled::new(Color::new(0.2, 0.1, 0.4)).with_sheen(1.0, 0.5)),
+            Rc::new(
+                Principled::new(Color::new(0.05, 0.2, 0.05))
+                    .with_specular(0.3)
+                    .with_clearcoat(1.0, 0.9),
+            ),
+            Rc::new(Principled::new(Color::new(0.9, 0.9, 0.95)).with_transmission(1.0, 1.45)),
+        ],
+        vec![
+            // skin, marble, jade and wax
+            Rc::new(Subsurface::new(
+                Color::new(0.97, 0.9, 0.85),
+                Color::new(0.3, 0.12, 0.06),
+                1.4,
+            )),
+            Rc::new(
+                Subsurface::new(
+                    Color::new(0.999, 0.998, 0.995),
+                    Color::new(0.2, 0.2, 0.25),
+                    1.5,
+                )
+                .with_phase(Rc::new(HenyeyGreenstein::new(0.3))),
+            ),
+            Rc::new(Subsurface::new(
+                Color::new(0.9, 0.99, 0.93),
+                Color::new(0.4, 0.8, 0.5),
+                1.6,
+            )),
+            Rc::new(Subsurface::new(
+                Color::new(0.99, 0.97, 0.9),
+                Color::new(0.5, 0.4, 0.25),
+                1.45,
+            )),
+        ],
+    ];
+    let middle = (rows.len() - 1) as f64 / 2.0;
+    for (row, mats) in rows.into_iter().enumerate() {
+        let columns = mats.len();
+        for (column, mat) in mats.into_iter().enumerate() {
+            let center = Point3::new(
+                2.2 * (column as f64 - (columns - 1) as f64 / 2.0),
+                0.9,
+                2.2 * (row as f64 - middle),
+            );
+            world.add(Rc::new(Sphere::new(center, 0.9, mat)));
+        }
+    }
+
+    // a bright key lamp and a dim fill, each picked by its power
+    let key = Rc::new(SphereLight::new(
+        Point3::new(-8.0, 10.0, 6.0),
+        1.0,
+        Color::new(40.0, 36.0, 30.0),
+    ));
+    let fill = Rc::new(SphereLight::new(
+        Point3::new(10.0, 5.0, 4.0),
+        0.6,
+        Color::new(8.0, 10.0, 14.0),
+    ));
+    world.add(key.clone());
+    world.add(fill.clone());
+
+    Scene {
+        world,
+        lights: Rc::new(PowerLightSampler::new(vec![key, fill])),
+        camera: Camera::new(
+            16.0 / 9.0,
+            800,
+            200,
+            50,
+            30.0,
+            Point3::new(0.0, 7.0, 15.0),
+            Point3::new(0.0, 0.5, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            15.0,
+        ),
+    }
+}
+
+// glasses of increasing dispersion in front of a checkerboard, rendered
+// spectrally so their edges fringe with color
+fn dispersion() -> Scene {
+    let mut world = HittableList::default();
+    world.add(checkered_floor(40));
+
+    let glasses = [
+        Ior::fused_silica(),
+        Ior::bk7(),
+        // dense flint
+        Ior::Cauchy {
+            a: 1.728,
+            b: 0.01342,
+        },
+        Ior::diamond(),
+    ];
+    for (i, ior) in glasses.into_iter().enumerate() {
+        world.add(Rc::new(Sphere::new(
+            Point3::new(2.2 * (i as f64 - 1.5), 1.0, 0.0),
+            1.0,
+            Rc::new(Dielectric::from_ior(ior)),
+        )));
+    }
+
+    Scene {
+        world,
+        lights: Rc::new(LightList::default()),
+        camera: Camera::new(
+            16.0 / 9.0,
+            600,
+            200,
+            50,
+            35.0,
+            Point3::new(0.0, 2.5, 9.0),
+            Point3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            9.0,
+        )
+        .with_spectral(true),
+    }
+}
+
+// opaque sheet with a grid of round holes, `holes` across
+fn perforated(holes: usize) -> Image {
+    let size = 16 * holes;
+    let data = (0..size * size)
+        .map(|i| {
+            let x = (i % size % 16) as f64 - 7.5;
+            let y = (i / size % 16) as f64 - 7.5;
+            if x * x + y * y < 36.0 {
+                Color::default()
+            } else {
+                Color::new(1.0, 1.0, 1.0)
+            }
+        })
+        .collect();
+    Image::new(size, size, data)
+}
+
+// tangent space normals of ripples running across u, `waves` of them over
+// the image, encoded as rgb = (n + 1) / 2
+fn ripples(size: usize, waves: f64) -> Image {
+    let data = (0..size * size)
+        .map(|i| {
+            let u = (i % size) as f64 / size as f64;
+            let slope = 0.5 * (2.0 * PI * waves * u).cos();
+            let n = Vec3::new(-slope, 0.0, 1.0).unit_vector();
+            0.5 * (n + Color::new(1.0, 1.0, 1.0))
+        })
+        .collect();
+    Image::new(size, size, data)
+}
+
+// a textured wall of two triangles, normal and bump mapped spheres, and
+// alpha masked cutouts. `asset` is a ppm image blending the wall's two
+// materials instead of the checkerboard
+fn textures(asset: Option<&str>) -> io::Result<Scene> {
+    let blend = match asset {
+        Some(path) => Image::load(path)?,
+        None => checker(8),
+    };
+
+    let mut world = HittableList::default();
+    world.add(floor(40.0, lambertian(0.5, 0.5, 0.5)));
+
+    let wall: Rc<dyn Material> = Rc::new(Mix::textured(
+        lambertian(0.8, 0.8, 0.7),
+        lambertian(0.6, 0.1, 0.1),
+        Rc::new(ImageTexture::new(blend)),
+    ));
+    let (a, b, c, d) = (
+        Point3::new(-6.0, 0.0, -3.0),
+        Point3::new(6.0, 0.0, -3.0),
+        Point3::new(6.0, 6.0, -3.0),
+        Point3::new(-6.0, 6.0, -3.0),
+    );
+    world.add(Rc::new(Triangle::new(a, b, c, wall.clone()).with_uvs(
+        [0.0, 0.0],
+        [1.0, 0.0],
+        [1.0, 1.0],
+    )));
+    world.add(Rc::new(Triangle::new(a, c, d, wall).with_uvs(
+        [0.0, 0.0],
+        [1.0, 1.0],
+        [0.0, 1.0],
+    )));
+
+    let rippled = NormalMap::new(
+        lambertian(0.2, 0.4, 0.7),
+        Rc::new(ImageTexture::linear(ripples(256, 12.0))),
+    );
+    world.add(Rc::new(Sphere::new(
+        Point3::new(-2.0, 1.0, 0.0),
+        1.0,
+        Rc::new(rippled),
+    )));
+    let hammered = NormalMap::bump(
+        Rc::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.05)),
+        Rc::new(ImageTexture::linear(checker(16))),
+        0.01,
+    );
+    world.add(Rc::new(Sphere::new(
+        Point3::new(1.0, 1.0, 0.0),
+        1.0,
+        Rc::new(hammered),
+    )));
+
+    // a perforated screen and a half transparent ghost
+    let screen = AlphaMask::new(
+        lambertian(0.2, 0.5, 0.2),
+        Rc::new(ImageTexture::linear(perforated(8))),
+    )
+    .with_cutoff(0.5);
+    world.add(Rc::new(Quad::new(
+        Point3::new(2.8, 0.0, 1.5),
+        Vec3::new(2.5, 0.0, -2.5),
+        Vec3::new(0.0, 2.5, 0.0),
+        Rc::new(screen),
+    )));
+    let ghost = AlphaMask::new(
+        lambertian(0.9, 0.9, 0.9),
+        Rc::new(SolidColor::new(Color::new(0.5, 0.5, 0.5))),
+    );
+    world.add(Rc::new(Sphere::new(
+        Point3::new(-4.5, 1.0, -1.0),
+        1.0,
+        Rc::new(ghost),
+    )));
+
+    Ok(Scene {
+        world,
+        lights: Rc::new(LightList::default()),
+        camera: Camera::new(
+            16.0 / 9.0,
+            600,
+            100,
+            50,
+            40.0,
+            Point3::new(0.0, 3.0, 10.0),
+            Point3::new(0.0, 1.5, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            10.0,
+        ),
+    })
+}
+
+// three figures, each lit mostly by one kind of light: a warm point light,
+// a blue spot light and a low sun with soft shadows
+fn lights() -> Scene {
+    let mut world = HittableList::default();
+    world.add(ground(lambertian(0.5, 0.5, 0.5)));
+
+    let figure = figure();
+    for x in [-4.0, 0.0, 4.0] {
+        world.add(Rc::new(Transform::new(
+            figure.clone(),
+            Mat4::translate(&Vec3::new(x, 0.0, 0.0)),
+        )));
+    }
+
+    let mut lights = LightList::new(Rc::new(PointLight::new(
+        Point3::new(-4.0, 4.5, 2.0),
+        Color::new(120.0, 70.0, 25.0),
+    )));
+    lights.add(Rc::new(SpotLight::new(
+        Point3::new(0.0, 7.0, 3.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Color::new(100.0, 200.0, 500.0),
+        20.0,
+        12.0,
+    )));
+    lights.add(Rc::new(
+        DirectionalLight::new(Vec3::new(1.0, 0.6, 0.3), Color::new(4.0, 3.6, 3.0))
+            .with_angular_diameter(5.0),
+    ));
+
+    Scene {
+        world,
+        lights: Rc::new(lights),
+        camera: Camera::new(
+            16.0 / 9.0,
+            600,
+            100,
+            50,
+            35.0,
+            Point3::new(0.0, 5.0, 14.0),
+            Point3::new(0.0, 1.2, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            14.0,
+        ),
+    }
+}
+
+// the sun setting, 2 degrees above the horizon
+fn dusk() -> PreethamSky {
+    PreethamSky::new(2.0, 200.0, 3.0)
+}
+
+// late afternoon daylight, the sun 15 degrees above the horizon
+fn afternoon() -> PreethamSky {
+    PreethamSky::new(15.0, 120.0, 3.0)
+}
+
+// a row of figures and a glass ball in late afternoon daylight, the sun
+// low behind the camera's left shoulder
+fn sky() -> Scene {
+    let mut world = HittableList::default();
+    world.add(ground(lambertian(0.5, 0.5, 0.5)));
+
+    let figure = figure();
+    for x in [-6.0, -2.0, 2.0, 6.0] {
+        world.add(Rc::new(Transform::new(
+            figure.clone(),
+            Mat4::translate(&Vec3::new(x, 0.0, -0.5 * x.abs())),
+        )));
+    }
+    world.add(Rc::new(Sphere::new(
+        Point3::new(0.0, 1.0, 3.0),
+        1.0,
+        Rc::new(Dielectric::new(1.5)),
+    )));
+
+    let sky = afternoon();
+    Scene {
+        world,
+        lights: Rc::new(LightList::new(Rc::new(sky.sun()))),
+        camera: Camera::new(
+            16.0 / 9.0,
+            600,
+            100,
+            50,
+            40.0,
+            Point3::new(0.0, 3.0, 14.0),
+            Point3::new(0.0, 2.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            14.0,
+        )
+        .with_sky(Rc::new(sky)),
+    }
+}
+
+// the random spheres at dusk, lit by a field of small glowing spheres and
+// the setting sun, picked by a light bvh, and by a large panel overhead that
+// only scattered rays find
+fn many_lights(rng: &mut PCG32RNG) -> Scene {
+    let mut world = random_world(rng, false);
+
+    let sky = dusk();
+    let mut lamps: Vec<Rc<dyn Light>> = vec![Rc::new(sky.sun())];
+    for a in -10..10 {
+        for b in -10..10 {
+            let center = Point3::new(
+                a as f64 + 0.9 * rng.random_f64(),
+                0.5 + 1.5 * rng.random_f64(),
+                b as f64 + 0.9 * rng.random_f64(),
+            );
+            let emission = Color::random_bounded(rng, 1.0, 20.0);
+            let lamp = Rc::new(SphereLight::new(center, 0.05, emission));
+            world.add(lamp.clone());
+            lamps.push(lamp);
+        }
+    }
+    world.add(Rc::new(Quad::new(
+        Point3::new(-3.0, 6.0, -3.0),
+        Vec3::new(6.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 6.0),
+        Rc::new(DiffuseLight::new(Color::new(0.6, 0.6, 0.8))),
+    )));
+
+    Scene {
+        world,
+        lights: Rc::new(LightBvh::new(lamps)),
+        camera: Camera::new(
+            16.0 / 9.0,
+            600,
+            100,
+            50,
+            20.0,
+            Point3::new(13.0, 2.0, 3.0),
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            10.0,
+        )
+        .with_sky(Rc::new(sky)),
+    }
+}
+
+// lm-63 text of a downlight, quadrant symmetric with a peak of 1500 cd,
+// wide along its 0-180 degree plane and narrow along 90-270
+fn downlight_ies() -> String {
+    let vertical: Vec<f64> = (0..=18).map(|i| 5.0 * i as f64).collect();
+    let plane = |n: i32| {
+        vertical
+            .iter()
+            .map(|&v| format!("{:.1}", 1500.0 * util::deg_to_rad(v).cos().powi(n)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let angles = vertical.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    format!(
+        "IESNA:LM-63-2002\nTILT=NONE\n1 2000 1 19 2 1 2 0.1 0.1 0\n1 1 20\n{}\n0 90\n{}\n{}\n",
+        angles.join(" "),
+        plane(2),
+        plane(10),
+    )
+}
+
+// a closed room lit only by a row of downlights close to its back wall, at
+// their measured brightness. the middle one is turned to wash the wall with
+// its narrow plane
+fn photometric(asset: Option<&str>) -> io::Result<Scene> {
+    let profile = Rc::new(match asset {
+        Some(path) => IesProfile::load(path)?,
+        None => IesProfile::parse(&downlight_ies())?,
+    });
+
+    let mut world = HittableList::default();
+    let (width, height, depth) = (9.0, 3.0, 8.0);
+    let (x, y, z) = (
+        Vec3::new(width, 0.0, 0.0),
+        Vec3::new(0.0, height, 0.0),
+        Vec3::new(0.0, 0.0, depth),
+    );
+    // white walls inside a black shell, which catches the rays that slip
+    // out through the seams where the walls meet
+    for (margin, mat) in [
+        (0.0, lambertian(0.8, 0.8, 0.8)),
+        (0.5, lambertian(0.0, 0.0, 0.0)),
+    ] {
+        let corner = Point3::new(-width / 2.0 - margin, -margin, -depth / 2.0 - margin);
+        let (x, y, z) = (
+            x + Vec3::new(2.0 * margin, 0.0, 0.0),
+            y + Vec3::new(0.0, 2.0 * margin, 0.0),
+            z + Vec3::new(0.0, 0.0, 2.0 * margin),
+        );
+        for (q, u, v) in [
+            (corner, x, z),
+            (corner + y, x, z),
+            (corner, x, y),
+            (corner + z, x, y),
+            (corner, z, y),
+            (corner + x, z, y),
+        ] {
+            world.add(Rc::new(Quad::new(q, u, v, mat.clone())));
+        }
+    }
+    world.add(Rc::new(Transform::new(
+        figure(),
+        Mat4::translate(&Vec3::new(-1.5, 0.0, -1.5)),
+    )));
+    world.add(Rc::new(Sphere::new(
+        Point3::new(1.5, 0.7, -2.0),
+        0.7,
+        Rc::new(Conductor::copper(0.2)),
+    )));
+
+    let mut lights = LightList::default();
+    for (i, lamp_x) in [-3.0, 0.0, 3.0].into_iter().enumerate() {
+        let position = Point3::new(lamp_x, height - 0.05, -depth / 2.0 + 0.6);
+        let light = PhotometricLight::from_candela(
+            position,
+            position - y,
+            x,
+            profile.clone(),
+            Color::new(1.0, 0.85, 0.7),
+            1.0,
+        );
+        let light = if i == 1 {
+            light.with_rotation(90.0)
+        } else {
+            light
+        };
+        lights.add(Rc::new(light));
+    }
+
+    Ok(Scene {
+        world,
+        lights: Rc::new(lights),
+        camera: Camera::new(
+            16.0 / 9.0,
+            600,
+            200,
+            50,
+            70.0,
+            Point3::new(0.0, 1.6, depth / 2.0 - 0.5),
+            Point3::new(0.0, 1.3, -depth / 2.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            7.0,
+        )
+        .with_exposure(7.0),
+    })
+}
+
+// the ring of "instances" seen from straight above its middle through a
+// 180 degree fisheye
+fn fisheye() -> Scene {
+    Scene {
+        camera: Camera::new(
+            1.0,
+            400,
+            100,
+            50,
+            90.0,
+            Point3::new(0.0, 4.5, 0.0),
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            0.0,
+            4.5,
+        )
+        .with_projection(Projection::Fisheye { fov: 180.0 }),
+        ..instances()
+    }
+}
+
+// the ring of "instances" in an isometric view, without perspective
+fn orthographic() -> Scene {
+    Scene {
+        camera: Camera::new(
+            16.0 / 9.0,
+            600,
+            100,
+            50,
+            90.0,
+            Point3::new(20.0, 20.0, 20.0),
+            Point3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            35.0,
+        )
+        .with_projection(Projection::Orthographic { height: 11.0 }),
+        ..instances()
+    }
+}
+
+// all around the daylight "sky" scene, from between its figures and its
+// glass ball
+fn panorama() -> Scene {
+    let scene = sky();
+    Scene {
+        camera: Camera::new(
+            2.0,
+            800,
+            100,
+            50,
+            90.0,
+            Point3::new(0.0, 1.8, 1.0),
+            Point3::new(0.0, 1.8, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            1.0,
+        )
+        .with_sky(Rc::new(afternoon()))
+        .with_projection(Projection::Equirectangular),
+        ..scene
+    }
+}
+
+// the "materials" spheres for a side by side stereo viewer, the eyes a
+// little wider apart than a person's for the scale of the scene
+fn stereo() -> Scene {
+    let scene = materials();
+    Scene {
+        camera: Camera::new(
+            16.0 / 9.0,
+            400,
+            100,
+            50,
+            30.0,
+            Point3::new(0.0, 7.0, 15.0),
+            Point3::new(0.0, 0.5, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            15.0,
+        )
+        .with_stereo(StereoMode::Parallel, 0.5, StereoLayout::SideBySide),
+        ..scene
+    }
+}
+
+// the ring of "instances" with the eyes turned in to meet at its middle
+// figure, one above the other
+fn toe_in() -> Scene {
+    let lookfrom = Point3::new(0.0, 6.0, 12.0);
+    let lookat = Point3::new(0.0, 1.0, 0.0);
+    Scene {
+        camera: Camera::new(
+            16.0 / 9.0,
+            600,
+            100,
+            50,
+            40.0,
+            lookfrom,
+            lookat,
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            (lookfrom - lookat).len(),
+        )
+        .with_stereo(StereoMode::ToeIn, 0.4, StereoLayout::TopBottom),
+        ..instances()
+    }
+}
+
+// "panorama" in omni-directional stereo for a vr headset, the left eye's
+// view above the right's
+fn stereo_panorama() -> Scene {
+    let scene = sky();
+    Scene {
+        camera: Camera::new(
+            2.0,
+            800,
+            100,
+            50,
+            90.0,
+            Point3::new(0.0, 1.8, 1.0),
+            Point3::new(0.0, 1.8, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            1.0,
+        )
+        .with_sky(Rc::new(afternoon()))
+        .with_projection(Projection::Equirectangular)
+        .with_stereo(StereoMode::OmniDirectional, 0.064, StereoLayout::TopBottom),
+        ..scene
+    }
+}
+
+// a figure in focus at dusk in front of a field of small lamps, which blur
+// into the shape of the aperture: a six bladed diaphragm, or the grayscale mask in
+// `asset`
+fn bokeh(asset: Option<&str>, rng: &mut PCG32RNG) -> io::Result<Scene> {
+    let aperture = match asset {
+        Some(path) => Aperture::image(&Image::load(path)?)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "aperture mask is black"))?,
+        None => Aperture::polygon(6, 15.0),
+    };
+
+    let mut world = HittableList::default();
+    world.add(ground(lambertian(0.3, 0.3, 0.3)));
+    world.add(figure());
+
+    let mut lights = LightList::default();
+    for _ in 0..60 {
+        let center = Point3::new(
+            20.0 * rng.random_f64() - 10.0,
+            0.5 + 5.0 * rng.random_f64(),
+            -15.0 - 15.0 * rng.random_f64(),
+        );
+        let lamp = Rc::new(SphereLight::new(
+            center,
+            0.08,
+            Color::random_bounded(rng, 20.0, 60.0),
+        ));
+        world.add(lamp.clone());
+        lights.add(lamp);
+    }
+
+    // focused on the figure's metal ball, a little above the image center
+    let camera = Camera::new(
+        16.0 / 9.0,
+        600,
+        200,
+        50,
+        30.0,
+        Point3::new(0.0, 2.0, 8.0),
+        Point3::new(0.0, 1.5, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        3.0,
+        8.0,
+    )
+    .with_sky(Rc::new(dusk()))
+    .with_aperture(aperture)
+    .focus_on_pixel(&world, 300, 105, rng);
+    Ok(Scene {
+        world,
+        lights: Rc::new(lights),
+        camera,
+    })
+}
+
+// the "materials" spheres through a 50 mm double gauss lens on a full frame
+// sensor, stopped down a little and focused on the principled row
+fn lens(asset: Option<&str>) -> io::Result<Scene> {
+    let lens = match asset {
+        Some(path) => LensSystem::load(path, 43.3)?,
+        None => LensSystem::parse(include_str!("../assets/lenses/dgauss.50mm.dat"), 43.3)?,
+    };
+    let lookfrom = Point3::new(0.0, 3.0, 12.0);
+    let lookat = Point3::new(0.0, 0.9, 1.1);
+
+    let scene = materials();
+    Ok(Scene {
+        camera: Camera::new(
+            16.0 / 9.0,
+            600,
+            200,
+            50,
+            40.0,
+            lookfrom,
+            lookat,
+            Vec3::new(0.0, 1.0, 0.0),
+            0.0,
+            (lookfrom - lookat).len(),
+        )
+        .with_lens(lens.with_aperture_diameter(12.0).with_units(1000.0))?,
+        ..scene
+    })
+}
+
+// the random spheres seen from high up with the lens tilted against the
+// ground, so only a thin band stays sharp and they look like a miniature.
+// the view is shifted up to show more of the far side without turning the
+// camera
+fn tilt_shift(rng: &mut PCG32RNG) -> Scene {
+    let world = random_world(rng, false);
+    let camera = Camera::new(
+        16.0 / 9.0,
+        600,
+        100,
+        50,
+        35.0,
+        Point3::new(12.0, 9.0, 6.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        4.0,
+        10.0,
+    )
+    .with_tilt(-12.0, 0.0)
+    .with_shift(0.0, 0.15)
+    .focus_on_lookat(&world, rng);
+    Scene {
+        world,
+        lights: Rc::new(LightList::default()),
+        camera,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_scene_loads() {
+        let mut rng = PCG32RNG::default();
+        for name in NAMES {
+            if let Err(e) = load(name, None, &mut rng) {
+                panic!("{name}: {e}");
+            }
+        }
+        let err = load("teapot", None, &mut rng).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn missing_assets_are_reported() {
+        let mut rng = PCG32RNG::default();
+        for name in ["cloud", "textures", "photometric", "bokeh", "lens"] {
+            let err = load(name, Some("/nonexistent"), &mut rng).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::NotFound, "{name}");
+        }
+    }
+}
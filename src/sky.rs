@@ -0,0 +1,245 @@
+use std::f64::consts::PI;
+
+use crate::{color::Color, light::directional::DirectionalLight, spectrum, util, vec3::Vec3};
+
+// radiance arriving from infinitely far away, for rays that leave the scene
+pub trait Sky {
+    // radiance from unit `direction`, excluding anything sampled as a light
+    fn radiance(&self, direction: &Vec3) -> Color;
+
+    // radiance of the parts also sampled as lights (the sun disk). only rays
+    // that could not have found them by next-event estimation see these
+    fn emitted(&self, _direction: &Vec3) -> Color {
+        Color::default()
+    }
+}
+
+// white to light blue blend over the elevation, the original backdrop
+pub struct Gradient;
+
+impl Sky for Gradient {
+    fn radiance(&self, direction: &Vec3) -> Color {
+        let a = 0.5 * (direction.y() + 1.0);
+        util::lerp(Color::new(1.0, 1.0, 1.0), Color::new(0.5, 0.7, 1.0), a)
+    }
+}
+
+// maps photometric quantities (kcd/m^2, klux) to scene radiance, so that a
+// white surface under a clear noon sky ends up close to 1. photometric
+// lights use it too, to sit in the same exposure
+pub const PHOTOMETRIC_SCALE: f64 = 1.0 / 25.0;
+
+// illuminance of the sun outside the atmosphere, in klux
+const SOLAR_ILLUMINANCE: f64 = 128.0;
+
+// angular diameter of the sun, in degrees
+const SUN_ANGULAR_DIAMETER: f64 = 0.53;
+
+// wavelengths in micrometers standing in for the r, g and b channels of the
+// sun's spectral attenuation
+const RGB_WAVELENGTHS: [f64; 3] = [0.630, 0.532, 0.465];
+
+// coefficients of the perez sky luminance distribution
+struct Perez {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+}
+
+impl Perez {
+    // relative luminance at zenith angle theta and angle gamma to the sun
+    fn f(&self, cos_theta: f64, gamma: f64) -> f64 {
+        (1.0 + self.a * (self.b / cos_theta).exp())
+            * (1.0 + self.c * (self.d * gamma).exp() + self.e * gamma.cos().powi(2))
+    }
+}
+
+// analytic daylight model of preetham, shirley and smits (1999), driven by
+// the position of the sun and the atmospheric turbidity (2 is very clear,
+// 10 hazy). y is up
+pub struct PreethamSky {
+    sun_direction: Vec3,
+    turbidity: f64,
+    theta_sun: f64,
+    // perez distributions and zenith values for Y, x and y
+    perez: [Perez; 3],
+    zenith: [f64; 3],
+}
+
+impl PreethamSky {
+    // `elevation` above the horizon and `azimuth` from +x towards +z, in
+    // degrees. the model only holds for the sun above the horizon
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> Self {
+        let elevation = util::deg_to_rad(elevation.clamp(0.0, 90.0));
+        let azimuth = util::deg_to_rad(azimuth);
+        let sun_direction = Vec3::new(
+            elevation.cos() * azimuth.cos(),
+            elevation.sin(),
+            elevation.cos() * azimuth.sin(),
+        );
+
+        let t = turbidity.max(1.0);
+        let theta_sun = PI / 2.0 - elevation;
+        let perez = [
+            Perez {
+                a: 0.1787 * t - 1.4630,
+                b: -0.3554 * t + 0.4275,
+                c: -0.0227 * t + 5.3251,
+                d: 0.1206 * t - 2.5771,
+                e: -0.0670 * t + 0.3703,
+            },
+            Perez {
+                a: -0.0193 * t - 0.2592,
+                b: -0.0665 * t + 0.0008,
+                c: -0.0004 * t + 0.2125,
+                d: -0.0641 * t - 0.8989,
+                e: -0.0033 * t + 0.0452,
+            },
+            Perez {
+                a: -0.0167 * t - 0.2608,
+                b: -0.0950 * t + 0.0092,
+                c: -0.0079 * t + 0.2102,
+                d: -0.0441 * t - 1.6537,
+                e: -0.0109 * t + 0.0529,
+            },
+        ];
+
+        // zenith luminance in kcd/m^2 and chromaticity
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_sun);
+        let luminance = ((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192).max(0.0);
+        let chromaticity = |m: [[f64; 4]; 3]| {
+            let th = [theta_sun.powi(3), theta_sun.powi(2), theta_sun, 1.0];
+            let tv = [t * t, t, 1.0];
+            (0..3)
+                .map(|i| tv[i] * (0..4).map(|j| m[i][j] * th[j]).sum::<f64>())
+                .sum::<f64>()
+        };
+        let x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let y = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        Self {
+            sun_direction,
+            turbidity: t,
+            theta_sun,
+            perez,
+            zenith: [luminance, x, y],
+        }
+    }
+
+    // sunlight at the ground, after rayleigh and aerosol extinction along
+    // the sun's path through the atmosphere
+    pub fn sun_irradiance(&self) -> Color {
+        let theta_deg = self.theta_sun.to_degrees();
+        let air_mass =
+            1.0 / (self.theta_sun.cos() + 0.15 * (93.885 - theta_deg).max(1e-3).powf(-1.253));
+
+        // angstrom turbidity with wavelength exponent 1.3
+        let beta = (0.04608 * self.turbidity - 0.04586).max(0.0);
+        let [r, g, b] = RGB_WAVELENGTHS.map(|lambda| {
+            let rayleigh = 0.008735 * lambda.powf(-4.08);
+            let aerosol = beta * lambda.powf(-1.3);
+            (-(rayleigh + aerosol) * air_mass).exp()
+        });
+
+        SOLAR_ILLUMINANCE * PHOTOMETRIC_SCALE * Color::new(r, g, b)
+    }
+
+    // directional light for the sun disk, to add to the scene's lights
+    pub fn sun(&self) -> DirectionalLight {
+        DirectionalLight::new(self.sun_direction, self.sun_irradiance())
+            .with_angular_diameter(SUN_ANGULAR_DIAMETER)
+    }
+
+    fn cos_sun_radius() -> f64 {
+        util::deg_to_rad(SUN_ANGULAR_DIAMETER / 2.0).cos()
+    }
+}
+
+impl Sky for PreethamSky {
+    fn radiance(&self, direction: &Vec3) -> Color {
+        // the model is undefined below the horizon, which repeats the
+        // horizon instead
+        let cos_theta = direction.y().max(0.01);
+        let gamma = Vec3::dot(direction, &self.sun_direction)
+            .clamp(-1.0, 1.0)
+            .acos();
+
+        let [luminance, x, y] = [0, 1, 2].map(|i| {
+            self.zenith[i] * self.perez[i].f(cos_theta, gamma)
+                / self.perez[i].f(1.0, self.theta_sun)
+        });
+
+        let xyz = Color::new(x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+        let rgb = spectrum::xyz_to_linear_srgb(&xyz) * PHOTOMETRIC_SCALE;
+        Color::new(rgb.x().max(0.0), rgb.y().max(0.0), rgb.z().max(0.0))
+    }
+
+    fn emitted(&self, direction: &Vec3) -> Color {
+        let cos_radius = Self::cos_sun_radius();
+        if Vec3::dot(direction, &self.sun_direction) < cos_radius {
+            return Color::default();
+        }
+        self.sun_irradiance() / (2.0 * PI * (1.0 - cos_radius))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::luminance;
+
+    #[test]
+    fn zenith_radiance_matches_the_zenith_luminance() {
+        let sky = PreethamSky::new(45.0, 0.0, 3.0);
+        let zenith = sky.radiance(&Vec3::new(0.0, 1.0, 0.0));
+        let expected = sky.zenith[0] * PHOTOMETRIC_SCALE;
+        assert!(expected > 0.0);
+        assert!((luminance(&zenith) - expected).abs() < 0.01 * expected);
+        // a clear sky is blue overhead
+        assert!(zenith.z() > zenith.x());
+    }
+
+    #[test]
+    fn sky_is_brightest_around_the_sun() {
+        let sky = PreethamSky::new(30.0, 90.0, 2.5);
+        let sun = sky.sun_direction;
+        assert!((sun - Vec3::new(0.0, 0.5, 3.0_f64.sqrt() / 2.0)).len() < 1e-12);
+
+        let near = Vec3::new(0.1, sun.y(), sun.z()).unit_vector();
+        let away = Vec3::new(0.0, sun.y(), -sun.z());
+        assert!(luminance(&sky.radiance(&near)) > 2.0 * luminance(&sky.radiance(&away)));
+        // below the horizon repeats the horizon
+        let below = sky.radiance(&Vec3::new(0.0, -0.5, -1.0).unit_vector());
+        assert!(below.x() >= 0.0 && below.x().is_finite());
+    }
+
+    #[test]
+    fn low_sun_is_dimmer_and_redder() {
+        let high = PreethamSky::new(60.0, 0.0, 3.0).sun_irradiance();
+        let low = PreethamSky::new(5.0, 0.0, 3.0).sun_irradiance();
+        assert!(luminance(&low) < luminance(&high));
+        assert!(low.x() / low.z() > high.x() / high.z());
+        // never more than outside the atmosphere
+        assert!(high.x() < SOLAR_ILLUMINANCE * PHOTOMETRIC_SCALE);
+    }
+
+    #[test]
+    fn sun_disk_emits_the_sun_irradiance() {
+        let sky = PreethamSky::new(40.0, 20.0, 2.0);
+        let sun = sky.sun_direction;
+        let solid_angle = 2.0 * PI * (1.0 - PreethamSky::cos_sun_radius());
+        let irradiance = sky.emitted(&sun) * solid_angle;
+        assert!((irradiance - sky.sun_irradiance()).len() < 1e-9);
+        assert_eq!(sky.emitted(&Vec3::new(0.0, 1.0, 0.0)), Color::default());
+    }
+}
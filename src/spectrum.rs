@@ -0,0 +1,224 @@
+use std::sync::OnceLock;
+
+use crate::{color::Color, util::rand::PCG32RNG};
+
+// visible range sampled in spectral mode, in nanometers
+pub const LAMBDA_MIN: f64 = 380.0;
+pub const LAMBDA_MAX: f64 = 780.0;
+
+// hero wavelength sampling (wilkie et al. 2014): one uniformly sampled hero
+// wavelength plus two more rotated by a third of the range. one radiance
+// value per wavelength is carried in the components of a `Color`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wavelengths {
+    lambda: [f64; 3],
+    secondary_terminated: bool,
+}
+
+impl Wavelengths {
+    pub fn sample(rng: &mut PCG32RNG) -> Self {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        let hero = rng.random_f64() * range;
+        let lambda = [0.0, 1.0, 2.0].map(|i| LAMBDA_MIN + (hero + i * range / 3.0) % range);
+        Self {
+            lambda,
+            secondary_terminated: false,
+        }
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.lambda[0]
+    }
+
+    pub fn get(&self, i: usize) -> f64 {
+        self.lambda[i]
+    }
+
+    // keeps only the hero wavelength of throughput `value`, after a
+    // wavelength dependent direction was chosen (e.g. dispersion). the hero
+    // is reweighted once so the estimate stays unbiased
+    pub fn terminate_secondary(&mut self, value: &Color) -> Color {
+        if self.secondary_terminated {
+            return *value;
+        }
+        self.secondary_terminated = true;
+        Color::new(3.0 * value.x(), 0.0, 0.0)
+    }
+}
+
+// smits (1999) rgb to spectrum basis, 10 bins over 380..720 nm
+const SMITS_BINS: usize = 10;
+const SMITS_MIN: f64 = 380.0;
+const SMITS_MAX: f64 = 720.0;
+
+const SMITS_WHITE: [f64; SMITS_BINS] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f64; SMITS_BINS] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f64; SMITS_BINS] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f64; SMITS_BINS] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f64; SMITS_BINS] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f64; SMITS_BINS] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f64; SMITS_BINS] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+// smits upsampling of an rgb value evaluated at a single wavelength. used for
+// both reflectances and emission; the film's white balance maps the flat
+// spectrum of rgb white back to white
+pub fn rgb_to_spectrum(rgb: &Color, lambda: f64) -> f64 {
+    let bin = (((lambda - SMITS_MIN) / (SMITS_MAX - SMITS_MIN) * SMITS_BINS as f64) as isize)
+        .clamp(0, SMITS_BINS as isize - 1) as usize;
+    let (r, g, b) = (rgb.x(), rgb.y(), rgb.z());
+
+    if r <= g && r <= b {
+        let mut v = r * SMITS_WHITE[bin];
+        if g <= b {
+            v += (g - r) * SMITS_CYAN[bin] + (b - g) * SMITS_BLUE[bin];
+        } else {
+            v += (b - r) * SMITS_CYAN[bin] + (g - b) * SMITS_GREEN[bin];
+        }
+        v
+    } else if g <= r && g <= b {
+        let mut v = g * SMITS_WHITE[bin];
+        if r <= b {
+            v += (r - g) * SMITS_MAGENTA[bin] + (b - r) * SMITS_BLUE[bin];
+        } else {
+            v += (b - g) * SMITS_MAGENTA[bin] + (r - b) * SMITS_RED[bin];
+        }
+        v
+    } else {
+        let mut v = b * SMITS_WHITE[bin];
+        if r <= g {
+            v += (r - b) * SMITS_YELLOW[bin] + (g - r) * SMITS_GREEN[bin];
+        } else {
+            v += (g - b) * SMITS_YELLOW[bin] + (r - g) * SMITS_RED[bin];
+        }
+        v
+    }
+}
+
+// `rgb` evaluated at each of the sampled wavelengths
+pub fn upsample(rgb: &Color, lambda: &Wavelengths) -> Color {
+    Color::new(
+        rgb_to_spectrum(rgb, lambda.get(0)),
+        rgb_to_spectrum(rgb, lambda.get(1)),
+        rgb_to_spectrum(rgb, lambda.get(2)),
+    )
+}
+
+// piecewise gaussian used by the cie fit
+fn g(x: f64, mu: f64, sigma1: f64, sigma2: f64) -> f64 {
+    let t = (x - mu) / if x < mu { sigma1 } else { sigma2 };
+    (-0.5 * t * t).exp()
+}
+
+// cie 1931 2-degree color matching functions, multi-lobe fit of
+// wyman, sloan and shirley (2013)
+pub fn cie_xyz(lambda: f64) -> Color {
+    let x = 1.056 * g(lambda, 599.8, 37.9, 31.0) + 0.362 * g(lambda, 442.0, 16.0, 26.7)
+        - 0.065 * g(lambda, 501.1, 20.4, 26.2);
+    let y = 0.821 * g(lambda, 568.8, 46.9, 40.5) + 0.286 * g(lambda, 530.9, 16.3, 31.1);
+    let z = 1.217 * g(lambda, 437.0, 11.8, 36.0) + 0.681 * g(lambda, 459.0, 26.0, 13.8);
+    Color::new(x, y, z)
+}
+
+pub fn xyz_to_linear_srgb(xyz: &Color) -> Color {
+    let (x, y, z) = (xyz.x(), xyz.y(), xyz.z());
+    Color::new(
+        3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+        -0.9692660 * x + 1.8760108 * y + 0.0415560 * z,
+        0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+    )
+}
+
+// per channel scale taking the upsampled rgb white to (1, 1, 1)
+fn white_balance() -> &'static Color {
+    static WHITE_BALANCE: OnceLock<Color> = OnceLock::new();
+    WHITE_BALANCE.get_or_init(|| {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let mut xyz = Color::default();
+        let mut lambda = LAMBDA_MIN;
+        while lambda < LAMBDA_MAX {
+            xyz += rgb_to_spectrum(&white, lambda + 0.5) * cie_xyz(lambda + 0.5);
+            lambda += 1.0;
+        }
+        let rgb = xyz_to_linear_srgb(&xyz);
+        Color::new(1.0 / rgb.x(), 1.0 / rgb.y(), 1.0 / rgb.z())
+    })
+}
+
+// monte carlo estimate of the film response to spectral radiance samples
+// `value` at `lambda`, as linear rgb
+pub fn to_rgb(value: &Color, lambda: &Wavelengths) -> Color {
+    let mut xyz = Color::default();
+    for i in 0..3 {
+        xyz += value[i] * cie_xyz(lambda.get(i));
+    }
+    // uniform wavelength pdf, averaged over the three samples
+    xyz *= (LAMBDA_MAX - LAMBDA_MIN) / 3.0;
+
+    xyz_to_linear_srgb(&xyz) * *white_balance()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wavelengths_are_spread_over_the_range() {
+        let mut rng = PCG32RNG::default();
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        for _ in 0..1000 {
+            let lambda = Wavelengths::sample(&mut rng);
+            for i in 0..3 {
+                assert!((LAMBDA_MIN..LAMBDA_MAX).contains(&lambda.get(i)));
+                let gap = (lambda.get((i + 1) % 3) - lambda.get(i)).rem_euclid(range);
+                assert!((gap - range / 3.0).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn terminating_secondary_wavelengths_keeps_the_estimate() {
+        let mut rng = PCG32RNG::default();
+        let mut lambda = Wavelengths::sample(&mut rng);
+        let value = Color::new(0.5, 0.25, 0.125);
+        assert_eq!(
+            lambda.terminate_secondary(&value),
+            Color::new(1.5, 0.0, 0.0)
+        );
+        // only reweighted once
+        assert_eq!(lambda.terminate_secondary(&value), value);
+    }
+
+    #[test]
+    fn rgb_round_trips_through_the_spectrum() {
+        let mut rng = PCG32RNG::default();
+        for rgb in [
+            Color::new(1.0, 1.0, 1.0),
+            Color::new(0.8, 0.2, 0.1),
+            Color::new(0.1, 0.5, 0.9),
+        ] {
+            let n = 200000;
+            let mut sum = Color::default();
+            for _ in 0..n {
+                let lambda = Wavelengths::sample(&mut rng);
+                sum += to_rgb(&upsample(&rgb, &lambda), &lambda) / n as f64;
+            }
+            for i in 0..3 {
+                assert!((sum[i] - rgb[i]).abs() < 0.1, "{rgb:?} -> {sum:?}");
+            }
+        }
+    }
+}
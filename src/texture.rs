@@ -0,0 +1,81 @@
+use crate::{
+    color::{self, Color},
+    image::Image,
+    vec3::Point3,
+};
+
+pub trait Texture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color;
+}
+
+pub struct SolidColor {
+    albedo: Color,
+}
+
+impl SolidColor {
+    pub fn new(albedo: Color) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
+        self.albedo
+    }
+}
+
+// image mapped over the surface's uv coordinates
+pub struct ImageTexture {
+    image: Image,
+}
+
+impl ImageTexture {
+    // color image, decoded from the gamma the renderer writes
+    pub fn new(image: Image) -> Self {
+        let image = image.map(|c| {
+            Color::new(
+                color::gamma_to_linear(c.x()),
+                color::gamma_to_linear(c.y()),
+                color::gamma_to_linear(c.z()),
+            )
+        });
+        Self { image }
+    }
+
+    // data image (normals, heights, weights) used as is
+    pub fn linear(image: Image) -> Self {
+        Self { image }
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Point3) -> Color {
+        self.image.sample(u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checker() -> Image {
+        // top row black and white, bottom row white and black
+        let (black, white) = (Color::default(), Color::new(0.5, 0.5, 0.5));
+        Image::new(2, 2, vec![black, white, white, black])
+    }
+
+    #[test]
+    fn image_texture_decodes_gamma() {
+        let p = Point3::default();
+        let texture = ImageTexture::new(checker());
+        // pixel centers, with v pointing up
+        assert_eq!(texture.value(0.75, 0.75, &p), Color::new(0.25, 0.25, 0.25));
+        assert_eq!(texture.value(0.25, 0.75, &p), Color::default());
+
+        let data = ImageTexture::linear(checker());
+        assert_eq!(data.value(0.25, 0.25, &p), Color::new(0.5, 0.5, 0.5));
+        // halfway between pixel centers, and repeated outside [0, 1]
+        assert_eq!(data.value(0.5, 0.75, &p), Color::new(0.25, 0.25, 0.25));
+        assert_eq!(data.value(1.25, -0.75, &p), data.value(0.25, 0.25, &p));
+    }
+}
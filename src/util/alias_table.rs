@@ -0,0 +1,107 @@
+use crate::util::rand::PCG32RNG;
+
+// samples indices in proportion to non-negative weights in constant time
+// (walker's alias method, vose's construction)
+pub struct AliasTable {
+    // probability of keeping bin i rather than jumping to its alias
+    threshold: Vec<f64>,
+    alias: Vec<usize>,
+    pmf: Vec<f64>,
+}
+
+impl AliasTable {
+    // None if there is no positive weight
+    pub fn new(weights: &[f64]) -> Option<Self> {
+        let total: f64 = weights.iter().map(|w| w.max(0.0)).sum();
+        if total <= 0.0 || !total.is_finite() {
+            return None;
+        }
+
+        let n = weights.len();
+        let pmf: Vec<f64> = weights.iter().map(|w| w.max(0.0) / total).collect();
+        let mut scaled: Vec<f64> = pmf.iter().map(|p| p * n as f64).collect();
+        let mut threshold = vec![1.0; n];
+        let mut alias: Vec<usize> = (0..n).collect();
+
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..n).partition(|&i| scaled[i] < 1.0);
+        while let (Some(s), Some(&l)) = (small.pop(), large.last()) {
+            threshold[s] = scaled[s];
+            alias[s] = l;
+            scaled[l] -= 1.0 - scaled[s];
+            if scaled[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // whatever is left is 1 up to rounding
+
+        Some(Self {
+            threshold,
+            alias,
+            pmf,
+        })
+    }
+
+    // returns the index and its probability
+    pub fn sample(&self, rng: &mut PCG32RNG) -> (usize, f64) {
+        let n = self.threshold.len();
+        let u = rng.random_f64() * n as f64;
+        let bin = (u as usize).min(n - 1);
+        let i = if u - (bin as f64) < self.threshold[bin] {
+            bin
+        } else {
+            self.alias[bin]
+        };
+        (i, self.pmf[i])
+    }
+
+    pub fn pmf(&self, i: usize) -> f64 {
+        self.pmf[i]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_follow_the_weights() {
+        let weights = [1.0, 0.0, 3.0, 0.5, 2.5, -1.0];
+        let table = AliasTable::new(&weights).unwrap();
+        let mut rng = PCG32RNG::default();
+
+        let n = 100000;
+        let mut counts = [0usize; 6];
+        for _ in 0..n {
+            let (i, pmf) = table.sample(&mut rng);
+            assert_eq!(pmf, table.pmf(i));
+            counts[i] += 1;
+        }
+
+        // negative weights count as zero
+        for (i, &w) in weights.iter().enumerate() {
+            let expected = w.max(0.0) / 7.0;
+            assert!((table.pmf(i) - expected).abs() < 1e-12);
+            let freq = counts[i] as f64 / n as f64;
+            assert!((freq - expected).abs() < 0.01, "{i}: {freq} != {expected}");
+        }
+        assert_eq!(counts[1] + counts[5], 0);
+    }
+
+    #[test]
+    fn single_weight_is_always_picked() {
+        let table = AliasTable::new(&[0.0, 2.0]).unwrap();
+        let mut rng = PCG32RNG::default();
+        for _ in 0..100 {
+            assert_eq!(table.sample(&mut rng), (1, 1.0));
+        }
+    }
+
+    #[test]
+    fn no_positive_weight_has_no_table() {
+        assert!(AliasTable::new(&[]).is_none());
+        assert!(AliasTable::new(&[0.0, -1.0]).is_none());
+        assert!(AliasTable::new(&[1.0, f64::INFINITY]).is_none());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod alias_table;
 pub mod rand;
 use std::{f64::consts::PI, ops};
 
@@ -4,7 +4,7 @@ use crate::util::rand::PCG32RNG;
 
 pub type Point3 = Vec3;
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct Vec3 {
     e: [f64; 3],
 }
@@ -114,6 +114,20 @@ impl Vec3 {
         }
     }
 
+    // cosine weighted direction about +z
+    #[inline]
+    pub fn random_cosine_direction(rng: &mut PCG32RNG) -> Self {
+        let r1 = rng.random_f64();
+        let r2 = rng.random_f64();
+
+        let phi = 2.0 * std::f64::consts::PI * r1;
+        let x = phi.cos() * r2.sqrt();
+        let y = phi.sin() * r2.sqrt();
+        let z = (1.0 - r2).sqrt();
+
+        Self::new(x, y, z)
+    }
+
     #[inline]
     pub fn random_in_unit_disk(rng: &mut PCG32RNG) -> Self {
         loop {
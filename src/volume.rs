@@ -0,0 +1,243 @@
+use std::{fs, io, path::Path};
+
+use crate::vec3::Point3;
+
+// dense grid of density samples covering the unit cube, with voxel centers at
+// ((i + 0.5) / nx, (j + 0.5) / ny, (k + 0.5) / nz)
+//
+// on-disk format: a single ASCII header line `VOL <nx> <ny> <nz>\n` followed
+// by nx * ny * nz little-endian f32 values, x varying fastest, then y, then z.
+// densities must be finite and non-negative
+pub struct DensityGrid {
+    res: [usize; 3],
+    data: Vec<f64>,
+}
+
+impl DensityGrid {
+    pub fn new(res: [usize; 3], data: Vec<f64>) -> Self {
+        assert!(
+            res.iter().all(|&n| n > 0),
+            "density grid resolution must be non-zero"
+        );
+        assert_eq!(
+            data.len(),
+            res[0] * res[1] * res[2],
+            "density grid data does not match its resolution"
+        );
+        Self { res, data }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        Self::parse(&bytes)
+    }
+
+    pub fn parse(bytes: &[u8]) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let header_len = bytes
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| invalid("missing voxel grid header"))?;
+        let header = std::str::from_utf8(&bytes[..header_len])
+            .map_err(|_| invalid("voxel grid header is not valid utf-8"))?;
+
+        let mut fields = header.split_whitespace();
+        if fields.next() != Some("VOL") {
+            return Err(invalid("not a voxel grid file"));
+        }
+        let mut res = [0usize; 3];
+        for n in &mut res {
+            *n = fields
+                .next()
+                .and_then(|f| f.parse().ok())
+                .filter(|&n| n > 0)
+                .ok_or_else(|| invalid("bad voxel grid resolution"))?;
+        }
+
+        let body = &bytes[header_len + 1..];
+        let size = res[0]
+            .checked_mul(res[1])
+            .and_then(|n| n.checked_mul(res[2]))
+            .and_then(|n| n.checked_mul(4))
+            .ok_or_else(|| invalid("voxel grid resolution is too large"))?;
+        if body.len() != size {
+            return Err(invalid("voxel grid data does not match its resolution"));
+        }
+
+        let data: Vec<f64> = body
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]) as f64)
+            .collect();
+        // tracking relies on densities in [0, majorant], negative or nan
+        // voxels would break both the majorants and ratio tracking
+        if !data.iter().all(|d| d.is_finite() && *d >= 0.0) {
+            return Err(invalid(
+                "voxel grid densities must be finite and non-negative",
+            ));
+        }
+
+        Ok(Self::new(res, data))
+    }
+
+    pub fn res(&self) -> [usize; 3] {
+        self.res
+    }
+
+    fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        self.data[(z * self.res[1] + y) * self.res[0] + x]
+    }
+
+    // trilinearly interpolated density at `p` in [0, 1]^3 grid coordinates
+    pub fn density(&self, p: &Point3) -> f64 {
+        let mut i0 = [0usize; 3];
+        let mut i1 = [0usize; 3];
+        let mut f = [0.0; 3];
+
+        for axis in 0..3 {
+            let n = self.res[axis];
+            let g = (p[axis] * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            let lo = g.floor();
+            i0[axis] = lo as usize;
+            i1[axis] = (i0[axis] + 1).min(n - 1);
+            f[axis] = g - lo;
+        }
+
+        let mut d = 0.0;
+        for (dz, z) in [(1.0 - f[2], i0[2]), (f[2], i1[2])] {
+            for (dy, y) in [(1.0 - f[1], i0[1]), (f[1], i1[1])] {
+                for (dx, x) in [(1.0 - f[0], i0[0]), (f[0], i1[0])] {
+                    d += dx * dy * dz * self.voxel(x, y, z);
+                }
+            }
+        }
+        d
+    }
+
+    // largest voxel value in the inclusive index range [lo, hi]
+    fn max_in(&self, lo: [usize; 3], hi: [usize; 3]) -> f64 {
+        let mut m = 0.0f64;
+        for z in lo[2]..=hi[2] {
+            for y in lo[1]..=hi[1] {
+                for x in lo[0]..=hi[0] {
+                    m = m.max(self.voxel(x, y, z));
+                }
+            }
+        }
+        m
+    }
+}
+
+// coarse grid of density upper bounds over blocks of voxels. tracking uses
+// the local bound instead of the global maximum, so sparse regions of the
+// volume are skipped with few tentative collisions
+pub struct MajorantGrid {
+    res: [usize; 3],
+    // voxels per cell along each axis. the last cell of an axis covers fewer
+    // when the grid resolution is not a multiple of it
+    block_size: usize,
+    data: Vec<f64>,
+}
+
+impl MajorantGrid {
+    pub fn new(grid: &DensityGrid, block_size: usize) -> Self {
+        let block_size = block_size.max(1);
+        let res = grid.res.map(|n| n.div_ceil(block_size));
+        let mut data = Vec::with_capacity(res[0] * res[1] * res[2]);
+
+        for z in 0..res[2] {
+            for y in 0..res[1] {
+                for x in 0..res[0] {
+                    // widen by a voxel on each side, as trilinear interpolation
+                    // near a block boundary reads its neighbours
+                    let cell = [x, y, z];
+                    let lo = cell.map(|c| (c * block_size).saturating_sub(1));
+                    let mut hi = [0usize; 3];
+                    for axis in 0..3 {
+                        hi[axis] = ((cell[axis] + 1) * block_size).min(grid.res[axis] - 1);
+                    }
+                    data.push(grid.max_in(lo, hi));
+                }
+            }
+        }
+
+        Self {
+            res,
+            block_size,
+            data,
+        }
+    }
+
+    pub fn res(&self) -> [usize; 3] {
+        self.res
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    pub fn get(&self, cell: [usize; 3]) -> f64 {
+        self.data[(cell[2] * self.res[1] + cell[1]) * self.res[0] + cell[0]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vol_bytes(header: &str, values: &[f32]) -> Vec<u8> {
+        let mut bytes = header.as_bytes().to_vec();
+        for v in values {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn parse_valid_grid() {
+        let values: Vec<f32> = (0..12).map(|i| i as f32).collect();
+        let grid = DensityGrid::parse(&vol_bytes("VOL 2 3 2\n", &values)).unwrap();
+        assert_eq!(grid.res(), [2, 3, 2]);
+        // x varies fastest, then y, then z
+        assert_eq!(grid.voxel(1, 0, 0), 1.0);
+        assert_eq!(grid.voxel(0, 1, 0), 2.0);
+        assert_eq!(grid.voxel(0, 0, 1), 6.0);
+        // voxel centers are interpolated exactly
+        assert_eq!(grid.density(&Point3::new(0.75, 0.5, 0.25)), 3.0);
+    }
+
+    #[test]
+    fn parse_rejects_truncated_body() {
+        let err = DensityGrid::parse(&vol_bytes("VOL 2 2 2\n", &[1.0; 7]))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn parse_rejects_bad_headers() {
+        for header in [
+            "VOL 4294967296 4294967296 4294967296\n",
+            &format!("VOL {} 2 1\n", usize::MAX),
+            "VOL 0 1 1\n",
+            "VOL 1 1\n",
+            "GRID 1 1 1\n",
+            "VOL 1 1 1",
+        ] {
+            let err = DensityGrid::parse(header.as_bytes()).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{header:?}");
+        }
+    }
+
+    #[test]
+    fn parse_rejects_bad_densities() {
+        for bad in [-0.5, f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let err = DensityGrid::parse(&vol_bytes("VOL 2 1 1\n", &[1.0, bad]))
+                .err()
+                .unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{bad}");
+        }
+        // empty space is fine
+        assert!(DensityGrid::parse(&vol_bytes("VOL 2 1 1\n", &[0.0, -0.0])).is_ok());
+    }
+}